@@ -0,0 +1,264 @@
+//! Searches several providers at once and merges their results, for queries that a single engine doesn't find enough images for.
+//!
+//! ```no_run
+//! extern crate tokio;
+//! extern crate image_search;
+//!
+//! use image_search::providers::{Bing, DuckDuckGo, Google, Provider};
+//! use image_search::{aggregate, Arguments};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), image_search::Error> {
+//!     let providers = [Provider::new(Google), Provider::new(Bing), Provider::new(DuckDuckGo)];
+//!     let images = aggregate::search(Arguments::new("axolotl", 100), &providers).await?;
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use crate::client::Session;
+use crate::providers::Provider;
+use crate::{
+    _search, content_hash, fetch_bytes, post_process, strip_tracking, Arguments, Error, Image,
+    ImageList, SearchResult,
+};
+use futures::{future, stream, StreamExt};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The maximum number of images that are fetched at the same time by `dedup_by_content`.
+const MAX_CONCURRENT_FETCHES: usize = 8;
+
+/// Searches each of the providers concurrently with the same arguments, and merges the results up to the limit.
+/// The results are interleaved by rank (the first result of each provider, then the second of each, and so on),
+/// and images that were already found by an earlier provider are skipped, comparing their urls without the scheme, `www.`, or tracking parameters.
+///
+/// Shuffling, `rank_by`, and `prefer_color` are applied once to the merged results rather than to the results of each provider.
+///
+/// The provider set on `args` is ignored. Providers that fail are left out of the results, as long as at least one of them succeeds.
+///
+/// # Errors
+/// This function will return an error if:
+/// * No providers are given
+/// * The query is empty, too long, or contains control characters
+/// * The limit is more than the provider with the smallest pages can return
+/// * Every provider fails, in which case the error of the first is returned
+pub async fn search(args: Arguments, providers: &[Provider]) -> SearchResult<ImageList> {
+    if providers.is_empty() {
+        return Err(Error::InvalidArguments(
+            "aggregate::search needs at least one provider".to_owned(),
+        ));
+    }
+    // Every provider has to be able to reach the limit on its own, so the limit is checked against the smallest pages
+    let smallest = providers
+        .iter()
+        .min_by_key(|Provider(provider)| provider.page_size())
+        .cloned();
+    if let Some(provider) = smallest {
+        Arguments {
+            provider,
+            ..args.clone()
+        }
+        .validate()?;
+    }
+
+    let providers = providers.to_vec();
+    async_std::task::spawn(async move {
+        let session = Arc::new(Session::default());
+        let searches = providers.into_iter().map(|provider| {
+            // The results are ordered after they are merged, so each provider keeps its own order
+            #[cfg_attr(not(feature = "image"), allow(unused_mut))]
+            let mut args = Arguments {
+                provider,
+                shuffle: false,
+                rank_by: None,
+                ..args.clone()
+            };
+            #[cfg(feature = "image")]
+            {
+                args.prefer_color = None;
+            }
+            _search(args, session.clone())
+        });
+        let results = future::join_all(searches).await;
+
+        let mut first_error = None;
+        let mut found = Vec::new();
+        for result in results {
+            match result {
+                Ok(images) => found.push(images.into_inner()),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+        if found.is_empty() {
+            if let Some(err) = first_error {
+                return Err(err);
+            }
+        }
+
+        let merged = merge(found);
+        #[cfg(feature = "image")]
+        let merged = match args.prefer_color {
+            Some(rgb) => crate::color::rank_by_color(merged, rgb, &args).await,
+            None => merged,
+        };
+
+        Ok(ImageList::from(post_process(&args, merged)).seed(args.seed))
+    })
+    .await
+}
+
+/// Removes the images whose contents are the same as an earlier image, such as the same picture hosted on different sites.
+/// Every image is downloaded to compare them, so this is much slower than the url comparison `search` does.
+/// Images that can't be fetched within `timeout` are kept, since they can't be compared.
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate tokio;
+/// extern crate image_search;
+///
+/// use image_search::providers::{Bing, Google, Provider};
+/// use image_search::{aggregate, Arguments};
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), image_search::Error> {
+///     let providers = [Provider::new(Google), Provider::new(Bing)];
+///     let images = aggregate::search(Arguments::new("axolotl", 50), &providers).await?;
+///     let unique = aggregate::dedup_by_content(&images, Duration::from_secs(10)).await;
+///
+///     Ok(())
+/// }
+/// ```
+pub async fn dedup_by_content<D: Into<Option<Duration>>>(
+    images: &[Image],
+    timeout: D,
+) -> ImageList {
+    let (images, timeout) = (images.to_vec(), timeout.into());
+    async_std::task::spawn(async move {
+        let urls: Vec<String> = images.iter().map(|image| image.url.clone()).collect();
+        let hashes: Vec<Option<String>> = stream::iter(urls)
+            .map(|url| async move {
+                fetch_bytes(url, timeout)
+                    .await
+                    .ok()
+                    .map(|buf| content_hash(&buf))
+            })
+            .buffered(MAX_CONCURRENT_FETCHES)
+            .collect()
+            .await;
+
+        let mut seen = HashSet::new();
+        images
+            .into_iter()
+            .zip(hashes)
+            .filter(|(_, hash)| match hash {
+                Some(hash) => seen.insert(hash.clone()),
+                None => true,
+            })
+            .map(|(image, _)| image)
+            .collect()
+    })
+    .await
+}
+
+/// Interleaves the results of each provider by rank, skipping images that have already been found.
+fn merge(results: Vec<Vec<Image>>) -> Vec<Image> {
+    let longest = results.iter().map(Vec::len).max().unwrap_or(0);
+    let mut results: Vec<_> = results.into_iter().map(Vec::into_iter).collect();
+    let mut seen = HashSet::new();
+    let mut merged = Vec::new();
+    for _ in 0..longest {
+        for images in results.iter_mut() {
+            if let Some(image) = images.next() {
+                if seen.insert(dedup_key(&image.url)) {
+                    merged.push(image);
+                }
+            }
+        }
+    }
+
+    merged
+}
+
+/// The url of an image without its scheme, `www.`, or tracking parameters, so that the same image found by different engines compares equal.
+fn dedup_key(url: &str) -> String {
+    let url = strip_tracking(url);
+    let url = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+    url.strip_prefix("www.").unwrap_or(url).to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{PageRequest, SearchProvider};
+    use futures::future::BoxFuture;
+
+    /// A provider that returns a single page of images with the given widths.
+    #[derive(Debug)]
+    struct Fixed {
+        name: &'static str,
+        widths: Vec<i64>,
+        page_size: usize,
+    }
+
+    impl SearchProvider for Fixed {
+        fn page_size(&self) -> usize {
+            self.page_size
+        }
+
+        fn fetch_page<'a>(
+            &'a self,
+            request: PageRequest<'a>,
+        ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+            Box::pin(async move {
+                if request.page() > 0 {
+                    return Ok(Vec::new());
+                }
+                Ok(self
+                    .widths
+                    .iter()
+                    .map(|width| {
+                        let url = format!("https://{}.com/{}.jpg", self.name, width);
+                        Image::new(&url, *width, 1, &url, "")
+                    })
+                    .collect())
+            })
+        }
+    }
+
+    fn fixed(name: &'static str, widths: &[i64], page_size: usize) -> Provider {
+        Provider::new(Fixed {
+            name,
+            widths: widths.to_vec(),
+            page_size,
+        })
+    }
+
+    #[tokio::test]
+    async fn ranks_merged_results() {
+        let providers = [fixed("a", &[1, 2, 3], 10), fixed("b", &[6, 5, 4], 10)];
+        let args = Arguments::new("cats", 4).rank_by(|image| image.width as f64);
+        let widths: Vec<i64> = search(args, &providers)
+            .await
+            .unwrap()
+            .iter()
+            .map(|image| image.width)
+            .collect();
+        assert_eq!(widths, [6, 5, 4, 3]);
+    }
+
+    #[tokio::test]
+    async fn limit_is_checked_against_smallest_pages() {
+        let providers = [fixed("a", &[1], 100), fixed("b", &[2], 1)];
+        let args = Arguments::new("cats", 11);
+        assert!(matches!(
+            search(args, &providers).await,
+            Err(Error::InvalidArguments(_))
+        ));
+    }
+}
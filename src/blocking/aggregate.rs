@@ -0,0 +1,34 @@
+//! Blocking versions of the functions in [`image_search::aggregate`](crate::aggregate).
+//!
+//! ```no_run
+//! extern crate image_search;
+//!
+//! use image_search::providers::{Bing, Google, Provider};
+//! use image_search::{blocking, Arguments};
+//!
+//! fn main() -> Result<(), image_search::Error> {
+//!     let providers = [Provider::new(Google), Provider::new(Bing)];
+//!     let images = blocking::aggregate::search(Arguments::new("axolotl", 100), &providers)?;
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use super::block_on;
+use crate::providers::Provider;
+use crate::{Arguments, Image, ImageList, SearchResult};
+use std::time::Duration;
+
+/// Searches each of the providers with the same arguments, and merges the results up to the limit.
+/// See [`aggregate::search`](crate::aggregate::search) for more information.
+pub fn search(args: Arguments, providers: &[Provider]) -> SearchResult<ImageList> {
+    let providers = providers.to_vec();
+    block_on(async move { crate::aggregate::search(args, &providers).await })
+}
+
+/// Removes the images whose contents are the same as an earlier image, downloading every image to compare them.
+/// See [`aggregate::dedup_by_content`](crate::aggregate::dedup_by_content) for more information.
+pub fn dedup_by_content<D: Into<Option<Duration>>>(images: &[Image], timeout: D) -> ImageList {
+    let (images, timeout) = (images.to_vec(), timeout.into());
+    block_on(async move { crate::aggregate::dedup_by_content(&images, timeout).await })
+}
@@ -0,0 +1,152 @@
+//! A blocking version of [`Client`](crate::Client).
+
+extern crate async_std;
+
+use super::SearchIter;
+use crate::{Arguments, Cassette, DownloadReport, ImageList, SearchResult};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// A blocking client for searching and downloading images, which keeps its connection pool, cookies, rate limit, and default arguments between calls.
+/// Cloning a `Client` is cheap, and all of the clones share the same connections and cookies.
+///
+/// # Example
+/// ```no_run
+/// extern crate image_search;
+///
+/// use image_search::{Arguments, Color};
+/// use image_search::blocking::Client;
+/// use std::time::Duration;
+///
+/// fn main() -> Result<(), image_search::Error> {
+///     let client = Client::new()
+///         .defaults(Arguments::default().color(Color::Gray))
+///         .rate_limit(Duration::from_secs(2));
+///
+///     let cats = client.search("cats", 10)?;
+///     let dogs = client.urls("dogs", 10)?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Client {
+    inner: crate::Client,
+}
+
+impl Client {
+    pub fn new() -> Client {
+        Client {
+            inner: crate::Client::new(),
+        }
+    }
+
+    /// Sets the arguments used for every search made with this client. The query and limit are replaced by the ones passed to each method.
+    pub fn defaults(self, defaults: Arguments) -> Self {
+        Client {
+            inner: self.inner.defaults(defaults),
+        }
+    }
+
+    /// Sets the minimum time between search requests made by this client and all of its clones.
+    pub fn rate_limit<D: Into<Option<Duration>>>(self, interval: D) -> Self {
+        Client {
+            inner: self.inner.rate_limit(interval),
+        }
+    }
+
+    /// Adds cookies to send with every search request, given in the format of a `Cookie` header (`name=value; name2=value2`).
+    /// See [`Client::cookies`](crate::Client::cookies) for more information.
+    pub fn cookies(self, cookies: &str) -> Self {
+        Client {
+            inner: self.inner.cookies(cookies),
+        }
+    }
+
+    /// Sets a file that the cookies Google sets are saved to, so that they can be reused the next time the program runs.
+    /// See [`Client::cookie_file`](crate::Client::cookie_file) for more information.
+    pub fn cookie_file<P: Into<PathBuf>>(self, path: P) -> Self {
+        Client {
+            inner: self.inner.cookie_file(path),
+        }
+    }
+
+    /// Adds a random delay of up to `window` before each search request made by this client and all of its clones, on top of the rate limit.
+    pub fn jitter<D: Into<Option<Duration>>>(self, window: D) -> Self {
+        Client {
+            inner: self.inner.jitter(window),
+        }
+    }
+
+    /// Records the responses to this client's search requests to cassette files, or replays them from the files instead of making any requests.
+    /// See [`Client::cassette`](crate::Client::cassette) for more information.
+    pub fn cassette<C: Into<Option<Cassette>>>(self, cassette: C) -> Self {
+        Client {
+            inner: self.inner.cassette(cassette),
+        }
+    }
+
+    /// Opens connections to Google and the thumbnail hosts ahead of time, so the first search made with this client doesn't have to wait for the handshakes.
+    /// See [`Client::warmup`](crate::Client::warmup) for more information.
+    pub fn warmup(&self) {
+        let inner = self.inner.clone();
+        super::block_on(async move { inner.warmup().await })
+    }
+
+    /// The Google host that this client's searches have been redirected to, such as `www.google.co.uk`.
+    /// Returns `None` if Google hasn't redirected any searches away from `www.google.com`.
+    pub fn search_host(&self) -> Option<String> {
+        self.inner.search_host()
+    }
+
+    /// The default arguments with the given query and limit.
+    pub fn arguments(&self, query: &str, limit: usize) -> Arguments {
+        self.inner.arguments(query, limit)
+    }
+
+    /// Search for images using the default arguments, returning images up to the provided limit.
+    /// See [`search`](super::search) for more information.
+    pub fn search(&self, query: &str, limit: usize) -> SearchResult<ImageList> {
+        let (inner, query) = (self.inner.clone(), query.to_owned());
+        super::block_on(async move { inner.search(&query, limit).await })
+    }
+
+    /// Search for images using the provided arguments instead of the defaults, while still sharing the client's connections and cookies.
+    pub fn search_with(&self, args: Arguments) -> SearchResult<ImageList> {
+        let inner = self.inner.clone();
+        super::block_on(async move { inner.search_with(args).await })
+    }
+
+    /// Search for images using the default arguments, returning an iterator that lazily fetches further pages of results.
+    /// See [`search_iter`](super::search_iter) for more information.
+    pub fn search_iter(&self, query: &str, limit: usize) -> SearchIter {
+        SearchIter::new(self.inner.pages(query, limit))
+    }
+
+    /// Search for images using the default arguments, returning the urls of the images.
+    /// See [`urls`](super::urls) for more information.
+    pub fn urls(&self, query: &str, limit: usize) -> SearchResult<Vec<String>> {
+        let (inner, query) = (self.inner.clone(), query.to_owned());
+        super::block_on(async move { inner.urls(&query, limit).await })
+    }
+
+    /// Search for images using the default arguments and download them.
+    /// See [`download`](super::download) for more information.
+    pub fn download(&self, query: &str, limit: usize) -> SearchResult<Vec<PathBuf>> {
+        let (inner, query) = (self.inner.clone(), query.to_owned());
+        super::block_on(async move { inner.download(&query, limit).await })
+    }
+
+    /// Search for images using the default arguments and download them, returning a `DownloadReport`.
+    /// See [`download_report`](super::download_report) for more information.
+    pub fn download_report(&self, query: &str, limit: usize) -> SearchResult<DownloadReport> {
+        let (inner, query) = (self.inner.clone(), query.to_owned());
+        super::block_on(async move { inner.download_report(&query, limit).await })
+    }
+
+    /// Search for images using the provided arguments instead of the defaults and download them, returning a `DownloadReport`.
+    pub fn download_with(&self, args: Arguments) -> SearchResult<DownloadReport> {
+        let inner = self.inner.clone();
+        super::block_on(async move { inner.download_with(args).await })
+    }
+}
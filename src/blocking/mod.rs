@@ -1,32 +1,128 @@
 //! `image_search::blocking` is an optional feature that contains all the original capabilities of the non-blocking counterpart, but synchronous instead of async.
 //! Similar to [`reqwest`](https://crates.io/crates/reqwest)'s blocking feature
 
+pub mod aggregate;
+mod client;
+pub mod quick;
+
+pub use self::client::Client;
+
 extern crate async_std;
-extern crate futures;
-extern crate glob;
-extern crate infer;
-extern crate serde_json;
-extern crate surf;
-
-use crate::{get, Arguments, DownloadError, Error, Image, SearchResult};
-use futures::future;
-use std::env;
-use std::fs::File;
-use std::io::Write;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+
+use crate::{
+    Arguments, DetailedSearch, DownloadReport, Image, ImageList, Pages, SearchResult, Session,
+    UrlStatus,
+};
+#[cfg(feature = "tokio")]
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::time::Duration;
 
+#[cfg(feature = "tokio")]
+thread_local! {
+    static HANDLE: RefCell<Option<tokio::runtime::Handle>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, waiting for any blocking calls it makes from the tokio runtime behind `handle`.
+/// Without this, blocking calls wait from the tokio runtime they are called from if there is one, or from async-std otherwise.
+/// The requests themselves always run on async-std, since the HTTP backends need the Tokio 0.2 reactor it provides.
+/// Requires the `tokio` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate image_search;
+/// extern crate tokio;
+///
+/// use image_search::{Arguments, blocking};
+///
+/// fn main() -> Result<(), image_search::Error> {
+///     let runtime = tokio::runtime::Runtime::new()?;
+///     let urls = blocking::with_handle(runtime.handle().clone(), || {
+///         blocking::urls(Arguments::new("cats", 10))
+///     })?;
+///
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "tokio")]
+pub fn with_handle<R>(handle: tokio::runtime::Handle, f: impl FnOnce() -> R) -> R {
+    /// Restores the previous handle when dropped, even if `f` panics.
+    struct Restore(Option<tokio::runtime::Handle>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            HANDLE.with(|current| *current.borrow_mut() = self.0.take());
+        }
+    }
+
+    let _restore = Restore(HANDLE.with(|current| current.replace(Some(handle))));
+    f()
+}
+
+/// Runs `future` to completion on async-std, which provides the Tokio 0.2 reactor the HTTP backends need.
+/// The task is waited on from a tokio runtime if one is available, so that calling from inside a runtime doesn't panic or stall it.
+fn block_on<F>(future: F) -> F::Output
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let task = async_std::task::spawn(future);
+
+    #[cfg(feature = "tokio")]
+    {
+        let handle = HANDLE
+            .with(|current| current.borrow().clone())
+            .or_else(|| tokio::runtime::Handle::try_current().ok());
+        if let Some(handle) = handle {
+            return block_on_handle(handle, task);
+        }
+    }
+
+    async_std::task::block_on(task)
+}
+
+/// Waits for `future` to complete on the runtime behind `handle`.
+#[cfg(feature = "tokio")]
+fn block_on_handle<F>(handle: tokio::runtime::Handle, future: F) -> F::Output
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    use tokio::runtime::{Handle, RuntimeFlavor};
+
+    match Handle::try_current() {
+        // Blocking is only a problem inside of a runtime
+        Err(_) => handle.block_on(future),
+        Ok(current) if current.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| handle.block_on(future))
+        }
+        // A current thread runtime can't give up its only thread, so the future is run on a separate one
+        Ok(_) => std::thread::scope(|scope| {
+            scope
+                .spawn(|| handle.block_on(future))
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        }),
+    }
+}
+
 /// Search for images based on the provided arguments and return images up to the provided limit.
 ///
 /// # Errors
 /// This function will return an error if:
+/// * The query is empty, too long, or contains control characters
 /// * The GET request fails
 /// * The images are not able to be parsed
 ///
 /// # Examples
 ///
-/// ```
+/// ```no_run
 /// extern crate image_search;
 ///
 /// use image_search::Arguments;
@@ -38,17 +134,100 @@ use std::time::Duration;
 ///
 ///     Ok(())
 /// }
-pub fn search(args: Arguments) -> SearchResult<Vec<Image>> {
-    let url = crate::build_url(&args);
+/// ```
+pub fn search(args: Arguments) -> SearchResult<ImageList> {
+    block_on(crate::_search(args, Arc::new(Session::default())))
+}
 
-    let body = async_std::task::block_on(get(url))?;
+/// Search for images based on the provided arguments, returning the images along with statistics about how the results were parsed.
+/// See [`search_detailed`](crate::search_detailed) for more information.
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate image_search;
+///
+/// use image_search::Arguments;
+/// use image_search::blocking::search_detailed;
+///
+/// fn main() -> Result<(), image_search::Error> {
+///     let detailed = search_detailed(Arguments::new("example", 10))?;
+///     println!("{} results skipped", detailed.stats.total_skipped());
+///
+///     Ok(())
+/// }
+/// ```
+pub fn search_detailed(args: Arguments) -> SearchResult<DetailedSearch> {
+    block_on(crate::_search_detailed(args, Arc::new(Session::default())))
+}
+
+/// Search for images based on the provided arguments, returning an iterator that lazily fetches further pages of results as they are needed.
+/// The iterator ends once the limit has been reached (unless it is 0) or Google stops returning new images.
+///
+/// Unlike `search`, results are never shuffled or ranked, since they are returned as soon as each page is fetched.
+///
+/// # Errors
+/// The iterator will return an error and then end if:
+/// * The query is empty, too long, or contains control characters
+/// * The GET request fails
+/// * The images are not able to be parsed
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate image_search;
+///
+/// use image_search::Arguments;
+/// use image_search::blocking::search_iter;
+///
+/// fn main() -> Result<(), image_search::Error> {
+///     let args = Arguments::new("cats", 250);
+///     for image in search_iter(args) {
+///         println!("{}", image?.url);
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub fn search_iter(args: Arguments) -> SearchIter {
+    SearchIter::new(Pages::new(args, Arc::new(Session::default())))
+}
 
-    let imgs = crate::unpack(body).ok_or(Error::Parse)?;
+/// An iterator over search results that lazily fetches pages as they are needed, created by [`search_iter`].
+pub struct SearchIter {
+    /// Taken while a page is being fetched, since the fetch runs as a separate task.
+    pages: Option<Pages>,
+    buffer: VecDeque<Image>,
+}
 
-    if imgs.len() > args.limit && args.limit > 0 {
-        Ok(imgs[..args.limit].to_vec())
-    } else {
-        Ok(imgs)
+impl SearchIter {
+    fn new(pages: Pages) -> SearchIter {
+        SearchIter {
+            pages: Some(pages),
+            buffer: VecDeque::new(),
+        }
+    }
+}
+
+impl Iterator for SearchIter {
+    type Item = SearchResult<Image>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.is_empty() {
+            let mut pages = self.pages.take()?;
+            let (pages, page) = block_on(async move {
+                let page = pages.next_page().await;
+                (pages, page)
+            });
+            self.pages = Some(pages);
+
+            match page? {
+                Ok(imgs) => self.buffer.extend(imgs),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        self.buffer.pop_front().map(Ok)
     }
 }
 
@@ -56,12 +235,13 @@ pub fn search(args: Arguments) -> SearchResult<Vec<Image>> {
 ///
 /// # Errors
 /// This function will return an error if:
+/// * The query is empty, too long, or contains control characters
 /// * The GET request fails
 /// * The images are not able to be parsed
 ///
 /// # Examples
 ///
-/// ```
+/// ```no_run
 /// extern crate image_search;
 ///
 /// use image_search::Arguments;
@@ -73,8 +253,9 @@ pub fn search(args: Arguments) -> SearchResult<Vec<Image>> {
 ///
 ///     Ok(())
 /// }
+/// ```
 pub fn urls(args: Arguments) -> SearchResult<Vec<String>> {
-    let thumbnails = (&args.thumbnails).to_owned();
+    let thumbnails = args.thumbnails;
     let images = search(args)?;
 
     let mut all: Vec<String> = Vec::new();
@@ -89,17 +270,64 @@ pub fn urls(args: Arguments) -> SearchResult<Vec<String>> {
     Ok(all)
 }
 
+/// Checks whether each of the urls can still be reached, without downloading or saving anything.
+/// See [`validate_urls`](crate::validate_urls) for more information.
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate image_search;
+///
+/// use image_search::blocking::validate_urls;
+/// use std::time::Duration;
+///
+/// fn main() {
+///     let saved = vec!["https://www.example.com/image.jpg".to_owned()];
+///     let statuses = validate_urls(saved, Duration::from_secs(10));
+/// }
+/// ```
+pub fn validate_urls<D: Into<Option<Duration>>>(urls: Vec<String>, timeout: D) -> Vec<UrlStatus> {
+    block_on(crate::validate_urls(urls, timeout.into()))
+}
+
+/// Groups the images into clusters of visually similar images by comparing their thumbnails.
+/// Clusters are ordered from largest to smallest, and each has a representative image that can be shown for the whole group.
+/// Requires the `image` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate image_search;
+///
+/// use image_search::Arguments;
+/// use image_search::blocking::{cluster, search};
+/// use std::time::Duration;
+///
+/// fn main() -> Result<(), image_search::Error> {
+///     let images = search(Arguments::new("jaguar", 50))?;
+///     let groups = cluster(&images, Duration::from_secs(10));
+///
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "image")]
+pub fn cluster<D: Into<Option<Duration>>>(images: &[Image], timeout: D) -> Vec<crate::Cluster> {
+    let (images, timeout) = (images.to_vec(), timeout.into());
+    block_on(async move { crate::cluster(&images, timeout).await })
+}
+
 /// Search for images based on the provided `Arguments` and downloads them to the path specified in the `directory` field in `Arguments`, or the "images" folder if none is provided.
 /// # Errors
 /// This function will return an error if:
+/// * The query is empty, too long, or contains control characters
 /// * The GET request fails
 /// * The images are not able to be parsed
 /// * The program is unable to create/read/write to files or directories
 ///
 /// # Examples
 ///
-/// ```
-/// extern crate image_search
+/// ```no_run
+/// extern crate image_search;
 ///
 /// use image_search::Arguments;
 /// use image_search::blocking::download;
@@ -111,162 +339,127 @@ pub fn urls(args: Arguments) -> SearchResult<Vec<String>> {
 ///
 ///     Ok(())
 /// }
+/// ```
+///
+/// Progress can be tracked with a callback set using `Arguments::on_event`:
+///
+/// ```no_run
+/// extern crate image_search;
+///
+/// use image_search::{Arguments, DownloadEvent};
+/// use image_search::blocking::download;
+///
+/// fn main() -> Result<(), image_search::Error> {
+///     let args = Arguments::new("cats", 10).on_event(|event| match event {
+///         DownloadEvent::Downloaded(file) => println!("Downloaded {}", file.path.display()),
+///         DownloadEvent::Skipped { url, reason } => println!("Skipped {}: {}", url, reason),
+///         _ => (),
+///     });
+///     let paths = download(args)?;
+///
+///     Ok(())
+/// }
+/// ```
 pub fn download(args: Arguments) -> SearchResult<Vec<PathBuf>> {
-    let images = urls(Arguments {
-        query: args.query.clone(),
-        limit: 0,
-        directory: args.directory.clone(),
-        ..args
-    })?;
-
-    let dir = match args.directory {
-        Some(dir) => dir.to_owned(),
-        None => match env::current_dir() {
-            Ok(v) => v,
-            Err(e) => return Err(Error::Dir(e)),
-        }
-        .join("images"),
-    };
-
-    match std::fs::create_dir_all(&dir) {
-        Ok(_) => (),
-        Err(e) => return Err(Error::Dir(e)),
-    };
-
-    let mut suffix = 0;
-    let mut paths: Vec<PathBuf> = Vec::new();
-    for _ in 0..args.limit {
-        let mut path = dir.join(args.query.to_owned() + &suffix.to_string());
-
-        let mut matches = match glob::glob(&(path.display().to_string() + ".*")) {
-            Ok(paths) => paths.last().is_some(),
-            Err(_) => false,
-        };
-
-        while matches {
-            suffix += 1;
-            path = dir.join(args.query.to_owned() + &suffix.to_string());
-            matches = match glob::glob(&(path.display().to_string() + ".*")) {
-                Ok(paths) => paths.last().is_some(),
-                Err(_) => false,
-            };
-        }
-
-        paths.push(path);
-        suffix += 1;
-    }
-
-    let with_extensions = async_std::task::block_on(download_n(images, paths, args.timeout));
-
-    Ok(with_extensions)
-}
-
-/// Downloads up to n images concurrently
-async fn download_n(
-    urls: Vec<String>,
-    paths: Vec<PathBuf>,
-    timeout: Option<Duration>,
-) -> Vec<PathBuf> {
-    let mut_urls = Arc::new(Mutex::new(urls));
-
-    let mut downloaders = Vec::new();
-    let client = surf::Client::new();
-    for path in paths {
-        downloaders.push(download_until(
-            mut_urls.clone(),
-            path,
-            client.clone(),
-            timeout,
-        ));
-    }
-
-    let with_extensions = future::join_all(downloaders)
-        .await
-        .into_iter()
-        .filter_map(|x| x.ok())
-        .collect();
-
-    with_extensions
+    Ok(download_report(args)?.paths())
 }
 
-macro_rules! next_available {
-    ($urls:expr) => {{
-        let mut mut_urls = $urls.lock().unwrap(); // Safe: no thread should panic while holding, since this is the only unwrap/expect
-        if mut_urls.is_empty() {
-            return Err(DownloadError::Overflow);
-        }
-        let url = mut_urls.remove(0);
-        std::mem::drop(mut_urls);
-
-        url
-    }};
+/// Search for images based on the provided `Arguments` and downloads them the same way as the `download` function,
+/// but returns a `DownloadReport` containing more information about the downloaded files and the run as a whole.
+///
+/// # Errors
+/// This function will return an error if:
+/// * The query is empty, too long, or contains control characters
+/// * The GET request fails
+/// * The images are not able to be parsed
+/// * The program is unable to create/read/write to files or directories
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate image_search;
+///
+/// use image_search::Arguments;
+/// use image_search::blocking::download_report;
+/// use std::time::Duration;
+///
+/// fn main() -> Result<(), image_search::Error> {
+///     let args = Arguments::new("cats", 10).deadline(Duration::from_secs(30));
+///     let report = download_report(args)?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn download_report(args: Arguments) -> SearchResult<DownloadReport> {
+    block_on(crate::_download(args, Arc::new(Session::default())))
 }
 
-/// Trys to download an image to a given path until one is successful or it runs out of possible urls
-async fn download_until(
-    urls: Arc<Mutex<Vec<String>>>,
-    path: PathBuf,
-    client: surf::Client,
-    timeout: Option<Duration>,
-) -> Result<PathBuf, DownloadError> {
-    let mut url = next_available!(urls);
-
-    let with_extension = loop {
-        let with_extension = download_image(client.clone(), &path, url.to_owned(), timeout).await;
-        if with_extension.is_ok() {
-            break with_extension;
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::providers::{PageRequest, SearchProvider};
+    use futures::future::BoxFuture;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// A provider that fetches a page from a server on localhost and returns no images.
+    #[derive(Debug)]
+    struct Local(String);
+
+    impl SearchProvider for Local {
+        fn page_size(&self) -> usize {
+            10
         }
-        url = next_available!(urls);
-    };
 
-    with_extension
-}
-
-async fn download_image(
-    client: surf::Client,
-    path: &PathBuf,
-    url: String,
-    timeout: Option<Duration>,
-) -> Result<PathBuf, DownloadError> {
-    let buf = match timeout {
-        Some(duration) => {
-            async_std::future::timeout(duration, client.recv_bytes(surf::get(url))).await?
-        }
-        None => client.recv_bytes(surf::get(url)).await,
-    }?;
-
-    let first_128 = buf.iter().take(1024).map(|x| *x).collect::<Vec<u8>>();
-    let svg = match std::str::from_utf8(&first_128) {
-        Ok(s) => s.contains("<svg"),
-        Err(_) => false,
-    };
-
-    let extension = if svg {
-        "svg".to_owned()
-    } else {
-        let kind = match infer::get(&buf) {
-            Some(k) => k,
-            None => return Err(DownloadError::Extension),
-        };
-
-        if kind.matcher_type() != infer::MatcherType::Image {
-            return Err(DownloadError::Extension);
+        fn fetch_page<'a>(
+            &'a self,
+            request: PageRequest<'a>,
+        ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+            Box::pin(async move {
+                request.get(&self.0).await?;
+                Ok(Vec::new())
+            })
         }
+    }
 
-        kind.extension().to_owned()
-    };
+    /// Serves empty pages on localhost, returning their url.
+    fn serve() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buf = [0; 4096];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                );
+            }
+        });
+        url
+    }
 
-    let with_extension = path.clone().with_extension(extension);
+    #[tokio::test(flavor = "multi_thread")]
+    async fn search_inside_tokio_runtime() {
+        let args = Arguments::new("cats", 1).provider(Local(serve()));
+        assert!(search(args).unwrap().is_empty());
+    }
 
-    let mut f = match File::create(&with_extension) {
-        Ok(f) => f,
-        Err(e) => return Err(DownloadError::Fs(e)),
-    };
+    #[tokio::test]
+    async fn search_inside_current_thread_runtime() {
+        let args = Arguments::new("cats", 1).provider(Local(serve()));
+        assert!(search(args).unwrap().is_empty());
+    }
 
-    match f.write_all(&buf) {
-        Ok(_) => (),
-        Err(e) => return Err(DownloadError::Fs(e)),
-    };
+    #[tokio::test(flavor = "multi_thread")]
+    async fn search_from_spawn_blocking() {
+        let args = Arguments::new("cats", 1).provider(Local(serve()));
+        let images = tokio::task::spawn_blocking(|| search(args)).await.unwrap();
+        assert!(images.unwrap().is_empty());
+    }
 
-    Ok(with_extension)
+    #[tokio::test(flavor = "multi_thread")]
+    async fn search_iter_inside_tokio_runtime() {
+        let args = Arguments::new("cats", 1).provider(Local(serve()));
+        assert!(search_iter(args).next().is_none());
+    }
 }
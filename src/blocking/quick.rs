@@ -0,0 +1,32 @@
+//! Blocking versions of the one-line helpers in [`image_search::quick`](crate::quick).
+//!
+//! ```no_run
+//! extern crate image_search;
+//!
+//! fn main() -> Result<(), image_search::Error> {
+//!     let urls = image_search::blocking::quick::urls("cats", 10)?;
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{Arguments, ImageList, SearchResult};
+use std::path::PathBuf;
+
+/// Search for `query` and return images up to `limit`.
+/// See [`search`](super::search) for more information.
+pub fn search(query: &str, limit: usize) -> SearchResult<ImageList> {
+    super::search(Arguments::new(query, limit))
+}
+
+/// Search for `query` and return the urls of the images, up to `limit`.
+/// See [`urls`](super::urls) for more information.
+pub fn urls(query: &str, limit: usize) -> SearchResult<Vec<String>> {
+    super::urls(Arguments::new(query, limit))
+}
+
+/// Search for `query` and download up to `limit` images to the "images" folder.
+/// See [`download`](super::download) for more information.
+pub fn download(query: &str, limit: usize) -> SearchResult<Vec<PathBuf>> {
+    super::download(Arguments::new(query, limit))
+}
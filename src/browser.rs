@@ -0,0 +1,81 @@
+//! A fallback that loads the results page in headless Chromium when the page returned to a plain request can't be parsed.
+//! Enabled by the optional `browser` feature, and requires Chrome or Chromium to be installed.
+
+extern crate chromiumoxide;
+
+use crate::providers::google::{unpack, unpack_fallback};
+use crate::{Arguments, Image};
+use chromiumoxide::browser::{Browser, BrowserConfig};
+use chromiumoxide::Page;
+use futures::StreamExt;
+
+/// Collects the images in the live page that are large enough to be results, as `[src, width, height]`.
+const THUMBNAILS_JS: &str = r#"Array.from(document.querySelectorAll("img"))
+    .filter(img => img.naturalWidth >= 50 && img.naturalHeight >= 50 && /^(https?:|data:image\/)/.test(img.src))
+    .map(img => [img.src, img.naturalWidth, img.naturalHeight])"#;
+
+/// Loads `url` in a headless browser and parses the images from the rendered page.
+/// If the page still can't be parsed, the images shown in the page are returned instead, with their thumbnails as their urls.
+/// Returns `None` if the browser can't be launched or no images are found.
+pub(crate) async fn fetch(url: &str, args: &Arguments) -> Option<Vec<Image>> {
+    let config = BrowserConfig::builder().build().ok()?;
+    let (mut browser, mut handler) = Browser::launch(config).await.ok()?;
+    let events = async_std::task::spawn(async move {
+        while let Some(event) = handler.next().await {
+            if event.is_err() {
+                break;
+            }
+        }
+    });
+
+    let images = match args.timeout {
+        Some(duration) => async_std::future::timeout(duration, load(&browser, url, args))
+            .await
+            .ok()
+            .flatten(),
+        None => load(&browser, url, args).await,
+    };
+
+    let _ = browser.close().await;
+    let _ = browser.wait().await;
+    events.cancel().await;
+
+    images
+}
+
+async fn load(browser: &Browser, url: &str, args: &Arguments) -> Option<Vec<Image>> {
+    let page = browser.new_page("about:blank").await.ok()?;
+    if let Some(user_agent) = &args.user_agent {
+        page.set_user_agent(user_agent.as_str()).await.ok()?;
+    }
+    page.goto(url).await.ok()?;
+    page.wait_for_navigation().await.ok()?;
+
+    let content = page.content().await.ok()?;
+    if let Some(imgs) = unpack(&content, args.thumbnails_only)
+        .ok()
+        .map(|(imgs, _)| imgs)
+        .or_else(|| unpack_fallback(&content, args.thumbnails_only))
+    {
+        return Some(imgs);
+    }
+
+    thumbnails(&page).await
+}
+
+/// The images displayed in `page`, which are only the thumbnails of the results.
+async fn thumbnails(page: &Page) -> Option<Vec<Image>> {
+    let found: Vec<(String, i64, i64)> =
+        page.evaluate(THUMBNAILS_JS).await.ok()?.into_value().ok()?;
+
+    let images: Vec<Image> = found
+        .into_iter()
+        .map(|(src, width, height)| Image::new(&src, width, height, &src, ""))
+        .collect();
+
+    if images.is_empty() {
+        None
+    } else {
+        Some(images)
+    }
+}
@@ -0,0 +1,737 @@
+//! A reusable client that keeps its connections, cookies, and default arguments between searches.
+
+extern crate async_std;
+extern crate surf;
+
+use crate::{
+    _download, _search, content_hash, retry_after, rng, Arguments, DownloadReport, Error, Image,
+    ImageList, Pages, SearchResult, MAX_REDIRECTS, MAX_RETRIES,
+};
+use futures::Stream;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The User-Agent sent with search requests if none is set with `Arguments::user_agent` or the `IMAGE_SEARCH_USER_AGENT` environment variable.
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+
+/// The headers a browser sends along with its User-Agent when navigating to a page, preferring `language` if one is given.
+/// The client hint (`Sec-CH-UA`) headers are only included for Chrome User-Agents, and are based on its version and platform so they stay consistent with it.
+fn browser_headers(user_agent: &str, language: Option<&str>) -> Vec<(&'static str, String)> {
+    let accept_language = match language {
+        Some(language) => format!("{},en;q=0.9", language),
+        None => "en-US,en;q=0.9".to_owned(),
+    };
+    let mut headers = vec![
+        ("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8".to_owned()),
+        ("Accept-Language", accept_language),
+        ("Sec-Fetch-Dest", "document".to_owned()),
+        ("Sec-Fetch-Mode", "navigate".to_owned()),
+        ("Sec-Fetch-Site", "none".to_owned()),
+        ("Sec-Fetch-User", "?1".to_owned()),
+        ("Upgrade-Insecure-Requests", "1".to_owned()),
+    ];
+
+    // Edge and Opera include "Chrome/" in their User-Agents too, but send different brands
+    let is_chrome = !user_agent.contains("Edg/") && !user_agent.contains("OPR/");
+    let version = user_agent
+        .split("Chrome/")
+        .nth(1)
+        .and_then(|rest| rest.split('.').next())
+        .filter(|version| {
+            is_chrome && !version.is_empty() && version.chars().all(|c| c.is_ascii_digit())
+        });
+
+    if let Some(version) = version {
+        let platform = if user_agent.contains("Android") {
+            "Android"
+        } else if user_agent.contains("Windows") {
+            "Windows"
+        } else if user_agent.contains("Macintosh") {
+            "macOS"
+        } else if user_agent.contains("CrOS") {
+            "Chrome OS"
+        } else if user_agent.contains("Linux") {
+            "Linux"
+        } else {
+            "Unknown"
+        };
+        let mobile = if user_agent.contains("Mobile") {
+            "?1"
+        } else {
+            "?0"
+        };
+
+        headers.push((
+            "Sec-CH-UA",
+            format!(
+                "\"Google Chrome\";v=\"{0}\", \"Chromium\";v=\"{0}\", \"Not_A Brand\";v=\"24\"",
+                version
+            ),
+        ));
+        headers.push(("Sec-CH-UA-Mobile", mobile.to_owned()));
+        headers.push(("Sec-CH-UA-Platform", format!("\"{}\"", platform)));
+    }
+
+    headers
+}
+
+/// Whether `labels` are one of the top level domains Google is on: `com`, a country code such as `de`, or `co.` or `com.` followed by a country code, such as `co.uk`.
+fn is_google_tld(labels: &[&str]) -> bool {
+    let country = |label: &str| label.len() == 2 && label.chars().all(|c| c.is_ascii_alphabetic());
+    match labels {
+        [tld] => *tld == "com" || country(tld),
+        [second, tld] => matches!(*second, "co" | "com") && country(tld),
+        _ => false,
+    }
+}
+
+/// Whether `host` is one of Google's search domains, such as `www.google.com` or `www.google.co.uk`.
+fn is_google_host(host: &str) -> bool {
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let labels: Vec<&str> = host.split('.').collect();
+    matches!(labels.split_first(), Some((&"google", tld)) if is_google_tld(tld))
+}
+
+/// Whether `host` belongs to Google, including subdomains like `consent.google.com`, so that cookies can be sent to it and accepted from it.
+/// Only hosts that end in `google.` and one of Google's top level domains match, so hosts like `google.example.com` don't.
+fn is_google_domain(host: &str) -> bool {
+    let labels: Vec<&str> = host.split('.').collect();
+    match labels.iter().rposition(|label| *label == "google") {
+        Some(position) => is_google_tld(&labels[position + 1..]),
+        None => false,
+    }
+}
+
+/// The hosts connected to by `Client::warmup`: Google itself and the hosts that serve the thumbnails.
+const WARMUP_HOSTS: [&str; 3] = [
+    "https://www.google.com/",
+    "https://encrypted-tbn0.gstatic.com/",
+    "https://encrypted-tbn1.gstatic.com/",
+];
+
+/// Records search responses to, or replays them from, a directory of cassette files, set with `Client::cassette`.
+/// Each response is stored as a JSON file named after a hash of the request url, containing the url and the response body.
+/// Replaying lets integrations be tested deterministically without making any requests to Google, and lets parser changes be checked against old responses.
+#[derive(Debug, Clone)]
+pub enum Cassette {
+    /// Search requests are made as normal, and each response is saved to a cassette file in the directory.
+    Record(PathBuf),
+    /// No search requests are made, and the responses are read from the cassette files in the directory instead.
+    /// Searches fail with a `404 Not Found` network error if a request wasn't recorded.
+    Replay(PathBuf),
+}
+
+/// Writes `contents` to `path`, creating it so that only the current user can read it on unix, since it holds session cookies.
+fn write_private(path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
+
+/// The path of the cassette file for `url` in `dir`.
+fn cassette_path(dir: &std::path::Path, url: &str) -> PathBuf {
+    dir.join(content_hash(url.as_bytes()))
+        .with_extension("json")
+}
+
+/// The state shared between requests: the HTTP client, the cookies Google has set, and the search rate limit.
+pub(crate) struct Session {
+    pub(crate) client: surf::Client,
+    cookies: Mutex<HashMap<String, String>>,
+    /// The cookies that Google set or that were loaded from the cookie file, which are the only ones saved to it.
+    saved_cookies: Mutex<HashMap<String, String>>,
+    cookie_file: Mutex<Option<PathBuf>>,
+    rate_limit: Mutex<Option<Duration>>,
+    jitter: Mutex<Option<Duration>>,
+    jitter_rng: Mutex<Option<StdRng>>,
+    last_search: async_std::sync::Mutex<Option<Instant>>,
+    host: Mutex<Option<String>>,
+    cassette: Mutex<Option<Cassette>>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            client: surf::Client::new(),
+            cookies: Mutex::new(HashMap::new()),
+            saved_cookies: Mutex::new(HashMap::new()),
+            cookie_file: Mutex::new(None),
+            rate_limit: Mutex::new(None),
+            jitter: Mutex::new(None),
+            jitter_rng: Mutex::new(None),
+            last_search: async_std::sync::Mutex::new(None),
+            host: Mutex::new(None),
+            cassette: Mutex::new(None),
+        }
+    }
+}
+
+/// The query parameters that engines take API keys in, which are left out of cassettes and errors.
+const SECRET_PARAMS: [&str; 2] = ["key", "api_key"];
+
+/// `url` without any API key parameters, used to name, record, and report cassettes so that keys aren't saved.
+/// Recordings made with one key are also replayed with another.
+fn redacted(url: &str) -> String {
+    let mut url = match surf::Url::parse(url) {
+        Ok(url) => url,
+        Err(_) => return url.to_owned(),
+    };
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| !SECRET_PARAMS.contains(&name.as_ref()))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    url.to_string()
+}
+
+/// Reads the response recorded for `url` from the cassette files in `dir`.
+fn replay(dir: &std::path::Path, url: &str) -> Result<String, surf::Error> {
+    let not_recorded = || {
+        surf::Error::from_str(
+            surf::StatusCode::NotFound,
+            format!("No recorded response for {}", url),
+        )
+    };
+
+    let json = fs::read_to_string(cassette_path(dir, url)).map_err(|_| not_recorded())?;
+    let cassette: serde_json::Value = serde_json::from_str(&json)?;
+    cassette["body"]
+        .as_str()
+        .map(str::to_owned)
+        .ok_or_else(not_recorded)
+}
+
+/// The detail of `Error::Provider` for a response with an error status, which is the status followed by the start of the body.
+fn status_error(status: surf::StatusCode, body: &str) -> String {
+    /// The most characters of the body that are included, since it may be a whole page.
+    const MAX_LENGTH: usize = 200;
+
+    let detail: String = body.trim().chars().take(MAX_LENGTH).collect();
+    if detail.is_empty() {
+        format!("{} {}", status as u16, status.canonical_reason())
+    } else {
+        format!(
+            "{} {}: {}",
+            status as u16,
+            status.canonical_reason(),
+            detail
+        )
+    }
+}
+
+/// Saves `body` as the response for `url` to a cassette file in `dir`.
+fn record(dir: &std::path::Path, url: &str, body: &str) -> Result<(), surf::Error> {
+    fs::create_dir_all(dir)?;
+    let cassette = serde_json::json!({ "url": url, "body": body });
+    fs::write(
+        cassette_path(dir, url),
+        serde_json::to_string_pretty(&cassette)?,
+    )?;
+    Ok(())
+}
+
+impl Session {
+    /// Makes a search request to `url` with the headers of a browser, waiting for the rate limit and sending and storing any cookies.
+    /// Rate limited requests are retried after the wait given by their `Retry-After` header, as long as it is within `Arguments::max_retry_wait`.
+    /// Redirects to other Google domains (such as a country domain) are followed, and the final host is used for later requests.
+    /// Any `headers` are sent as well, but aren't recorded to the cassette, and neither are API keys in the url.
+    /// Responses with an error status fail with `Error::Provider`, and aren't recorded.
+    pub(crate) async fn get(
+        &self,
+        url: String,
+        headers: &[(&str, &str)],
+        args: &Arguments,
+    ) -> SearchResult<String> {
+        let cassette = self
+            .cassette
+            .lock()
+            .expect("Cassette lock poisoned")
+            .clone();
+        if let Some(Cassette::Replay(dir)) = &cassette {
+            return Ok(replay(dir, &redacted(&url))?);
+        }
+
+        let (status, body) = self.fetch(&url, headers, args).await?;
+        // Errors from the engine, such as a rejected API key or a used up quota, would otherwise be parsed as if they were results
+        if !status.is_success() {
+            return Err(Error::Provider(status_error(status, &body)));
+        }
+        if let Some(Cassette::Record(dir)) = &cassette {
+            record(dir, &redacted(&url), &body)?;
+        }
+        Ok(body)
+    }
+
+    /// Makes the request for `get`, without recording or replaying it, returning the status and body of the final response.
+    async fn fetch(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+        args: &Arguments,
+    ) -> Result<(surf::StatusCode, String), surf::Error> {
+        self.wait_for_rate_limit(args.seed).await;
+
+        let user_agent = args
+            .user_agent
+            .clone()
+            .or_else(|| env::var("IMAGE_SEARCH_USER_AGENT").ok())
+            .unwrap_or_else(|| USER_AGENT.to_owned());
+
+        let mut url = surf::Url::parse(url)?;
+        // Only searches sent to Google follow it to the country domain it redirected to
+        if let Some(host) = self
+            .host()
+            .filter(|_| url.host_str().is_some_and(is_google_host))
+        {
+            url.set_host(Some(&host))?;
+        }
+
+        let mut redirects = 0;
+        let mut retries = 0;
+        while redirects < MAX_REDIRECTS {
+            let mut req = self
+                .client
+                .get(url.clone())
+                .header("User-Agent", user_agent.as_str());
+            for (name, value) in browser_headers(&user_agent, args.language.as_deref()) {
+                req = req.header(name, value);
+            }
+            for (name, value) in headers {
+                req = req.header(*name, *value);
+            }
+            let cookie = self.cookie_header();
+            if !cookie.is_empty() && url.host_str().is_some_and(is_google_domain) {
+                req = req.header("Cookie", cookie);
+            }
+
+            let mut res = req.await?;
+            if url.host_str().is_some_and(is_google_domain) {
+                self.store_cookies(&res);
+            }
+
+            if retries < MAX_RETRIES {
+                if let Some(wait) = retry_after(&res, args.max_retry_wait) {
+                    retries += 1;
+                    async_std::task::sleep(wait).await;
+                    continue;
+                }
+            }
+
+            let location = match res.header("Location") {
+                Some(location) if res.status().is_redirection() => {
+                    location.last().as_str().to_owned()
+                }
+                _ => return Ok((res.status(), res.body_string().await?)),
+            };
+            url = url.join(&location)?;
+            redirects += 1;
+
+            if let Some(host) = url.host_str().filter(|host| is_google_host(host)) {
+                if url.path() == "/search" {
+                    *self.host.lock().expect("Host lock poisoned") = Some(host.to_owned());
+                }
+            }
+        }
+
+        Err(surf::Error::from_str(
+            surf::StatusCode::LoopDetected,
+            "Too many redirects",
+        ))
+    }
+
+    /// The Google host that searches are redirected to, if they have been redirected.
+    pub(crate) fn host(&self) -> Option<String> {
+        self.host.lock().expect("Host lock poisoned").clone()
+    }
+
+    /// Stores the cookies from the `Set-Cookie` headers of `res`, saving them to the cookie file if there is one.
+    /// Only responses from Google should be passed, since the cookies are sent back to every Google domain.
+    fn store_cookies(&self, res: &surf::Response) {
+        if let Some(set_cookies) = res.header("Set-Cookie") {
+            let mut cookies = self.cookies.lock().expect("Cookie lock poisoned");
+            let mut saved = self.saved_cookies.lock().expect("Cookie lock poisoned");
+            for set_cookie in set_cookies.iter() {
+                // Only the name and value are needed, the attributes after them are ignored
+                let pair = set_cookie.as_str().split(';').next().unwrap_or_default();
+                if let Some((name, value)) = pair.split_once('=') {
+                    let (name, value) = (name.trim().to_owned(), value.trim().to_owned());
+                    cookies.insert(name.clone(), value.clone());
+                    saved.insert(name, value);
+                }
+            }
+
+            if let Some(path) = &*self.cookie_file.lock().expect("Cookie file lock poisoned") {
+                // Failing to save only means the cookies won't be reused next time, so it isn't worth failing the search over
+                if let Ok(json) = serde_json::to_string_pretty(&*saved) {
+                    let _ = write_private(path, json.as_bytes());
+                }
+            }
+        }
+    }
+
+    /// Adds the cookies from `header`, which is in the format of a `Cookie` header (`name=value; name2=value2`).
+    fn add_cookies(&self, header: &str) {
+        let mut cookies = self.cookies.lock().expect("Cookie lock poisoned");
+        for pair in header.split(';') {
+            if let Some((name, value)) = pair.split_once('=') {
+                cookies.insert(name.trim().to_owned(), value.trim().to_owned());
+            }
+        }
+    }
+
+    /// Loads the cookies saved in `path`, and saves any new cookies to it from then on.
+    fn set_cookie_file(&self, path: PathBuf) {
+        if let Ok(json) = fs::read_to_string(&path) {
+            if let Ok(saved) = serde_json::from_str::<HashMap<String, String>>(&json) {
+                self.cookies
+                    .lock()
+                    .expect("Cookie lock poisoned")
+                    .extend(saved.clone());
+                self.saved_cookies
+                    .lock()
+                    .expect("Cookie lock poisoned")
+                    .extend(saved);
+            }
+        }
+
+        *self.cookie_file.lock().expect("Cookie file lock poisoned") = Some(path);
+    }
+
+    /// The value of the `Cookie` header containing all of the stored cookies.
+    fn cookie_header(&self) -> String {
+        let cookies = self.cookies.lock().expect("Cookie lock poisoned");
+        cookies
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<String>>()
+            .join("; ")
+    }
+
+    /// Waits until enough time has passed since the last search request to satisfy the rate limit, plus a random amount of jitter if it is set.
+    /// The jitter is drawn from a random number generator created from `seed` the first time it is needed, so a new session with the same seed waits the same amounts of time.
+    async fn wait_for_rate_limit(&self, seed: Option<u64>) {
+        let rate_limit = *self.rate_limit.lock().expect("Rate limit lock poisoned");
+        let jitter = *self.jitter.lock().expect("Jitter lock poisoned");
+        let mut last_search = self.last_search.lock().await;
+
+        if let (Some(rate_limit), Some(last)) = (rate_limit, *last_search) {
+            let elapsed = last.elapsed();
+            if elapsed < rate_limit {
+                async_std::task::sleep(rate_limit - elapsed).await;
+            }
+        }
+
+        if let Some(jitter) = jitter.filter(|jitter| !jitter.is_zero()) {
+            let delay = self
+                .jitter_rng
+                .lock()
+                .expect("Jitter lock poisoned")
+                .get_or_insert_with(|| rng(seed))
+                .gen_range(Duration::ZERO..jitter);
+            async_std::task::sleep(delay).await;
+        }
+
+        *last_search = Some(Instant::now());
+    }
+}
+
+/// A client for searching and downloading images, which keeps its connection pool, cookies, rate limit, and default arguments between calls.
+/// Cloning a `Client` is cheap, and all of the clones share the same connections and cookies.
+///
+/// # Example
+/// ```no_run
+/// extern crate tokio;
+/// extern crate image_search;
+///
+/// use image_search::{Arguments, Client, Color};
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), image_search::Error> {
+///     let client = Client::new()
+///         .defaults(Arguments::default().color(Color::Gray))
+///         .rate_limit(Duration::from_secs(2));
+///
+///     let cats = client.search("cats", 10).await?;
+///     let dogs = client.urls("dogs", 10).await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Client {
+    session: Arc<Session>,
+    defaults: Arguments,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client::new()
+    }
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("defaults", &self.defaults)
+            .finish()
+    }
+}
+
+impl Client {
+    pub fn new() -> Client {
+        Client {
+            session: Arc::new(Session::default()),
+            defaults: Arguments::default(),
+        }
+    }
+
+    /// Sets the arguments used for every search made with this client. The query and limit are replaced by the ones passed to each method.
+    pub fn defaults(mut self, defaults: Arguments) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    /// Sets the minimum time between search requests made by this client and all of its clones.
+    /// Useful for avoiding being blocked by Google when making many searches.
+    pub fn rate_limit<D: Into<Option<Duration>>>(self, interval: D) -> Self {
+        *self
+            .session
+            .rate_limit
+            .lock()
+            .expect("Rate limit lock poisoned") = interval.into();
+        self
+    }
+
+    /// Adds cookies to send with every search request, given in the format of a `Cookie` header (`name=value; name2=value2`).
+    /// This can be used to search with an existing Google session, such as by copying the `SID` and `SAPISID` cookies from a browser.
+    /// The cookies are only ever sent to Google, never to the hosts the images are downloaded from.
+    pub fn cookies(self, cookies: &str) -> Self {
+        self.session.add_cookies(cookies);
+        self
+    }
+
+    /// Sets a file that the cookies Google sets are saved to, so that they can be reused the next time the program runs.
+    /// Any cookies already saved in the file are loaded straight away, and the file is ignored if it can't be read. It is created the first time a cookie is set.
+    /// Cookies added with [`cookies`](Client::cookies) are never saved to it.
+    /// The file holds session credentials in plaintext, so on unix it is created readable only by the current user.
+    pub fn cookie_file<P: Into<PathBuf>>(self, path: P) -> Self {
+        self.session.set_cookie_file(path.into());
+        self
+    }
+
+    /// Adds a random delay of up to `window` before each search request made by this client and all of its clones, on top of the rate limit.
+    /// Spreads out the timing of requests in batch jobs, rather than making them at a fixed interval.
+    pub fn jitter<D: Into<Option<Duration>>>(self, window: D) -> Self {
+        *self.session.jitter.lock().expect("Jitter lock poisoned") = window.into();
+        self
+    }
+
+    /// Records the responses to this client's search requests to cassette files, or replays them from the files instead of making any requests.
+    /// Only search requests are recorded, not the requests for downloading images. Setting to `None` goes back to making requests as normal.
+    ///
+    /// # Example
+    /// ```no_run
+    /// extern crate tokio;
+    /// extern crate image_search;
+    ///
+    /// use image_search::{Cassette, Client};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), image_search::Error> {
+    ///     let client = Client::new().cassette(Cassette::Replay("tests/cassettes".into()));
+    ///     let images = client.search("cats", 10).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn cassette<C: Into<Option<Cassette>>>(self, cassette: C) -> Self {
+        *self
+            .session
+            .cassette
+            .lock()
+            .expect("Cassette lock poisoned") = cassette.into();
+        self
+    }
+
+    /// Opens connections to Google and the thumbnail hosts ahead of time, so the first search made with this client doesn't have to wait for the handshakes.
+    /// Any errors are ignored, since a failed warmup only means the first search will be slower.
+    pub async fn warmup(&self) {
+        let session = self.session.clone();
+        async_std::task::spawn(async move {
+            let requests = WARMUP_HOSTS
+                .iter()
+                .map(|url| session.client.head(url).header("User-Agent", USER_AGENT));
+            futures::future::join_all(requests).await;
+        })
+        .await
+    }
+
+    /// The Google host that this client's searches have been redirected to, such as `www.google.co.uk`.
+    /// Returns `None` if Google hasn't redirected any searches away from `www.google.com`.
+    pub fn search_host(&self) -> Option<String> {
+        self.session.host()
+    }
+
+    /// The default arguments with the given query and limit.
+    pub fn arguments(&self, query: &str, limit: usize) -> Arguments {
+        Arguments {
+            query: query.to_owned(),
+            limit,
+            ..self.defaults.clone()
+        }
+    }
+
+    /// Search for images using the default arguments, returning images up to the provided limit.
+    /// See [`search`](crate::search) for more information.
+    pub async fn search(&self, query: &str, limit: usize) -> SearchResult<ImageList> {
+        self.search_with(self.arguments(query, limit)).await
+    }
+
+    /// Search for images using the provided arguments instead of the defaults, while still sharing the client's connections and cookies.
+    pub async fn search_with(&self, args: Arguments) -> SearchResult<ImageList> {
+        async_std::task::spawn(_search(args, self.session.clone())).await
+    }
+
+    /// Search for images using the default arguments, returning the urls of the images.
+    /// See [`urls`](crate::urls) for more information.
+    pub async fn urls(&self, query: &str, limit: usize) -> SearchResult<Vec<String>> {
+        let args = self.arguments(query, limit);
+        let thumbnails = args.thumbnails;
+        let images = self.search_with(args).await?;
+
+        Ok(images
+            .into_iter()
+            .map(|image| {
+                if thumbnails {
+                    image.thumbnail
+                } else {
+                    image.url
+                }
+            })
+            .collect())
+    }
+
+    /// Search for images using the default arguments and download them.
+    /// See [`download`](crate::download) for more information.
+    pub async fn download(&self, query: &str, limit: usize) -> SearchResult<Vec<PathBuf>> {
+        Ok(self.download_report(query, limit).await?.paths())
+    }
+
+    /// Search for images using the default arguments and download them, returning a `DownloadReport`.
+    /// See [`download_report`](crate::download_report) for more information.
+    pub async fn download_report(&self, query: &str, limit: usize) -> SearchResult<DownloadReport> {
+        self.download_with(self.arguments(query, limit)).await
+    }
+
+    /// Search for images using the provided arguments instead of the defaults and download them, returning a `DownloadReport`.
+    pub async fn download_with(&self, args: Arguments) -> SearchResult<DownloadReport> {
+        async_std::task::spawn(_download(args, self.session.clone())).await
+    }
+
+    /// Search for images using the default arguments, returning a stream that lazily fetches further pages of results.
+    /// See [`search_stream`](crate::search_stream) for more information.
+    pub fn search_stream(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> impl Stream<Item = SearchResult<Image>> {
+        crate::page_stream(self.pages(query, limit))
+    }
+
+    pub(crate) fn pages(&self, query: &str, limit: usize) -> Pages {
+        Pages::new(self.arguments(query, limit), self.session.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_api_keys() {
+        assert_eq!(
+            redacted("https://pixabay.com/api/?key=secret&q=cat&page=2"),
+            "https://pixabay.com/api/?q=cat&page=2"
+        );
+        assert_eq!(
+            redacted("https://serpapi.com/search.json?api_key=secret"),
+            "https://serpapi.com/search.json"
+        );
+        assert_eq!(
+            redacted("https://www.google.com/search?q=key"),
+            "https://www.google.com/search?q=key"
+        );
+    }
+
+    #[test]
+    fn google_hosts() {
+        assert!(is_google_host("www.google.com"));
+        assert!(is_google_host("google.de"));
+        assert!(is_google_host("www.google.co.uk"));
+        assert!(is_google_host("www.google.com.au"));
+        assert!(!is_google_host("google.attacker.com"));
+        assert!(!is_google_host("www.google.com.evil.io"));
+        assert!(!is_google_host("consent.google.com"));
+    }
+
+    #[test]
+    fn google_domains() {
+        assert!(is_google_domain("consent.google.com"));
+        assert!(is_google_domain("www.google.co.uk"));
+        assert!(is_google_domain("google.fr"));
+        assert!(!is_google_domain("google.attacker.com"));
+        assert!(!is_google_domain("x.google.com.evil.io"));
+        assert!(!is_google_domain("notgoogle.com"));
+        assert!(!is_google_domain("google."));
+    }
+
+    #[test]
+    fn cookie_file_only_saves_cookies_google_set() {
+        let path =
+            env::temp_dir().join(format!("image_search_cookies_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let session = Session::default();
+        session.add_cookies("SID=secret");
+        session.set_cookie_file(path.clone());
+        let mut res = surf::http::Response::new(surf::StatusCode::Ok);
+        res.append_header("Set-Cookie", "NID=abc; Path=/; HttpOnly");
+        session.store_cookies(&surf::Response::from(res));
+
+        let saved = fs::read_to_string(&path).unwrap();
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777
+        };
+        let _ = fs::remove_file(&path);
+
+        let saved: HashMap<String, String> = serde_json::from_str(&saved).unwrap();
+        assert_eq!(saved.get("NID").map(String::as_str), Some("abc"));
+        assert!(!saved.contains_key("SID"));
+        assert!(session.cookie_header().contains("SID=secret"));
+        #[cfg(unix)]
+        assert_eq!(mode, 0o600);
+    }
+}
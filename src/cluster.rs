@@ -0,0 +1,124 @@
+//! Grouping results into visually similar clusters by their thumbnails, for the `cluster` function.
+
+extern crate image;
+
+use crate::{color::average_color, fetch_bytes, Image};
+use futures::{stream, StreamExt};
+use std::time::Duration;
+
+/// The maximum number of thumbnails that are fetched at the same time.
+const MAX_CONCURRENT_THUMBNAILS: usize = 16;
+
+/// The largest difference between two thumbnails, from 0 to 1, for them to be put in the same cluster.
+const MAX_DIFFERENCE: f64 = 0.3;
+
+/// The largest possible distance between two colors in RGB space.
+const MAX_COLOR_DISTANCE: f64 = 441.673; // sqrt(3 * 255^2)
+
+/// A group of visually similar images returned by the `cluster` function.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Cluster {
+    /// The image that best represents the cluster, which is the earliest image in it.
+    pub representative: Image,
+    /// All of the images in the cluster, including the representative, in the order they were given.
+    pub images: Vec<Image>,
+}
+
+/// The features of a thumbnail that are compared when clustering.
+#[derive(Clone, Copy)]
+struct Features {
+    /// A difference hash of the thumbnail, capturing its overall structure.
+    hash: u64,
+    /// The average color of the thumbnail.
+    color: [f64; 3],
+}
+
+/// Groups `images` into clusters by comparing the features of their thumbnails to the representative of each cluster.
+pub(crate) async fn cluster(images: Vec<Image>, timeout: Option<Duration>) -> Vec<Cluster> {
+    let thumbnails: Vec<String> = images.iter().map(|image| image.thumbnail.clone()).collect();
+    let features: Vec<Option<Features>> = stream::iter(thumbnails)
+        .map(|thumbnail| thumbnail_features(thumbnail, timeout))
+        .buffered(MAX_CONCURRENT_THUMBNAILS)
+        .collect()
+        .await;
+
+    let mut clusters: Vec<(Features, Cluster)> = Vec::new();
+    let mut unclustered = Vec::new();
+    for (features, image) in features.into_iter().zip(images) {
+        let features = match features {
+            Some(features) => features,
+            None => {
+                unclustered.push(image);
+                continue;
+            }
+        };
+
+        match clusters
+            .iter_mut()
+            .find(|(representative, _)| difference(representative, &features) <= MAX_DIFFERENCE)
+        {
+            Some((_, cluster)) => cluster.images.push(image),
+            None => clusters.push((
+                features,
+                Cluster {
+                    representative: image.clone(),
+                    images: vec![image],
+                },
+            )),
+        }
+    }
+
+    let mut clusters: Vec<Cluster> = clusters.into_iter().map(|(_, cluster)| cluster).collect();
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.images.len()));
+
+    // Images whose thumbnails can't be compared are each given their own cluster
+    clusters.extend(unclustered.into_iter().map(|image| Cluster {
+        representative: image.clone(),
+        images: vec![image],
+    }));
+
+    clusters
+}
+
+/// The features of the thumbnail at `url`, or `None` if it can't be fetched or decoded.
+async fn thumbnail_features(url: String, timeout: Option<Duration>) -> Option<Features> {
+    let buf = fetch_bytes(url, timeout).await.ok()?;
+    Some(Features {
+        hash: difference_hash(&buf)?,
+        color: average_color(&buf)?,
+    })
+}
+
+/// A 64 bit difference hash of the image encoded in `buf`, where each bit is whether a pixel is brighter than the one to its right after shrinking the image to 9x8.
+fn difference_hash(buf: &[u8]) -> Option<u64> {
+    let gray = image::load_from_memory(buf)
+        .ok()?
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+
+    let mut hash = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter = gray.get_pixel(x, y).0[0] > gray.get_pixel(x + 1, y).0[0];
+            hash = (hash << 1) | brighter as u64;
+        }
+    }
+
+    Some(hash)
+}
+
+/// How different two thumbnails are, from 0 (identical) to 1, weighing their structure and color equally.
+fn difference(a: &Features, b: &Features) -> f64 {
+    let structure = (a.hash ^ b.hash).count_ones() as f64 / 64.0;
+    let color = a
+        .color
+        .iter()
+        .zip(b.color)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f64>()
+        .sqrt()
+        / MAX_COLOR_DISTANCE;
+
+    (structure + color) / 2.0
+}
@@ -0,0 +1,73 @@
+//! Re-ranking results by how close the colors of their thumbnails are to a target color, for `Arguments::prefer_color`.
+
+extern crate image;
+
+use crate::{fetch_bytes, Arguments, Image};
+use futures::{stream, StreamExt};
+use std::time::Duration;
+
+/// The maximum number of thumbnails that are fetched at the same time.
+const MAX_CONCURRENT_THUMBNAILS: usize = 16;
+
+/// Orders `imgs` by how close the average color of their thumbnails is to `target`, closest first.
+/// Images whose thumbnails can't be fetched or decoded are moved to the end, keeping their order.
+pub(crate) async fn rank_by_color(
+    imgs: Vec<Image>,
+    target: [u8; 3],
+    args: &Arguments,
+) -> Vec<Image> {
+    let thumbnails: Vec<String> = imgs.iter().map(|image| image.thumbnail.clone()).collect();
+    let distances: Vec<Option<f64>> = stream::iter(thumbnails)
+        .map(|thumbnail| thumbnail_distance(thumbnail, target, args.timeout))
+        .buffered(MAX_CONCURRENT_THUMBNAILS)
+        .collect()
+        .await;
+
+    let mut ranked: Vec<(Option<f64>, Image)> = distances.into_iter().zip(imgs).collect();
+    ranked.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => a.total_cmp(b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+
+    ranked.into_iter().map(|(_, image)| image).collect()
+}
+
+/// The distance between the average color of the thumbnail at `url` and `target`, or `None` if it can't be fetched or decoded.
+async fn thumbnail_distance(
+    url: String,
+    target: [u8; 3],
+    timeout: Option<Duration>,
+) -> Option<f64> {
+    let buf = fetch_bytes(url, timeout).await.ok()?;
+    average_color(&buf).map(|color| distance(color, target))
+}
+
+/// The average color of the pixels in the image encoded in `buf`, or `None` if it can't be decoded.
+pub(crate) fn average_color(buf: &[u8]) -> Option<[f64; 3]> {
+    let rgb = image::load_from_memory(buf).ok()?.to_rgb8();
+    let pixels = (rgb.width() as f64) * (rgb.height() as f64);
+    if pixels == 0.0 {
+        return None;
+    }
+
+    let mut sum = [0.0; 3];
+    for pixel in rgb.pixels() {
+        for (total, channel) in sum.iter_mut().zip(pixel.0) {
+            *total += channel as f64;
+        }
+    }
+
+    Some(sum.map(|total| total / pixels))
+}
+
+/// The distance between two colors in RGB space.
+fn distance(color: [f64; 3], target: [u8; 3]) -> f64 {
+    color
+        .iter()
+        .zip(target)
+        .map(|(a, b)| (a - b as f64).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
@@ -0,0 +1,261 @@
+//! Constructing [`Arguments`] from configuration that can be deserialized with [`serde`](https://crates.io/crates/serde).
+
+extern crate serde;
+
+use crate::providers::Engine;
+use crate::{Arguments, Error, Format, NaiveDate, SafeSearch};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// A deserializable version of [`Arguments`], for taking search arguments from config files or user-facing APIs.
+/// Enums are given as strings matching their variant names, ignoring case, spaces, dashes, and underscores (e.g. `"creative_commons"` for `License::CreativeCommons`).
+/// Durations are given in seconds and dates in `YYYY-MM-DD` format.
+/// A `max_per_host` of `0` removes the limit on downloads from the same host.
+/// `file_mode` and `dir_mode` are accepted but ignored on platforms other than unix.
+/// The `provider` is the name of one of the built in search engines in `providers::Engine`, such as `"bing"`.
+///
+/// # Example
+/// ```
+/// use image_search::{Arguments, ArgumentsConfig};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let config: ArgumentsConfig = serde_json::from_str(r#"{
+///     "query": "cats",
+///     "limit": 10,
+///     "color": "gray",
+///     "formats": ["jpg", "png"]
+/// }"#)?;
+/// assert_eq!(config.query, "cats");
+/// assert_eq!(config.limit, 10);
+///
+/// let args = Arguments::try_from(config)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArgumentsConfig {
+    pub query: String,
+    pub limit: usize,
+    pub thumbnails: bool,
+    pub thumbnails_only: bool,
+    pub shuffle: bool,
+    pub seed: Option<u64>,
+    pub timeout: Option<f64>,
+    pub user_agent: Option<String>,
+    pub deadline: Option<f64>,
+    pub max_retry_wait: Option<f64>,
+    pub max_per_host: Option<usize>,
+    pub host_delay: Option<f64>,
+    pub directory: Option<PathBuf>,
+    #[cfg(unix)]
+    pub file_mode: Option<u32>,
+    #[cfg(unix)]
+    pub dir_mode: Option<u32>,
+    // Permissions only apply on unix, but the same config should still load elsewhere
+    #[cfg(not(unix))]
+    #[serde(rename = "file_mode")]
+    _file_mode: Option<serde::de::IgnoredAny>,
+    #[cfg(not(unix))]
+    #[serde(rename = "dir_mode")]
+    _dir_mode: Option<serde::de::IgnoredAny>,
+    pub session_dirs: bool,
+    pub naming: Option<String>,
+    pub download_order: Option<String>,
+    pub reject_animated: bool,
+    pub allow_svg: Option<bool>,
+    pub head_check: bool,
+    pub archive_fallback: bool,
+    pub save_to_archive: bool,
+    pub resolve_urls: bool,
+    pub min_file_size: Option<u64>,
+    pub max_file_size: Option<u64>,
+    pub parse_dump: Option<PathBuf>,
+    pub provider: Option<String>,
+
+    pub filetype: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub site: Option<String>,
+    pub exclude: Vec<String>,
+    pub block_domains: Vec<String>,
+
+    pub color: Option<String>,
+    pub color_type: Option<String>,
+    pub license: Option<String>,
+    pub image_type: Option<String>,
+    pub time: Option<String>,
+    pub ratio: Option<String>,
+    pub size: Option<String>,
+    pub larger_than: Option<String>,
+    pub safe_search: Option<String>,
+    pub region: Option<String>,
+    pub country_restrict: bool,
+    pub language: Option<String>,
+    pub raw_tbs: Option<String>,
+    pub disable_filtering: bool,
+    pub extra_params: Vec<(String, String)>,
+    pub formats: Vec<String>,
+    pub strict_format: bool,
+    #[cfg(feature = "image")]
+    pub prefer_color: Option<[u8; 3]>,
+    #[cfg(feature = "image")]
+    pub watermarks: Option<String>,
+    #[cfg(feature = "image")]
+    pub max_text_density: Option<f64>,
+}
+
+impl TryFrom<ArgumentsConfig> for Arguments {
+    type Error = Error;
+
+    fn try_from(config: ArgumentsConfig) -> Result<Self, Self::Error> {
+        let mut args = Arguments::new(&config.query, config.limit)
+            .thumbnails(config.thumbnails)
+            .thumbnails_only(config.thumbnails_only)
+            .shuffle(config.shuffle)
+            .seed(config.seed)
+            .session_dirs(config.session_dirs)
+            .reject_animated(config.reject_animated)
+            .head_check(config.head_check)
+            .archive_fallback(config.archive_fallback)
+            .save_to_archive(config.save_to_archive)
+            .resolve_urls(config.resolve_urls)
+            .min_file_size(config.min_file_size)
+            .max_file_size(config.max_file_size)
+            .parse_dump(config.parse_dump)
+            .strict_format(config.strict_format)
+            .country_restrict(config.country_restrict)
+            .disable_filtering(config.disable_filtering);
+
+        #[cfg(unix)]
+        {
+            args = args.file_mode(config.file_mode).dir_mode(config.dir_mode);
+        }
+
+        #[cfg(feature = "image")]
+        {
+            args = args
+                .prefer_color(config.prefer_color)
+                .max_text_density(config.max_text_density);
+            if let Some(watermarks) = config.watermarks {
+                args = args.watermarks(watermarks.parse()?);
+            }
+        }
+
+        if let Some(allow_svg) = config.allow_svg {
+            args = args.allow_svg(allow_svg);
+        }
+        if let Some(timeout) = config.timeout {
+            args = args.timeout(seconds(timeout, "timeout")?);
+        }
+        if let Some(user_agent) = config.user_agent {
+            args = args.user_agent(&user_agent);
+        }
+        if let Some(deadline) = config.deadline {
+            args = args.deadline(seconds(deadline, "deadline")?);
+        }
+        if let Some(max_retry_wait) = config.max_retry_wait {
+            args = args.max_retry_wait(seconds(max_retry_wait, "max_retry_wait")?);
+        }
+        if let Some(max_per_host) = config.max_per_host {
+            args = args.max_per_host(Some(max_per_host).filter(|&max| max > 0));
+        }
+        if let Some(host_delay) = config.host_delay {
+            args = args.host_delay(seconds(host_delay, "host_delay")?);
+        }
+        if let Some(directory) = config.directory {
+            args = args.directory(directory);
+        }
+        if let Some(naming) = config.naming {
+            args = args.naming(naming.parse()?);
+        }
+        if let Some(download_order) = config.download_order {
+            args = args.download_order(download_order.parse()?);
+        }
+        if let Some(provider) = config.provider {
+            args = args.provider(provider.parse::<Engine>()?);
+        }
+
+        if let Some(filetype) = config.filetype {
+            args = args.filetype(&filetype);
+        }
+        if let Some(before) = config.before {
+            args = args.before(date(&before, "before")?);
+        }
+        if let Some(after) = config.after {
+            args = args.after(date(&after, "after")?);
+        }
+        if let Some(site) = config.site {
+            args = args.site(&site);
+        }
+        let exclude: Vec<&str> = config.exclude.iter().map(String::as_str).collect();
+        args = args.exclude(&exclude);
+        let block_domains: Vec<&str> = config.block_domains.iter().map(String::as_str).collect();
+        args = args.block_domains(&block_domains);
+
+        if let Some(color) = config.color {
+            args = args.color(color.parse()?);
+        }
+        if let Some(color_type) = config.color_type {
+            args = args.color_type(color_type.parse()?);
+        }
+        if let Some(license) = config.license {
+            args = args.license(license.parse()?);
+        }
+        if let Some(image_type) = config.image_type {
+            args = args.image_type(image_type.parse()?);
+        }
+        if let Some(time) = config.time {
+            args = args.time(time.parse()?);
+        }
+        if let Some(ratio) = config.ratio {
+            args = args.ratio(ratio.parse()?);
+        }
+        if let Some(size) = config.size {
+            args = args.size(size.parse()?);
+        }
+        if let Some(larger_than) = config.larger_than {
+            args = args.larger_than(larger_than.parse()?);
+        }
+        if let Some(safe_search) = config.safe_search {
+            args = args.safe_search(safe_search.parse::<SafeSearch>()?);
+        }
+        if let Some(region) = config.region {
+            args = args.region(&region);
+        }
+        if let Some(language) = config.language {
+            args = args.language(&language);
+        }
+        if let Some(raw_tbs) = config.raw_tbs {
+            args = args.raw_tbs(&raw_tbs);
+        }
+        for (name, value) in config.extra_params.iter() {
+            args = args.extra_param(name, value);
+        }
+        let formats = config
+            .formats
+            .iter()
+            .map(|format| format.parse())
+            .collect::<Result<Vec<Format>, Error>>()?;
+        args = args.formats(&formats);
+
+        args.validate()?;
+
+        Ok(args)
+    }
+}
+
+/// Converts a number of seconds from a config into a `Duration`.
+fn seconds(secs: f64, field: &str) -> Result<Duration, Error> {
+    Duration::try_from_secs_f64(secs).map_err(|_| {
+        Error::InvalidArguments(format!("{} must be a positive number of seconds", field))
+    })
+}
+
+/// Parses a `YYYY-MM-DD` date from a config.
+fn date(date: &str, field: &str) -> Result<NaiveDate, Error> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+        Error::InvalidArguments(format!("{} must be a date in YYYY-MM-DD format", field))
+    })
+}
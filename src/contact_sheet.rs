@@ -0,0 +1,131 @@
+//! Composing downloaded images into a single labelled grid, for reviewing a download at a glance.
+
+extern crate image;
+
+use image::{imageops, Rgb, RgbImage};
+use std::path::Path;
+
+/// The width and height in pixels of each cell of a contact sheet.
+const CELL_SIZE: u32 = 160;
+
+/// The space in pixels between cells and around the edge of a contact sheet.
+const PADDING: u32 = 8;
+
+/// How many pixels each pixel of the label font is drawn as.
+const LABEL_SCALE: u32 = 2;
+
+const BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+const PLACEHOLDER: Rgb<u8> = Rgb([200, 200, 200]);
+const LABEL_BACKGROUND: Rgb<u8> = Rgb([0, 0, 0]);
+const LABEL_FOREGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+
+/// A 3x5 pixel font for the digits 0-9, with each row stored in the lowest 3 bits from left to right.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// Composes the images at `paths` into a grid with `columns` columns, labelling each cell with the index of its image.
+/// Each image is scaled down to fit a 160x160 cell, and images that can't be opened are shown as a gray cell so that the indices still line up.
+/// Requires the `image` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate tokio;
+/// extern crate image_search;
+///
+/// use image_search::{Arguments, contact_sheet, download};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let paths = download(Arguments::new("example", 100)).await?;
+///     contact_sheet(&paths, 10).save("contact_sheet.png")?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn contact_sheet<P: AsRef<Path>>(paths: &[P], columns: usize) -> RgbImage {
+    let columns = columns.clamp(1, paths.len().max(1)) as u32;
+    let rows = (paths.len() as u32).div_ceil(columns).max(1);
+    let mut sheet = RgbImage::from_pixel(
+        columns * (CELL_SIZE + PADDING) + PADDING,
+        rows * (CELL_SIZE + PADDING) + PADDING,
+        BACKGROUND,
+    );
+
+    for (index, path) in paths.iter().enumerate() {
+        let x = PADDING + (index as u32 % columns) * (CELL_SIZE + PADDING);
+        let y = PADDING + (index as u32 / columns) * (CELL_SIZE + PADDING);
+
+        match image::open(path) {
+            Ok(image) => {
+                let thumbnail = image.thumbnail(CELL_SIZE, CELL_SIZE).to_rgb8();
+                // Centered within the cell, since the thumbnail keeps the aspect ratio of the image
+                let offset_x = (CELL_SIZE - thumbnail.width()) / 2;
+                let offset_y = (CELL_SIZE - thumbnail.height()) / 2;
+                imageops::replace(
+                    &mut sheet,
+                    &thumbnail,
+                    (x + offset_x) as i64,
+                    (y + offset_y) as i64,
+                );
+            }
+            Err(_) => fill(&mut sheet, x, y, CELL_SIZE, CELL_SIZE, PLACEHOLDER),
+        }
+
+        draw_label(&mut sheet, x, y, index);
+    }
+
+    sheet
+}
+
+/// Draws `index` in the top left corner of the cell at (`x`, `y`), on a background so that it can be read over any image.
+fn draw_label(sheet: &mut RgbImage, x: u32, y: u32, index: usize) {
+    let digits: Vec<usize> = index
+        .to_string()
+        .bytes()
+        .map(|digit| (digit - b'0') as usize)
+        .collect();
+
+    // Each digit is 3 pixels wide with 1 pixel of space between them and around the edge
+    let width = (digits.len() as u32 * 4 + 1) * LABEL_SCALE;
+    let height = 7 * LABEL_SCALE;
+    fill(sheet, x, y, width, height, LABEL_BACKGROUND);
+
+    for (position, digit) in digits.into_iter().enumerate() {
+        let left = x + (position as u32 * 4 + 1) * LABEL_SCALE;
+        let top = y + LABEL_SCALE;
+        for (row, bits) in DIGITS[digit].iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) != 0 {
+                    fill(
+                        sheet,
+                        left + column * LABEL_SCALE,
+                        top + row as u32 * LABEL_SCALE,
+                        LABEL_SCALE,
+                        LABEL_SCALE,
+                        LABEL_FOREGROUND,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Fills a rectangle of `sheet` with `color`, clipping it to the edges of the sheet.
+fn fill(sheet: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+    for py in y..(y + height).min(sheet.height()) {
+        for px in x..(x + width).min(sheet.width()) {
+            sheet.put_pixel(px, py, color);
+        }
+    }
+}
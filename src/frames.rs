@@ -0,0 +1,52 @@
+//! Saving the frames of downloaded GIFs as PNGs, for `Arguments::extract_frames`.
+
+extern crate image;
+
+use crate::{DownloadError, Frames};
+use image::codecs::gif::GifDecoder;
+use image::AnimationDecoder;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// Saves the selected frames of the GIF in `buf` next to `path`, named like `cats0_frame0.png`, returning the paths of the frames.
+/// Returns no frames if `buf` isn't a GIF.
+pub(crate) fn extract_frames(
+    buf: &[u8],
+    path: &Path,
+    frames: Frames,
+) -> Result<Vec<PathBuf>, DownloadError> {
+    if !buf.starts_with(b"GIF8") {
+        return Ok(Vec::new());
+    }
+
+    let step = match frames {
+        Frames::First => usize::MAX,
+        Frames::EveryNth(n) => n.max(1),
+    };
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let decoder = GifDecoder::new(Cursor::new(buf)).map_err(|_| DownloadError::Extension)?;
+    let mut paths = Vec::new();
+    for (i, frame) in decoder.into_frames().enumerate() {
+        if i % step != 0 {
+            continue;
+        }
+
+        let frame = frame.map_err(|_| DownloadError::Extension)?;
+        let frame_path = path.with_file_name(format!("{}_frame{}.png", stem, i));
+        frame
+            .into_buffer()
+            .save(&frame_path)
+            .map_err(|err| DownloadError::Fs(std::io::Error::other(err)))?;
+        paths.push(frame_path);
+
+        if matches!(frames, Frames::First) {
+            break;
+        }
+    }
+
+    Ok(paths)
+}
@@ -0,0 +1,164 @@
+//! A list of search results with helpers for the filtering that is commonly done on them.
+
+use crate::{on_domains, rng, Image, NaiveDate};
+use rand::seq::SliceRandom;
+use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
+
+/// The images returned by a search, which can be used just like a `Vec<Image>`.
+/// Adds helpers for filtering and transforming the results, which can be chained together.
+///
+/// # Example
+/// ```no_run
+/// extern crate tokio;
+/// extern crate image_search;
+///
+/// use image_search::{Arguments, search};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), image_search::Error> {
+///     let urls = search(Arguments::new("example", 100))
+///         .await?
+///         .min_dimensions(1920, 1080)
+///         .from_domains(&["wikimedia.org"])
+///         .take_random(10)
+///         .urls();
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ImageList {
+    images: Vec<Image>,
+    seed: Option<u64>,
+}
+
+impl ImageList {
+    /// Sets the seed used by `ImageList::take_random`, making the sample reproducible.
+    /// Lists returned by a search use the seed set with `Arguments::seed`.
+    pub fn seed<S: Into<Option<u64>>>(mut self, seed: S) -> Self {
+        self.seed = seed.into();
+        self
+    }
+
+    /// Unwraps the list into the `Vec<Image>` it contains.
+    pub fn into_inner(self) -> Vec<Image> {
+        self.images
+    }
+
+    /// Keeps only the images that are at least `width` pixels wide and `height` pixels tall, according to the dimensions reported by Google.
+    pub fn min_dimensions(self, width: i64, height: i64) -> Self {
+        self.filter(|image| image.width >= width && image.height >= height)
+    }
+
+    /// Keeps only the images whose url is on one of the given domains or any of their subdomains.
+    /// For example, `"wikimedia.org"` matches both `wikimedia.org` and `upload.wikimedia.org`.
+    pub fn from_domains(self, domains: &[&str]) -> Self {
+        self.filter(|image| on_domains(&image.url, domains))
+    }
+
+    /// Removes any images with the same url as an earlier image, keeping the order of the rest.
+    pub fn dedup_by_url(self) -> Self {
+        let mut seen = HashSet::new();
+        self.filter(|image| seen.insert(image.url.clone()))
+    }
+
+    /// Keeps only the images published on or after `after` and on or before `before`, either of which can be `None` to leave that side open.
+    /// Images without a known publish date are removed, which includes every result from Google, since it doesn't include dates.
+    pub fn published_between(self, after: Option<NaiveDate>, before: Option<NaiveDate>) -> Self {
+        self.filter(|image| match image.published {
+            Some(published) => {
+                let date = published.date_naive();
+                after.is_none_or(|after| date >= after)
+                    && before.is_none_or(|before| date <= before)
+            }
+            None => false,
+        })
+    }
+
+    /// Sorts the images from the most recently published to the least, with the images without a known publish date at the end.
+    pub fn newest_first(mut self) -> Self {
+        self.images
+            .sort_by_key(|image| std::cmp::Reverse(image.published));
+        self
+    }
+
+    /// Keeps `n` images chosen at random, or all of them (shuffled) if there are fewer than `n`.
+    /// The choice is reproducible if the list has a seed.
+    pub fn take_random(mut self, n: usize) -> Self {
+        self.images.shuffle(&mut rng(self.seed));
+        self.images.truncate(n);
+        self
+    }
+
+    /// The urls of the images.
+    pub fn urls(&self) -> Vec<String> {
+        self.images.iter().map(|image| image.url.clone()).collect()
+    }
+
+    /// The urls of the thumbnails of the images.
+    pub fn thumbnails(&self) -> Vec<String> {
+        self.images
+            .iter()
+            .map(|image| image.thumbnail.clone())
+            .collect()
+    }
+
+    fn filter<F: FnMut(&Image) -> bool>(mut self, f: F) -> Self {
+        self.images.retain(f);
+        self
+    }
+}
+
+impl Deref for ImageList {
+    type Target = Vec<Image>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.images
+    }
+}
+
+impl DerefMut for ImageList {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.images
+    }
+}
+
+impl From<Vec<Image>> for ImageList {
+    fn from(images: Vec<Image>) -> Self {
+        ImageList { images, seed: None }
+    }
+}
+
+impl From<ImageList> for Vec<Image> {
+    fn from(list: ImageList) -> Self {
+        list.images
+    }
+}
+
+impl FromIterator<Image> for ImageList {
+    fn from_iter<I: IntoIterator<Item = Image>>(iter: I) -> Self {
+        ImageList {
+            images: iter.into_iter().collect(),
+            seed: None,
+        }
+    }
+}
+
+impl IntoIterator for ImageList {
+    type Item = Image;
+    type IntoIter = std::vec::IntoIter<Image>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.images.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ImageList {
+    type Item = &'a Image;
+    type IntoIter = std::slice::Iter<'a, Image>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.images.iter()
+    }
+}
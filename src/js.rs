@@ -0,0 +1,67 @@
+//! Functions exported to JavaScript with [`wasm-bindgen`](https://crates.io/crates/wasm-bindgen), for using the crate from Node or the browser.
+//! Enabled by the optional `js` feature, which is usually combined with the `wasm` backend.
+//!
+//! Each function returns a `Promise`, which resolves to an array of results or rejects with the error message:
+//! ```js
+//! import { search, urls } from "image_search";
+//!
+//! const images = await search("cats", 10);
+//! console.log(images[0].url, images[0].width, images[0].height);
+//! ```
+
+extern crate js_sys;
+extern crate wasm_bindgen;
+
+use crate::{Arguments, Image};
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+/// Search for `query` and resolve to an array of image objects, with the same fields as [`Image`].
+#[wasm_bindgen(js_name = search)]
+pub async fn js_search(query: String, limit: usize) -> Result<Array, JsValue> {
+    let images = crate::search(Arguments::new(&query, limit))
+        .await
+        .map_err(to_js_error)?;
+
+    images.iter().map(image_object).collect()
+}
+
+/// Search for `query` and resolve to an array of image urls.
+#[wasm_bindgen(js_name = urls)]
+pub async fn js_urls(query: String, limit: usize) -> Result<Array, JsValue> {
+    let urls = crate::urls(Arguments::new(&query, limit))
+        .await
+        .map_err(to_js_error)?;
+
+    Ok(urls.into_iter().map(JsValue::from).collect())
+}
+
+/// Converts an `Image` into a plain JavaScript object.
+fn image_object(image: &Image) -> Result<JsValue, JsValue> {
+    let object = Object::new();
+    Reflect::set(&object, &"url".into(), &image.url.as_str().into())?;
+    Reflect::set(&object, &"width".into(), &(image.width as f64).into())?;
+    Reflect::set(&object, &"height".into(), &(image.height as f64).into())?;
+    Reflect::set(
+        &object,
+        &"thumbnail".into(),
+        &image.thumbnail.as_str().into(),
+    )?;
+    Reflect::set(&object, &"source".into(), &image.source.as_str().into())?;
+    if let Some(resolved_url) = &image.resolved_url {
+        Reflect::set(
+            &object,
+            &"resolvedUrl".into(),
+            &resolved_url.as_str().into(),
+        )?;
+    }
+    if let Some(license) = &image.license {
+        Reflect::set(&object, &"license".into(), &license.as_str().into())?;
+    }
+
+    Ok(object.into())
+}
+
+fn to_js_error(err: crate::Error) -> JsValue {
+    js_sys::Error::new(&err.to_string()).into()
+}
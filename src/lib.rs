@@ -1,27 +1,27 @@
 //! A crate designed to search Google Images based on provided arguments.
-//! Due to the limitations of using only a single request to fetch images, only a max of about 100 images can be found per request.
+//! Each page of results contains about 100 images, so larger limits require multiple requests.
 //! These images may be protected under copyright, and you shouldn't do anything punishable with them, like using them for commercial use.
 //!
 //! # Examples
 //! Using the asynchronous API requires some sort of async runtime, usually [`tokio`](https://crates.io/crates/tokio), which can be added to your `Cargo.toml` like so:
-//! ```
+//! ```toml
 //! [dependencies]
-//! image_search = "0.4"
+//! image_search = "0.5"
 //! tokio = { version = "1", features = ["full"] }
 //! ```
 //! It can be used like this:
-//! ```
+//! ```no_run
 //! extern crate tokio;
 //! extern crate image_search;
 //!
-//! use std::path::PathBuf;
+//! use std::path::{Path, PathBuf};
 //! use image_search::{Arguments, urls, search, download};
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), image_search::Error> {
 //!     let args = Arguments::new("example", 10)
 //!         .color(image_search::Color::Gray)
-//!         .directory(PathBuf::new("downloads")); // Only affects the download function
+//!         .directory(PathBuf::from("downloads")); // Only affects the download function
 //!     
 //!     // Returns the urls of the search results
 //!     let _image_urls = urls(args.clone()).await?;
@@ -36,15 +36,15 @@
 //!
 //! # Blocking
 //! There is an optional "blocking" API that can be enabled:
-//! ```
+//! ```toml
 //! [dependencies]
-//! image_search = { version = "0.4", features = ["blocking"] }
+//! image_search = { version = "0.5", features = ["blocking"] }
 //! ```
 //! This is called like so:
-//! ```
+//! ```ignore
 //! extern crate image_search;
 //!
-//! use std::path::PathBuf;
+//! use std::path::{Path, PathBuf};
 //! use image_search::{Arguments, blocking::{urls, search, download}};
 //!
 //! fn main() -> Result<(), image_search::Error> {
@@ -60,35 +60,97 @@
 //! }
 //! ```
 
+pub mod aggregate;
 #[cfg(feature = "blocking")]
 pub mod blocking;
+#[cfg(feature = "browser")]
+mod browser;
+mod client;
+#[cfg(feature = "image")]
+mod cluster;
+#[cfg(feature = "image")]
+mod color;
+#[cfg(feature = "serde")]
+mod config;
+#[cfg(feature = "image")]
+mod contact_sheet;
+#[cfg(feature = "image")]
+mod frames;
+mod image_list;
+#[cfg(feature = "js")]
+pub mod js;
+pub mod providers;
+mod query;
+pub mod quick;
+mod reputation;
+mod storage;
+#[cfg(feature = "image")]
+mod text;
+#[cfg(feature = "image")]
+mod watermark;
+
+use client::Session;
+pub use client::{Cassette, Client};
+#[cfg(feature = "image")]
+pub use cluster::Cluster;
+#[cfg(feature = "serde")]
+pub use config::ArgumentsConfig;
+#[cfg(feature = "image")]
+pub use contact_sheet::contact_sheet;
+pub use image_list::ImageList;
+use providers::{Engine, Provider};
+pub use providers::{PageRequest, SearchProvider};
+pub use query::Query;
+pub use storage::{FileSink, Location, MemorySink, Metadata, StorageSink};
 
 extern crate async_std;
+extern crate base64;
+extern crate chrono;
 extern crate futures;
-extern crate glob;
+#[cfg(feature = "image")]
+extern crate image;
+extern crate imagesize;
 extern crate infer;
+extern crate rand;
+extern crate regex;
 extern crate serde_json;
+extern crate sha2;
 extern crate surf;
 
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env;
 use std::fmt;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use std::path::{Path, PathBuf};
 
-use std::fs::File;
-use std::path::PathBuf;
+pub use chrono::{DateTime, NaiveDate, Utc};
 
-use futures::future;
-use std::io::{self, Write};
+use base64::Engine as _;
+use futures::{future, stream, Stream, StreamExt};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use sha2::{Digest, Sha256};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
+/// The maximum length of a query (including search operators) that will be sent to Google.
+const MAX_QUERY_LENGTH: usize = 2048;
+
+/// Reads the default timeout in seconds from the `IMAGE_SEARCH_TIMEOUT` environment variable.
+fn env_timeout() -> Option<Duration> {
+    let secs: f64 = env::var("IMAGE_SEARCH_TIMEOUT").ok()?.trim().parse().ok()?;
+    Duration::try_from_secs_f64(secs).ok()
+}
+
 /// Used to construct the arguments for searching and downloading images.
 ///
 /// # Example
-/// ```
+/// ```no_run
 /// extern crate tokio;
 /// extern crate image_search;
 ///
-/// use image_search::{self, Arguments};
+/// use image_search::Arguments;
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), image_search::Error> {
@@ -96,14 +158,47 @@ use std::sync::{Arc, Mutex};
 ///         .color(image_search::Color::Black)
 ///         .ratio(image_search::Ratio::Square);
 ///     let images = image_search::search(args).await?;
+///
+///     Ok(())
 /// }
+/// ```
 #[derive(Debug, Clone)]
 pub struct Arguments {
     query: String,
+    operators: Vec<String>,
     limit: usize,
     thumbnails: bool,
+    thumbnails_only: bool,
+    shuffle: bool,
+    seed: Option<u64>,
     timeout: Option<Duration>,
+    user_agent: Option<String>,
+    deadline: Option<Duration>,
+    max_retry_wait: Option<Duration>,
+    max_per_host: Option<usize>,
+    host_delay: Option<Duration>,
     directory: Option<PathBuf>,
+    #[cfg(unix)]
+    file_mode: Option<u32>,
+    #[cfg(unix)]
+    dir_mode: Option<u32>,
+    session_dirs: bool,
+    naming: Naming,
+    download_order: DownloadOrder,
+    reject_animated: bool,
+    allow_svg: bool,
+    head_check: bool,
+    archive_fallback: bool,
+    save_to_archive: bool,
+    resolve_urls: bool,
+    min_file_size: Option<u64>,
+    max_file_size: Option<u64>,
+    parse_dump: Option<PathBuf>,
+    on_event: Option<EventCallback>,
+    rank_by: Option<RankFn>,
+    storage: Option<Storage>,
+    faces: Option<(Faces, FaceDetector)>,
+    provider: Provider,
 
     color: Color,
     color_type: ColorType,
@@ -111,64 +206,429 @@ pub struct Arguments {
     image_type: ImageType,
     time: Time,
     ratio: Ratio,
-    format: Format,
+    size: Size,
+    larger_than: MinSize,
+    region: Option<String>,
+    country_restrict: bool,
+    language: Option<String>,
+    raw_tbs: Option<String>,
+    disable_filtering: bool,
+    blocked_domains: Vec<String>,
+    extra_params: Vec<(String, String)>,
+    safe_search: Option<SafeSearch>,
+    formats: Vec<Format>,
+    strict_format: bool,
+    #[cfg(feature = "image")]
+    prefer_color: Option<[u8; 3]>,
+    #[cfg(feature = "image")]
+    extract_frames: Option<Frames>,
+    #[cfg(feature = "image")]
+    keep_gif: bool,
+    #[cfg(feature = "image")]
+    watermarks: Watermarks,
+    #[cfg(feature = "image")]
+    max_text_density: Option<f64>,
+}
+
+/// Arguments with an empty query and no limit, which must have a query set with `Arguments::query` before they can be used.
+impl Default for Arguments {
+    fn default() -> Self {
+        Arguments::new("", 0)
+    }
 }
 
 impl Arguments {
-    fn params(&self) -> String {
-        let split = &String::from("%2C");
-        let mut params_str = String::new();
-
-        let color = self.color.param();
-        let color_type = self.color_type.param();
-        let license = self.license.param();
-        let image_type = self.image_type.param();
-        let time = self.time.param();
-        let ratio = self.ratio.param();
-        let format = self.format.param();
-        let params = [color, color_type, license, image_type, time, ratio, format];
-
-        for param in params.iter() {
-            if param.len() > 1 {
-                params_str += split;
-                params_str += param;
+    /// Checks that the query can be sent to Google, returning the reason if it can't.
+    pub(crate) fn validate(&self) -> SearchResult<()> {
+        let invalid = |reason: &str| Err(Error::InvalidArguments(reason.to_owned()));
+
+        if self.query.trim().is_empty() {
+            return invalid("query cannot be empty");
+        }
+        if self.full_query().chars().count() > MAX_QUERY_LENGTH {
+            return invalid("query is too long");
+        }
+        if self.query.chars().any(char::is_control) {
+            return invalid("query cannot contain control characters");
+        }
+        let Provider(provider) = &self.provider;
+        let max_limit = MAX_PAGES * provider.page_size().max(1);
+        if self.limit > max_limit {
+            return Err(Error::InvalidArguments(format!(
+                "limit cannot be more than {} ({} pages of results)",
+                max_limit, MAX_PAGES
+            )));
+        }
+        if let Some(region) = &self.region {
+            if region.len() != 2 || !region.chars().all(|c| c.is_ascii_alphabetic()) {
+                return invalid("region must be a two letter country code");
+            }
+        }
+        if let Some(language) = &self.language {
+            if language.is_empty()
+                || !language
+                    .chars()
+                    .all(|c| c.is_ascii_alphabetic() || c == '-')
+            {
+                return invalid("language must be a language code such as `ja` or `zh-CN`");
             }
         }
 
-        params_str
+        Ok(())
+    }
+
+    /// Calls the event callback with `event`, if there is one.
+    fn emit(&self, event: DownloadEvent) {
+        if let Some(EventCallback(callback)) = &self.on_event {
+            callback(&event);
+        }
+    }
+
+    /// Whether the url or source of `image` is on one of the domains blocked with `Arguments::block_domains`.
+    fn is_blocked(&self, image: &Image) -> bool {
+        !self.blocked_domains.is_empty()
+            && (on_domains(&image.url, &self.blocked_domains)
+                || on_domains(&image.source, &self.blocked_domains))
+    }
+
+    /// The query with all of the search operators appended.
+    fn full_query(&self) -> String {
+        let mut query = self.query.clone();
+        for operator in self.operators.iter() {
+            query.push(' ');
+            query += operator;
+        }
+
+        query
     }
 
+    /// Creates the arguments for searching for `query`, fetching at most `limit` images.
+    /// At most 10 pages of results are fetched, so searches fail with `Error::InvalidArguments` if the limit is more than 10 pages hold (1000 images for Google).
     pub fn new(query: &str, limit: usize) -> Arguments {
         Arguments {
             query: query.to_owned(),
+            operators: Vec::new(),
             limit,
             thumbnails: false,
-            timeout: Some(Duration::from_secs(20)),
+            thumbnails_only: false,
+            shuffle: false,
+            seed: None,
+            timeout: Some(env_timeout().unwrap_or(Duration::from_secs(20))),
+            user_agent: None,
+            deadline: None,
+            max_retry_wait: Some(Duration::from_secs(30)),
+            max_per_host: Some(4),
+            host_delay: None,
 
             directory: None,
+            #[cfg(unix)]
+            file_mode: None,
+            #[cfg(unix)]
+            dir_mode: None,
+            session_dirs: false,
+            naming: Naming::Query,
+            download_order: DownloadOrder::AsReturned,
+            reject_animated: false,
+            allow_svg: true,
+            head_check: false,
+            archive_fallback: false,
+            save_to_archive: false,
+            resolve_urls: false,
+            min_file_size: None,
+            max_file_size: None,
+            parse_dump: None,
+            on_event: None,
+            rank_by: None,
+            storage: None,
+            faces: None,
+            provider: Provider::new(providers::Google),
             color: Color::None,
             color_type: ColorType::None,
             license: License::None,
             image_type: ImageType::None,
             time: Time::None,
             ratio: Ratio::None,
-            format: Format::None,
+            size: Size::None,
+            larger_than: MinSize::None,
+            region: None,
+            country_restrict: false,
+            language: None,
+            raw_tbs: None,
+            disable_filtering: false,
+            blocked_domains: Vec::new(),
+            extra_params: Vec::new(),
+            safe_search: None,
+            formats: Vec::new(),
+            strict_format: false,
+            #[cfg(feature = "image")]
+            prefer_color: None,
+            #[cfg(feature = "image")]
+            extract_frames: None,
+            #[cfg(feature = "image")]
+            keep_gif: true,
+            #[cfg(feature = "image")]
+            watermarks: Watermarks::Ignore,
+            #[cfg(feature = "image")]
+            max_text_density: None,
         }
     }
 
+    /// Creates the arguments for searching for a structured `Query`, such as one with exact phrases or `OR` alternatives.
+    pub fn from_query(query: &Query, limit: usize) -> Arguments {
+        Arguments::new(&query.to_string(), limit)
+    }
+
+    /// Sets the keyword(s) to search for, replacing the query given to `Arguments::new`.
+    pub fn query(mut self, query: &str) -> Self {
+        self.query = query.to_owned();
+        self
+    }
+
+    /// Sets the maximum amount of images to fetch, replacing the limit given to `Arguments::new`.
+    /// A limit of 0 is the same as `Arguments::no_limit`, and limits over 10 pages of results (1000 images for Google) are rejected like in `Arguments::new`.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Removes the limit, so that pages of results are fetched one at a time until Google stops returning new images.
+    /// As a safety cap, at most 10 pages (about 1000 images) are fetched. The `download` function downloads every image found.
+    pub fn no_limit(mut self) -> Self {
+        self.limit = 0;
+        self
+    }
+
     /// Sets the directory the images will be downloaded to. Only used in the download function.
     pub fn directory<P: Into<PathBuf>>(mut self, dir: P) -> Self {
         self.directory = Some(dir.into());
         self
     }
 
-    /// Sets the optional request timeout for the `download` function. Defaults to 20 seconds.
+    /// Sets the permissions of the files written by the `download` function, such as `0o640`, instead of leaving them to the umask.
+    /// Only applies when writing to the filesystem. Only available on Unix.
+    #[cfg(unix)]
+    pub fn file_mode<M: Into<Option<u32>>>(mut self, mode: M) -> Self {
+        self.file_mode = mode.into();
+        self
+    }
+
+    /// Sets the permissions of the directories created by the `download` function, such as `0o750`, instead of leaving them to the umask.
+    /// Only available on Unix.
+    #[cfg(unix)]
+    pub fn dir_mode<M: Into<Option<u32>>>(mut self, mode: M) -> Self {
+        self.dir_mode = mode.into();
+        self
+    }
+
+    /// Determines whether each call to `download` writes into its own session directory, located at `<directory>/<query>/<timestamp>/`.
+    /// The timestamp is the UTC time the download started in RFC 3339 format, with `-` in place of `:` so that it is a valid path on every platform (e.g. `2024-01-31T12-00-00Z`).
+    pub fn session_dirs(mut self, session_dirs: bool) -> Self {
+        self.session_dirs = session_dirs;
+        self
+    }
+
+    /// Sets the order in which the `download` function tries the images it found. Defaults to `DownloadOrder::AsReturned`.
+    pub fn download_order(mut self, order: DownloadOrder) -> Self {
+        self.download_order = order;
+        self
+    }
+
+    /// Sets how downloaded files are named. Defaults to `Naming::Query`.
+    pub fn naming(mut self, naming: Naming) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    /// Saves frames of each downloaded GIF as PNG files next to it, named like `cats0_frame0.png`. Their paths are stored in the `frames` field of each `DownloadedFile`.
+    /// Only available with the `image` feature.
+    #[cfg(feature = "image")]
+    pub fn extract_frames<F: Into<Option<Frames>>>(mut self, frames: F) -> Self {
+        self.extract_frames = frames.into();
+        self
+    }
+
+    /// Determines whether GIFs are kept after their frames are extracted with `Arguments::extract_frames`. Defaults to `true`.
+    /// If they aren't kept, the `path` of each `DownloadedFile` is the first frame instead. Only available with the `image` feature.
+    #[cfg(feature = "image")]
+    pub fn keep_gif(mut self, keep_gif: bool) -> Self {
+        self.keep_gif = keep_gif;
+        self
+    }
+
+    /// Determines whether SVG files are downloaded (and saved with the `.svg` extension) or skipped. Defaults to `true`.
+    pub fn allow_svg(mut self, allow_svg: bool) -> Self {
+        self.allow_svg = allow_svg;
+        self
+    }
+
+    /// Determines whether the `download` function checks each image for a stock photo watermark, and whether it flags or skips the images that seem to have one.
+    /// The check is a heuristic: images from stock photo sites are assumed to be watermarked, and the rest are checked for dense text or logos in the corners, center, and bottom edge.
+    /// Defaults to `Watermarks::Ignore`. Requires the `image` feature.
+    #[cfg(feature = "image")]
+    pub fn watermarks(mut self, watermarks: Watermarks) -> Self {
+        self.watermarks = watermarks;
+        self
+    }
+
+    /// Sets the largest fraction of an image, from 0 to 1, that can be covered in text for the `download` function to keep it, skipping memes, screenshots, and slides.
+    /// The amount of text is estimated with a heuristic that looks for sharp, two-tone contrast like letters on a background, so photos with a lot of fine detail can score higher than expected.
+    /// Around `0.25` works well for building photo datasets. Defaults to `None`. Requires the `image` feature.
+    #[cfg(feature = "image")]
+    pub fn max_text_density<D: Into<Option<f64>>>(mut self, density: D) -> Self {
+        self.max_text_density = density.into();
+        self
+    }
+
+    /// Determines whether animated GIF, WebP, and PNG (APNG) files are skipped by the `download` function, regardless of the image type being searched for.
+    pub fn reject_animated(mut self, reject_animated: bool) -> Self {
+        self.reject_animated = reject_animated;
+        self
+    }
+
+    /// Determines whether a HEAD request is made before downloading each image, skipping urls that aren't images or are outside of the file size limits.
+    /// Saves downloading error pages and very large files, but some hosts don't handle HEAD requests properly, so it is disabled by default.
+    pub fn head_check(mut self, head_check: bool) -> Self {
+        self.head_check = head_check;
+        self
+    }
+
+    /// Determines whether the `download` function looks for an archived copy of images whose urls return `404 Not Found` or `410 Gone`,
+    /// trying the Wayback Machine before moving on to the next image.
+    /// Files downloaded from an archive have the archive recorded in the `archive` field of their `DownloadedFile`. Disabled by default.
+    pub fn archive_fallback(mut self, archive_fallback: bool) -> Self {
+        self.archive_fallback = archive_fallback;
+        self
+    }
+
+    /// Determines whether the `download` function asks the Wayback Machine to save a snapshot of the url of each image it downloads,
+    /// so that where the images came from can still be checked after the original urls stop working.
+    /// Whether the snapshot was requested successfully is recorded in the `archived` field of each `DownloadedFile`. Disabled by default.
+    pub fn save_to_archive(mut self, save_to_archive: bool) -> Self {
+        self.save_to_archive = save_to_archive;
+        self
+    }
+
+    /// Sets the minimum size in bytes of the files the `download` function will save.
+    pub fn min_file_size<S: Into<Option<u64>>>(mut self, size: S) -> Self {
+        self.min_file_size = size.into();
+        self
+    }
+
+    /// Sets the maximum size in bytes of the files the `download` function will save.
+    pub fn max_file_size<S: Into<Option<u64>>>(mut self, size: S) -> Self {
+        self.max_file_size = size.into();
+        self
+    }
+
+    /// Sets a callback that is called with each `DownloadEvent` while the `download` function is running, such as to display a progress bar.
+    /// The callback may be called from multiple threads, so it should return quickly.
+    pub fn on_event<F: Fn(&DownloadEvent) + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        self.on_event = Some(EventCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Sets where the `download` function stores the files it downloads, instead of writing them to the filesystem.
+    /// The paths in the results are still worked out from `Arguments::directory`, and are passed to the sink as the names of the files.
+    /// Extracting frames with `Arguments::extract_frames` is only supported when writing to the filesystem.
+    pub fn storage<S: StorageSink + 'static>(mut self, sink: S) -> Self {
+        self.storage = Some(Storage(Arc::new(sink)));
+        self
+    }
+
+    /// Filters downloaded images by the number of faces in them, counted by `detector`, such as to build a dataset without any faces in it.
+    /// `detector` is given the contents of each file and returns the number of faces it found, or `None` if it couldn't check the file, in which case the file is skipped.
+    /// No detector is built in, so one from a crate such as `rustface` has to be provided. The count is stored in the `faces` field of each `DownloadedFile`.
+    ///
+    /// # Example
+    /// ```
+    /// use image_search::{Arguments, Faces};
+    ///
+    /// # fn count_faces(_: &[u8]) -> Option<usize> { Some(0) }
+    /// let args = Arguments::new("street", 10).faces(Faces::Zero, |bytes| count_faces(bytes));
+    /// ```
+    pub fn faces<F: Fn(&[u8]) -> Option<usize> + Send + Sync + 'static>(
+        mut self,
+        faces: Faces,
+        detector: F,
+    ) -> Self {
+        self.faces = Some((faces, FaceDetector(Arc::new(detector))));
+        self
+    }
+
+    /// Sets a function that scores each image, so that the results are ordered from the highest score to the lowest before the limit is applied.
+    /// Images with the same score keep their original order. This is applied after `Arguments::shuffle`.
+    ///
+    /// ```
+    /// use image_search::Arguments;
+    ///
+    /// // Prefer larger images, especially ones from Wikimedia
+    /// let args = Arguments::new("example", 10).rank_by(|image| {
+    ///     let size = (image.width * image.height) as f64;
+    ///     if image.url.contains("wikimedia.org") {
+    ///         size * 2.0
+    ///     } else {
+    ///         size
+    ///     }
+    /// });
+    /// ```
+    pub fn rank_by<F: Fn(&Image) -> f64 + Send + Sync + 'static>(mut self, score: F) -> Self {
+        self.rank_by = Some(RankFn(Arc::new(score)));
+        self
+    }
+
+    /// Sets a directory that the raw response body is written to whenever it cannot be parsed.
+    /// This is useful for reporting and debugging changes to the way Google stores its data.
+    pub fn parse_dump<P: Into<Option<PathBuf>>>(mut self, dir: P) -> Self {
+        self.parse_dump = dir.into();
+        self
+    }
+
+    /// Sets the optional request timeout for the `download` function.
+    /// Defaults to the number of seconds in the `IMAGE_SEARCH_TIMEOUT` environment variable, or 20 seconds if it isn't set.
     /// Not recomended to set to `None`, very rarely an image will fail to send data but not throw an error, causing the `download` function to never exit.
     pub fn timeout<D: Into<Option<Duration>>>(mut self, timeout: D) -> Self {
         self.timeout = timeout.into();
         self
     }
 
+    /// Sets the User-Agent header sent with search requests.
+    /// Defaults to the `IMAGE_SEARCH_USER_AGENT` environment variable, or a desktop Chrome User-Agent if it isn't set.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_owned());
+        self
+    }
+
+    /// Sets the maximum total time the `download` function can take, including the search itself.
+    /// Once the deadline is reached, any downloads still in progress are aborted and the images that were already downloaded are returned.
+    /// `download_report` can be used to check whether the deadline was reached.
+    pub fn deadline<D: Into<Option<Duration>>>(mut self, deadline: D) -> Self {
+        self.deadline = deadline.into();
+        self
+    }
+
+    /// Sets the longest time to wait before retrying a request that was rate limited with a `429 Too Many Requests` response.
+    /// The wait is taken from the `Retry-After` header, and requests asking for a longer wait than this are not retried.
+    /// Defaults to 30 seconds. Setting to `None` disables retrying.
+    pub fn max_retry_wait<D: Into<Option<Duration>>>(mut self, max_wait: D) -> Self {
+        self.max_retry_wait = max_wait.into();
+        self
+    }
+
+    /// Sets the maximum number of images the `download` function downloads from the same host at once.
+    /// Results are often dominated by a single CDN, which may ban or time out clients making too many requests in parallel.
+    /// Defaults to 4. Setting to `None` removes the limit.
+    pub fn max_per_host<N: Into<Option<usize>>>(mut self, max_per_host: N) -> Self {
+        self.max_per_host = max_per_host.into();
+        self
+    }
+
+    /// Sets the minimum time between the `download` function starting consecutive downloads from the same host.
+    /// Useful for respecting the capacity of the sites the images are hosted on, rather than just Google's. Defaults to `None`.
+    pub fn host_delay<D: Into<Option<Duration>>>(mut self, delay: D) -> Self {
+        self.host_delay = delay.into();
+        self
+    }
+
     /// Determines whether the image urls are switched out for the thumbnail urls.
     /// For example, the `urls` function will return the thumbnail urls instead of the image urls, and the `download` function will download the thumbnails instead of the full size image.
     /// Only affects the `urls` and `download` functions.
@@ -177,25 +637,138 @@ impl Arguments {
         self
     }
 
-    /// Sets the color that Google will filter by.
+    /// Enables a faster parsing mode that only reads the thumbnail url and dimensions of each result.
+    /// This is more robust to changes in Google's format, but the `url` of each `Image` is set to the thumbnail url,
+    /// the dimensions are those of the thumbnail, and the `source` is left empty.
+    pub fn thumbnails_only(mut self, thumbnails_only: bool) -> Self {
+        self.thumbnails_only = thumbnails_only;
+        self
+    }
+
+    /// Determines whether the results are shuffled before the limit is applied.
+    /// Useful for getting some variety out of repeated searches for the same query.
+    pub fn shuffle(mut self, shuffle: bool) -> Self {
+        self.shuffle = shuffle;
+        self
+    }
+
+    /// Sets the seed used for everything random, making the results reproducible: shuffling the results, `ImageList::take_random`, and the delays added by `Client::jitter`.
+    /// If no seed is set, a random one is used for every search.
+    /// Apart from shuffling, results are always in a stable order: the order of the pages and of the results within them, then by `Arguments::rank_by`.
+    pub fn seed<S: Into<Option<u64>>>(mut self, seed: S) -> Self {
+        self.seed = seed.into();
+        self
+    }
+
+    /// Only finds results with the given file extension, using the `filetype:` search operator.
+    /// Any leading `.` and characters that are not alphanumeric are stripped from the extension.
+    pub fn filetype(mut self, extension: &str) -> Self {
+        let extension: String = extension
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect();
+
+        if !extension.is_empty() {
+            self.operators.push(format!("filetype:{}", extension));
+        }
+        self
+    }
+
+    /// Only finds results from the given site, using the `site:` search operator, such as `wikipedia.org` or `example.com/gallery`.
+    /// Any scheme (`https://`), trailing `/`, whitespace, and quotes are stripped, and calling this again replaces the site.
+    pub fn site(mut self, site: &str) -> Self {
+        let site = site
+            .trim()
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_end_matches('/');
+        let site: String = site
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_control() && *c != '"')
+            .collect();
+
+        self.operators
+            .retain(|operator| !operator.starts_with("site:"));
+        if !site.is_empty() {
+            self.operators.push(format!("site:{}", site));
+        }
+        self
+    }
+
+    /// Leaves out results matching any of the given terms, using the `-` search operator, such as `-cartoon`.
+    /// Terms containing spaces are excluded as exact phrases, and quotes and leading `-`s are stripped from each term.
+    pub fn exclude(mut self, terms: &[&str]) -> Self {
+        for term in terms {
+            let term: String = term
+                .trim()
+                .trim_start_matches('-')
+                .chars()
+                .filter(|c| !c.is_control() && *c != '"')
+                .collect();
+            let term = term.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+            if term.contains(' ') {
+                self.operators.push(format!("-\"{}\"", term));
+            } else if !term.is_empty() {
+                self.operators.push(format!("-{}", term));
+            }
+        }
+        self
+    }
+
+    /// Only finds results published before the given date, using the `before:` search operator.
+    pub fn before(mut self, date: NaiveDate) -> Self {
+        self.operators
+            .push(format!("before:{}", date.format("%Y-%m-%d")));
+        self
+    }
+
+    /// Only finds results published after the given date, using the `after:` search operator.
+    pub fn after(mut self, date: NaiveDate) -> Self {
+        self.operators
+            .push(format!("after:{}", date.format("%Y-%m-%d")));
+        self
+    }
+
+    /// Determines whether the url of each result is resolved by following any redirects and removing tracking parameters.
+    /// The resolved url is stored in the `resolved_url` field of each `Image`, leaving the original `url` untouched.
+    pub fn resolve_urls(mut self, resolve_urls: bool) -> Self {
+        self.resolve_urls = resolve_urls;
+        self
+    }
+
+    /// Sets the search engine used to find images. Defaults to `providers::Google`.
+    /// The filters set on the `Arguments` are passed on to the provider, which applies the ones the engine supports.
+    ///
+    /// ```
+    /// use image_search::{providers::Google, Arguments};
+    ///
+    /// let args = Arguments::new("example", 10).provider(Google);
+    /// ```
+    pub fn provider<P: SearchProvider + 'static>(mut self, provider: P) -> Self {
+        self.provider = Provider::new(provider);
+        self
+    }
+
+    /// Sets the color that the search provider will filter by.
     pub fn color(mut self, color: Color) -> Self {
         self.color = color;
         self
     }
 
-    /// Sets the color type that Google will filter by.
+    /// Sets the color type that the search provider will filter by.
     pub fn color_type(mut self, color_type: ColorType) -> Self {
         self.color_type = color_type;
         self
     }
 
-    /// Sets the license that Google will filter by.
+    /// Sets the license that the search provider will filter by.
     pub fn license(mut self, license: License) -> Self {
         self.license = license;
         self
     }
 
-    /// Sets the image type that Google will filter by.
+    /// Sets the image type that the search provider will filter by.
     pub fn image_type(mut self, image_type: ImageType) -> Self {
         self.image_type = image_type;
         self
@@ -213,13 +786,207 @@ impl Arguments {
         self
     }
 
-    /// Sets the image format that Google will filter by.
+    /// Sets the size bucket the images are filtered by, such as only large images for wallpapers.
+    pub fn size(mut self, size: Size) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Only finds images that are at least the given size, such as `MinSize::Mp12` for images that can be printed at a high quality.
+    /// Google can't filter by both at once, so this takes the place of `Arguments::size` when both are set.
+    pub fn larger_than(mut self, larger_than: MinSize) -> Self {
+        self.larger_than = larger_than;
+        self
+    }
+
+    /// Sets the country results are ranked for, as a two letter country code such as `de`, instead of the country the request is made from.
+    /// Results from other countries are still found unless `Arguments::country_restrict` is set.
+    pub fn region(mut self, region: &str) -> Self {
+        self.region = Some(region.to_ascii_lowercase());
+        self
+    }
+
+    /// Only finds images on pages from the country set with `Arguments::region`. Only supported by Google, `CustomSearch`, and `SerpApi`.
+    pub fn country_restrict(mut self, country_restrict: bool) -> Self {
+        self.country_restrict = country_restrict;
+        self
+    }
+
+    /// Sets the language of the search engine's interface, as a language code such as `ja` or `zh-CN`, which also affects how results are ranked.
+    /// Defaults to English.
+    pub fn language(mut self, language: &str) -> Self {
+        self.language = Some(language.to_owned());
+        self
+    }
+
+    /// Adds filters to Google's `tbs` parameter as they are, such as `isz:lt,islt:2mp`, for filters that don't have an argument yet.
+    /// They are added after the filters set by the other arguments, and calling this again replaces them. Also used by `SerpApi`.
+    pub fn raw_tbs(mut self, tbs: &str) -> Self {
+        self.raw_tbs = Some(tbs.trim_matches(',').to_owned()).filter(|tbs| !tbs.is_empty());
+        self
+    }
+
+    /// Drops results whose url or source page is on any of the given domains or their subdomains, before the limit is applied.
+    /// For example, `"pinterest.com"` matches both `pinterest.com` and `i.pinterest.com`. Calling this again adds to the domains already blocked.
+    pub fn block_domains(mut self, domains: &[&str]) -> Self {
+        self.blocked_domains.extend(
+            domains
+                .iter()
+                .map(|domain| domain.trim().trim_start_matches('.').to_ascii_lowercase())
+                .filter(|domain| !domain.is_empty()),
+        );
+        self
+    }
+
+    /// Stops Google from collapsing similar results (`filter=0`) and from correcting the spelling of the query (`nfpr=1`), for datasets that want every result.
+    /// Google returns fewer results for some queries without this. Also used by `SerpApi`.
+    pub fn disable_filtering(mut self, disable_filtering: bool) -> Self {
+        self.disable_filtering = disable_filtering;
+        self
+    }
+
+    /// Adds a parameter to Google's search urls, for options that don't have an argument yet.
+    /// Parameters are added after the ones set by the other arguments, and can be added more than once. Other engines ignore them.
+    pub fn extra_param(mut self, name: &str, value: &str) -> Self {
+        self.extra_params.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Sets how strictly explicit results are filtered out. Defaults to `None`, which leaves the search provider's own default in place.
+    /// Providers that don't support a level use the closest one they do, and Baidu and Wikimedia Commons ignore it.
+    pub fn safe_search<S: Into<Option<SafeSearch>>>(mut self, safe_search: S) -> Self {
+        self.safe_search = safe_search.into();
+        self
+    }
+
+    /// Sets the image format that the search provider will filter by.
     pub fn format(mut self, format: Format) -> Self {
-        self.format = format;
+        self.formats = match format {
+            Format::None => Vec::new(),
+            format => vec![format],
+        };
+        self
+    }
+
+    /// Sets multiple image formats that Google will filter by, allowing images of any of the formats.
+    /// Google only supports filtering by a single format, so a separate request is made for each format and the results are merged.
+    pub fn formats(mut self, formats: &[Format]) -> Self {
+        self.formats = formats
+            .iter()
+            .copied()
+            .filter(|format| !matches!(format, Format::None))
+            .collect();
+        self
+    }
+
+    /// Orders the results by how close the average color of their thumbnails is to `rgb`, closest first, before the limit is applied.
+    /// This is much finer than `Arguments::color`, but fetches the thumbnail of every result, so it makes searches much slower.
+    /// Applied before `Arguments::shuffle` and `Arguments::rank_by`, which take priority over it. Only available with the `image` feature.
+    #[cfg(feature = "image")]
+    pub fn prefer_color<C: Into<Option<[u8; 3]>>>(mut self, rgb: C) -> Self {
+        self.prefer_color = rgb.into();
+        self
+    }
+
+    /// Determines whether the `download` function checks that each file is actually in one of the formats set with `Arguments::format` or `Arguments::formats`,
+    /// skipping any that aren't and trying the next image instead. Google's format filter isn't always accurate, so this is disabled by default.
+    /// JPG files with the `.jpeg` extension are treated as JPG. Has no effect if no formats are set.
+    pub fn strict_format(mut self, strict_format: bool) -> Self {
+        self.strict_format = strict_format;
         self
     }
 }
 
+/// Determines the order in which the `download` function tries the images it found.
+/// Since only the first images to download successfully are kept, this decides which images end up being downloaded.
+#[derive(Debug, Clone, Copy)]
+pub enum DownloadOrder {
+    /// Images are tried in the order they were returned by the search.
+    AsReturned,
+    /// Images with the most pixels are tried first, according to the dimensions reported by Google.
+    LargestFirst,
+    /// Images with the fewest pixels are tried first, according to the dimensions reported by Google.
+    SmallestFirst,
+    /// Images are taken from each host in turn, so that no single host receives all of the first requests.
+    RoundRobin,
+}
+
+/// An archive that a dead image url was downloaded from instead, when `Arguments::archive_fallback` is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Archive {
+    /// The most recent snapshot from the Internet Archive's Wayback Machine, at `web.archive.org`.
+    Wayback,
+}
+
+impl Archive {
+    /// The url of the archived copy of `url`.
+    fn url(&self, url: &str) -> String {
+        match self {
+            // The `id_` suffix returns the original file rather than the Wayback Machine's viewer, and the current time redirects to the most recent snapshot
+            Self::Wayback => format!(
+                "https://web.archive.org/web/{}id_/{}",
+                chrono::Utc::now().format("%Y%m%d%H%M%S"),
+                url
+            ),
+        }
+    }
+}
+
+/// Determines what the `download` function does with images that seem to carry a stock photo watermark, set with `Arguments::watermarks`.
+#[cfg(feature = "image")]
+#[derive(Debug, Clone, Copy)]
+pub enum Watermarks {
+    /// Images aren't checked for watermarks.
+    Ignore,
+    /// Images are downloaded either way, with the result of the check stored in the `watermarked` field of each `DownloadedFile`.
+    Flag,
+    /// Images that seem to be watermarked are skipped, and the next image is tried instead.
+    Reject,
+}
+
+/// The number of faces that downloaded images must have, set with `Arguments::faces`.
+/// The faces are counted by the detector given to `Arguments::faces`, since this crate doesn't include one.
+#[derive(Debug, Clone, Copy)]
+pub enum Faces {
+    /// Images must not have any faces.
+    Zero,
+    /// Images must have at least this many faces.
+    AtLeast(usize),
+    /// Images must have at most this many faces.
+    AtMost(usize),
+}
+
+impl Faces {
+    /// Whether an image with `count` faces is allowed.
+    fn allows(&self, count: usize) -> bool {
+        match *self {
+            Self::Zero => count == 0,
+            Self::AtLeast(min) => count >= min,
+            Self::AtMost(max) => count <= max,
+        }
+    }
+}
+
+/// Determines which frames of a GIF are saved by `Arguments::extract_frames`.
+#[derive(Debug, Clone, Copy)]
+pub enum Frames {
+    /// Only the first frame is saved.
+    First,
+    /// Every nth frame is saved, starting with the first, so `EveryNth(1)` saves every frame.
+    EveryNth(usize),
+}
+
+/// Determines how the files created by the `download` function are named.
+#[derive(Debug, Clone, Copy)]
+pub enum Naming {
+    /// Files are named after the query followed by a number, such as `cats0.jpg`.
+    Query,
+    /// Files are named after the first 16 hex digits of the SHA-256 hash of their contents, such as `9f86d081884c7d65.jpg`.
+    /// The same image is never written twice, even across multiple calls.
+    ContentHash,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Color {
     None,
@@ -276,11 +1043,25 @@ impl ColorType {
     }
 }
 
+/// The usage rights images are filtered by. The value Google's `tbs` parameter is given for each is listed with the variant.
+///
+/// The `Reuse` variants are Google's older usage rights filters, which are still supported, and are more specific than `CreativeCommons`.
+/// Every other engine uses its closest equivalents, and whether an image can actually be used should always be checked on the page it came from.
 #[derive(Debug, Clone, Copy)]
 pub enum License {
     None,
+    /// Images under Creative Commons licenses (`il:cl`).
     CreativeCommons,
+    /// Images under commercial and other licenses (`il:ol`).
     Other,
+    /// Images that are free to use or share (`sur:f`).
+    Reuse,
+    /// Images that are free to use or share, even commercially (`sur:fc`).
+    CommercialReuse,
+    /// Images that are free to use, share, or modify (`sur:fm`).
+    ReuseWithModification,
+    /// Images that are free to use, share, or modify, even commercially (`sur:fmc`).
+    CommercialReuseWithModification,
 }
 
 impl License {
@@ -289,6 +1070,10 @@ impl License {
             Self::None => "",
             Self::CreativeCommons => "il:cl",
             Self::Other => "il:ol",
+            Self::Reuse => "sur:f",
+            Self::CommercialReuse => "sur:fc",
+            Self::ReuseWithModification => "sur:fm",
+            Self::CommercialReuseWithModification => "sur:fmc",
         })
     }
 }
@@ -319,21 +1104,72 @@ impl ImageType {
 #[derive(Debug, Clone, Copy)]
 pub enum Time {
     None,
+    Hour,
     Day,
     Week,
     Month,
     Year,
+    /// Posted within the given amount of time, rounded up to the nearest minute.
+    Within(Duration),
 }
 
 impl Time {
+    /// How long ago the images can be posted, counting a month as 30 days and a year as 365 days, or `None` for `Time::None`.
+    pub fn duration(&self) -> Option<Duration> {
+        const HOUR: u64 = 60 * 60;
+        const DAY: u64 = 24 * HOUR;
+
+        match self {
+            Self::None => None,
+            Self::Hour => Some(Duration::from_secs(HOUR)),
+            Self::Day => Some(Duration::from_secs(DAY)),
+            Self::Week => Some(Duration::from_secs(7 * DAY)),
+            Self::Month => Some(Duration::from_secs(30 * DAY)),
+            Self::Year => Some(Duration::from_secs(365 * DAY)),
+            Self::Within(within) => Some(*within),
+        }
+    }
+
+    /// The number of minutes in the duration, rounded up and at least 1, or `None` for `Time::None`.
+    pub(crate) fn minutes(&self) -> Option<u64> {
+        self.duration()
+            .map(|duration| duration.as_secs().div_ceil(60).max(1))
+    }
+
+    /// Rounds `Hour` and `Within` up to the shortest of `Day`, `Week`, `Month`, and `Year` that covers them, for engines that only support those.
+    /// Durations longer than a year become `Time::None`.
+    pub(crate) fn round_up(&self) -> Time {
+        let duration = match self {
+            Self::Hour | Self::Within(_) => self.duration(),
+            time => return *time,
+        };
+
+        [Self::Day, Self::Week, Self::Month, Self::Year]
+            .into_iter()
+            .find(|time| duration <= time.duration())
+            .unwrap_or(Self::None)
+    }
+
     fn param(&self) -> String {
-        String::from(match self {
-            Self::None => "",
-            Self::Day => "qdr:d",
-            Self::Week => "qdr:w",
-            Self::Month => "qdr:m",
-            Self::Year => "qdr:y",
-        })
+        match self {
+            Self::None => String::new(),
+            Self::Hour => "qdr:h".to_owned(),
+            Self::Day => "qdr:d".to_owned(),
+            Self::Week => "qdr:w".to_owned(),
+            Self::Month => "qdr:m".to_owned(),
+            Self::Year => "qdr:y".to_owned(),
+            // Google takes a number of minutes, hours, or days, so the largest unit that fits exactly is used
+            Self::Within(_) => {
+                let minutes = self.minutes().unwrap_or(1);
+                if minutes % (24 * 60) == 0 {
+                    format!("qdr:d{}", minutes / (24 * 60))
+                } else if minutes % 60 == 0 {
+                    format!("qdr:h{}", minutes / 60)
+                } else {
+                    format!("qdr:n{}", minutes)
+                }
+            }
+        }
     }
 }
 
@@ -359,26 +1195,148 @@ impl Ratio {
 }
 
 #[derive(Debug, Clone, Copy)]
-pub enum Format {
+pub enum Size {
     None,
-    Jpg,
-    Gif,
-    Png,
-    Bmp,
-    Svg,
-    Webp,
-    Ico,
-    Raw,
+    Large,
+    Medium,
+    Icon,
 }
 
-impl Format {
+impl Size {
     fn param(&self) -> String {
         String::from(match self {
             Self::None => "",
-            Self::Jpg => "ift:jpg",
-            Self::Gif => "ift:gif",
-            Self::Png => "ift:png",
-            Self::Bmp => "ift:bmp",
+            Self::Large => "isz:l",
+            Self::Medium => "isz:m",
+            Self::Icon => "isz:i",
+        })
+    }
+}
+
+/// A minimum image size, named after Google's `Larger than` options.
+/// The sizes given in megapixels are the 4:3 dimensions Google uses, such as 1600×1200 for `Mp2`.
+#[derive(Debug, Clone, Copy)]
+pub enum MinSize {
+    None,
+    /// 400×300
+    Qsvga,
+    /// 640×480
+    Vga,
+    /// 800×600
+    Svga,
+    /// 1024×768
+    Xga,
+    Mp2,
+    Mp4,
+    Mp6,
+    Mp8,
+    Mp10,
+    Mp12,
+    Mp15,
+    Mp20,
+    Mp40,
+    Mp70,
+}
+
+impl MinSize {
+    /// The minimum width and height of the images, or `None` for `MinSize::None`.
+    pub fn dimensions(&self) -> Option<(i64, i64)> {
+        match self {
+            Self::None => None,
+            Self::Qsvga => Some((400, 300)),
+            Self::Vga => Some((640, 480)),
+            Self::Svga => Some((800, 600)),
+            Self::Xga => Some((1024, 768)),
+            Self::Mp2 => Some((1600, 1200)),
+            Self::Mp4 => Some((2272, 1704)),
+            Self::Mp6 => Some((2816, 2112)),
+            Self::Mp8 => Some((3264, 2448)),
+            Self::Mp10 => Some((3648, 2736)),
+            Self::Mp12 => Some((4096, 3072)),
+            Self::Mp15 => Some((4480, 3360)),
+            Self::Mp20 => Some((5120, 3840)),
+            Self::Mp40 => Some((7216, 5412)),
+            Self::Mp70 => Some((9600, 7200)),
+        }
+    }
+
+    fn param(&self) -> String {
+        let size = match self {
+            Self::None => return String::new(),
+            Self::Qsvga => "qsvga",
+            Self::Vga => "vga",
+            Self::Svga => "svga",
+            Self::Xga => "xga",
+            Self::Mp2 => "2mp",
+            Self::Mp4 => "4mp",
+            Self::Mp6 => "6mp",
+            Self::Mp8 => "8mp",
+            Self::Mp10 => "10mp",
+            Self::Mp12 => "12mp",
+            Self::Mp15 => "15mp",
+            Self::Mp20 => "20mp",
+            Self::Mp40 => "40mp",
+            Self::Mp70 => "70mp",
+        };
+        format!("isz:lt,islt:{}", size)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SafeSearch {
+    Off,
+    Moderate,
+    Strict,
+}
+
+impl SafeSearch {
+    /// The value of Google's `safe` parameter, which is empty for `Moderate` since that is Google's default.
+    fn param(&self) -> String {
+        String::from(match self {
+            Self::Off => "off",
+            Self::Moderate => "",
+            Self::Strict => "active",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Format {
+    None,
+    Jpg,
+    Gif,
+    Png,
+    Bmp,
+    Svg,
+    Webp,
+    Ico,
+    Raw,
+}
+
+impl Format {
+    /// Whether a file with the extension `extension` (as detected from its contents) is in this format.
+    fn matches_extension(&self, extension: &str) -> bool {
+        match self {
+            Self::None => true,
+            Self::Jpg => matches!(extension, "jpg" | "jpeg"),
+            Self::Gif => extension == "gif",
+            Self::Png => extension == "png",
+            Self::Bmp => extension == "bmp",
+            Self::Svg => extension == "svg",
+            Self::Webp => extension == "webp",
+            Self::Ico => extension == "ico",
+            // Most raw formats are based on TIFF, so they can't be told apart from it by their contents
+            Self::Raw => matches!(extension, "cr2" | "tif"),
+        }
+    }
+
+    fn param(&self) -> String {
+        String::from(match self {
+            Self::None => "",
+            Self::Jpg => "ift:jpg",
+            Self::Gif => "ift:gif",
+            Self::Png => "ift:png",
+            Self::Bmp => "ift:bmp",
             Self::Svg => "ift:svg",
             Self::Webp => "ift:webp",
             Self::Ico => "ift:ico",
@@ -389,37 +1347,339 @@ impl Format {
 
 /// Contains info about an image including the original url, the dimensions of the image (x, y), the url of the thumbnail, and the name of the source.
 ///
+/// More fields may be added in future versions, so images are created with `Image::new` outside of this crate, such as by a custom `SearchProvider`.
+///
 /// # Example
 /// ```
-/// Image {
-///     url: "https://www.example.com/static/image.jpg",
-///     width: 1920,
-///     height: 1080,
-///     thumbnail: "https://encrypted-tbn0.gstatic.com/images?q=tbn:ANd9GcQQazt0j8bbA34OYbfE9hf7g_bzFGwbSZmwflwVw-rnOSOmdSX03xYzHZkm_TsmbnM3m88&usqp=CAU",
-///     source: "https://www.example.com/articles/example.html"
-/// }
+/// use image_search::Image;
+///
+/// let mut image = Image::new(
+///     "https://www.example.com/static/image.jpg",
+///     1920,
+///     1080,
+///     "https://encrypted-tbn0.gstatic.com/images?q=tbn:ANd9GcQQazt0j8bbA34OYbfE9hf7g_bzFGwbSZmwflwVw-rnOSOmdSX03xYzHZkm_TsmbnM3m88&usqp=CAU",
+///     "https://www.example.com/articles/example.html",
+/// );
+/// image.license = Some("CC BY-SA 4.0".to_owned());
+/// ```
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct Image {
     pub url: String,
     pub width: i64,
     pub height: i64,
     pub thumbnail: String,
     pub source: String,
+    /// The url that `url` redirects to, with tracking parameters removed. Only set when `Arguments::resolve_urls` is enabled and the url could be resolved.
+    pub resolved_url: Option<String>,
+    /// When the image or its page was published, if the provider includes a date with its results (currently Flickr, Giphy, Tenor, Wikimedia Commons, and the Bing Image Search API).
+    pub published: Option<DateTime<Utc>>,
+    /// The license the image is published under, such as `CC BY-SA 4.0` or `Public domain`, if the provider includes one (currently Wikimedia Commons and Pixabay).
+    pub license: Option<String>,
+    /// The size of the full image in bytes, if the provider includes it (currently the Bing Image Search and Custom Search APIs).
+    pub file_size: Option<u64>,
+    /// The format of the full image, if the provider includes it (currently the Bing Image Search and Custom Search APIs).
+    pub format: Option<Format>,
+}
+
+impl Image {
+    /// Creates an image with the given urls and dimensions, leaving the optional details unset.
+    pub fn new(url: &str, width: i64, height: i64, thumbnail: &str, source: &str) -> Image {
+        Image {
+            url: url.to_owned(),
+            width,
+            height,
+            thumbnail: thumbnail.to_owned(),
+            source: source.to_owned(),
+            resolved_url: None,
+            published: None,
+            license: None,
+            file_size: None,
+            format: None,
+        }
+    }
+
+    /// Scores the image from 0 to 1 by the reputation of the site it was found on.
+    /// Reference sites, museums, and archives such as Wikipedia and Wikimedia Commons score highly, stock photo sites and content farms score low, and any other site scores 0.5.
+    /// Can be passed to `Arguments::rank_by` to prefer results from reputable sites.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use image_search::{Arguments, Image};
+    ///
+    /// let args = Arguments::new("example", 10).rank_by(Image::score);
+    /// ```
+    pub fn score(&self) -> f64 {
+        reputation::score(self)
+    }
+
+    /// The domain of the page the image was found on without any `www.` prefix, such as `en.wikipedia.org`, for attributing the image.
+    /// Falls back to the domain of the image itself when the source page isn't known, such as when only thumbnails were parsed.
+    pub fn source_domain(&self) -> Option<String> {
+        let host = reputation::host(&self.source).or_else(|| reputation::host(&self.url))?;
+        match host.strip_prefix("www.") {
+            Some(domain) => Some(domain.to_owned()),
+            None => Some(host),
+        }
+    }
+
+    /// The url of a 32x32 favicon for `source_domain`, served by Google's favicon service, which can be displayed next to the image without looking up the icon first.
+    pub fn favicon_url(&self) -> Option<String> {
+        self.source_domain()
+            .map(|domain| format!("https://www.google.com/s2/favicons?domain={}&sz=32", domain))
+    }
+
+    /// Fetches the thumbnail of the image, returning its bytes. Useful for loading previews one at a time, such as in a UI.
+    /// Thumbnails that are embedded in the results page as `data:` urls are decoded without making a request.
+    ///
+    /// # Errors
+    /// This function will return an error if:
+    /// * The GET request fails, returns an error status, or takes longer than `timeout`
+    /// * The thumbnail is an invalid `data:` url
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// extern crate tokio;
+    /// extern crate image_search;
+    ///
+    /// use image_search::{Arguments, search};
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), image_search::Error> {
+    ///     let images = search(Arguments::new("example", 10)).await?;
+    ///     let preview = images[0].fetch_thumbnail(Duration::from_secs(5)).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn fetch_thumbnail<D: Into<Option<Duration>>>(
+        &self,
+        timeout: D,
+    ) -> SearchResult<Vec<u8>> {
+        async_std::task::spawn(fetch_bytes(self.thumbnail.clone(), timeout.into())).await
+    }
+
+    /// Fetches the full size image into memory and decodes it, without writing it to a file.
+    /// Uses the same timeout as the `download` function does by default. Only available with the `image` feature.
+    ///
+    /// # Errors
+    /// This function will return an error if:
+    /// * The GET request fails, returns an error status, or times out
+    /// * The image can't be decoded, such as if it is in an unsupported format
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// extern crate tokio;
+    /// extern crate image_search;
+    ///
+    /// use image_search::{Arguments, search};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), image_search::Error> {
+    ///     let images = search(Arguments::new("example", 10)).await?;
+    ///     let decoded = images[0].open().await?;
+    ///     println!("{}x{}", decoded.width(), decoded.height());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "image")]
+    pub async fn open(&self) -> SearchResult<image::DynamicImage> {
+        let url = self.url.clone();
+        async_std::task::spawn(async move {
+            let buf =
+                fetch_bytes(url, Some(env_timeout().unwrap_or(Duration::from_secs(20)))).await?;
+            image::load_from_memory(&buf)
+                .map_err(|err| Error::Download(format!("unable to decode image: {}", err)))
+        })
+        .await
+    }
+
+    /// Downloads this image to `path`, the same way the `download` function downloads each image, using the default arguments.
+    /// The file extension is determined from the contents of the file, and replaces any extension `path` already has.
+    /// See [`download_to_with`](Image::download_to_with) for more information.
+    pub async fn download_to<P: Into<PathBuf>>(&self, path: P) -> SearchResult<DownloadedFile> {
+        self.download_to_with(path, &Arguments::default()).await
+    }
+
+    /// Downloads this image to `path` using the provided arguments, which control things such as the timeout, file size limits, and whether the thumbnail is downloaded instead.
+    /// The file extension is determined from the contents of the file, and replaces any extension `path` already has.
+    ///
+    /// # Errors
+    /// This function will return an error if:
+    /// * The GET request fails or times out
+    /// * The file is not an image, or is rejected by the arguments (such as by its size)
+    /// * The program is unable to write to the file
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// extern crate tokio;
+    /// extern crate image_search;
+    ///
+    /// use image_search::{Arguments, search};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), image_search::Error> {
+    ///     let images = search(Arguments::new("example", 10)).await?;
+    ///     let file = images[3].download_to("example").await?;
+    ///     println!("Saved to {}", file.path.display());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn download_to_with<P: Into<PathBuf>>(
+        &self,
+        path: P,
+        args: &Arguments,
+    ) -> SearchResult<DownloadedFile> {
+        let (image, path, args) = (self.clone(), path.into(), args.clone());
+        async_std::task::spawn(async move {
+            Ok(download_image(surf::Client::new(), &path, image, &args).await?)
+        })
+        .await
+    }
+}
+
+/// Contains info about a file created by the `download_report` function, including where it was written, the url it was downloaded from, and the search result it came from.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DownloadedFile {
+    /// Where the file was written, or the name it was given if it was stored with a sink set with `Arguments::storage`.
+    pub path: PathBuf,
+    /// Where the file was stored by the `StorageSink`, which is its path unless a different sink was set with `Arguments::storage`.
+    pub location: Location,
+    pub url: String,
+    pub image: Image,
+    /// The real dimensions (width, height) of the file, read from its header. `None` if they could not be determined.
+    pub dimensions: Option<(usize, usize)>,
+    /// Whether the real dimensions of the file differ from the dimensions reported by Google.
+    pub dimension_mismatch: bool,
+    /// The PNG files the frames of the GIF were saved to, if `Arguments::extract_frames` is set. Empty for every other file.
+    pub frames: Vec<PathBuf>,
+    /// The archive the file was downloaded from because the original url was dead, if `Arguments::archive_fallback` is enabled.
+    /// `url` is the url of the archived copy in that case.
+    pub archive: Option<Archive>,
+    /// Whether the Wayback Machine accepted a request to save `url`, if `Arguments::save_to_archive` is enabled.
+    pub archived: bool,
+    /// Whether the file seems to carry a stock photo watermark. `None` unless `Arguments::watermarks` is set to check for them.
+    pub watermarked: Option<bool>,
+    /// The number of faces in the file, if `Arguments::faces` is set.
+    pub faces: Option<usize>,
+}
+
+/// The result of a call to `download_report`, containing the files that were downloaded and info about the run as a whole.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DownloadReport {
+    pub files: Vec<DownloadedFile>,
+    /// Whether the deadline set with `Arguments::deadline` was reached before all of the images were downloaded.
+    pub deadline_reached: bool,
+    /// The number of images that were skipped because they weren't in one of the requested formats, when `Arguments::strict_format` is enabled.
+    pub format_rejected: usize,
+}
+
+impl DownloadReport {
+    /// Returns the paths of all of the downloaded files.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.files.iter().map(|file| file.path.clone()).collect()
+    }
+
+    /// Composes the downloaded files into a single grid with `columns` columns, labelled with their indices in `files`.
+    /// See the `contact_sheet` function for details. Requires the `image` feature.
+    #[cfg(feature = "image")]
+    pub fn contact_sheet(&self, columns: usize) -> image::RgbImage {
+        contact_sheet(&self.paths(), columns)
+    }
+}
+
+/// An event that occurs while the `download` function is running, passed to the callback set with `Arguments::on_event`.
+// Events are passed by reference and not stored, so the size of `Downloaded` doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum DownloadEvent {
+    /// The search finished, finding the given number of images that can be downloaded.
+    Found { images: usize },
+    /// An image was downloaded successfully.
+    Downloaded(DownloadedFile),
+    /// An image could not be downloaded, so the next available image will be tried instead.
+    Skipped { url: String, reason: String },
+    /// All of the downloads have finished, with the given number of images downloaded successfully.
+    Finished { downloaded: usize },
+}
+
+/// A callback that is called with each `DownloadEvent`.
+#[derive(Clone)]
+struct EventCallback(Arc<dyn Fn(&DownloadEvent) + Send + Sync>);
+
+impl fmt::Debug for EventCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EventCallback")
+    }
+}
+
+/// The sink set with `Arguments::storage`.
+#[derive(Clone)]
+struct Storage(Arc<dyn StorageSink>);
+
+impl fmt::Debug for Storage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Storage")
+    }
+}
+
+/// A function that counts the faces in an image for `Arguments::faces`.
+#[derive(Clone)]
+struct FaceDetector(Arc<CountFaces>);
+
+type CountFaces = dyn Fn(&[u8]) -> Option<usize> + Send + Sync;
+
+impl fmt::Debug for FaceDetector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FaceDetector")
+    }
+}
+
+/// A function that scores images for `Arguments::rank_by`.
+#[derive(Clone)]
+struct RankFn(Arc<dyn Fn(&Image) -> f64 + Send + Sync>);
+
+impl fmt::Debug for RankFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RankFn")
+    }
 }
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
-    Parse,
+    /// The images couldn't be parsed from the search engine's response, with the stage of parsing that failed and details about what went wrong.
+    Parse {
+        stage: ParseStage,
+        detail: String,
+    },
     Dir(io::Error),
     Network(surf::Error),
+    InvalidArguments(String),
+    Download(String),
+    /// The search engine returned an error instead of results, such as for a rejected API key or a used up quota.
+    Provider(String),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Parse        => write!(f, "Unable to parse images from json. Google may have changed the way their data is stored"),
-            Self::Dir(err)     => write!(f, "Unable to find or create: {}", err),
-            Self::Network(err) => write!(f, "GET request failed: {}", err),
+            Self::Parse { stage, detail }  => write!(f, "Unable to parse images ({}: {}). The search engine may have changed the way its data is stored", stage, detail),
+            Self::Dir(err)                 => write!(f, "Unable to find or create: {}", err),
+            Self::Network(err)             => write!(f, "GET request failed: {}", err),
+            Self::InvalidArguments(reason) => write!(f, "Invalid arguments: {}", reason),
+            Self::Download(reason)         => write!(f, "Unable to download image: {}", reason),
+            Self::Provider(reason)         => write!(f, "Search engine error: {}", reason),
         }
     }
 }
@@ -427,9 +1687,37 @@ impl fmt::Display for Error {
 impl std::error::Error for Error {
     fn description(&self) -> &str {
         match *self {
-            Self::Parse => "Unable to parse images from json",
+            Self::Parse { .. } => "Unable to parse images from json",
             Self::Dir(_) => "Error when finding or creating directory",
             Self::Network(_) => "Failed to make GET request",
+            Self::InvalidArguments(_) => "Invalid search arguments",
+            Self::Download(_) => "Unable to download image",
+            Self::Provider(_) => "The search engine returned an error",
+        }
+    }
+}
+
+/// The stage of parsing a response that failed, given in `Error::Parse`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseStage {
+    /// The marker before the results (such as Google's `var m={`) wasn't found.
+    Marker,
+    /// The end of the script containing the results wasn't found.
+    ScriptBoundary,
+    /// The results weren't valid JSON.
+    Json,
+    /// The JSON didn't have the structure that the images are expected to be in.
+    ImageArray,
+    /// An embedded `data:` url couldn't be decoded.
+    DataUri,
+}
+
+impl Error {
+    fn parse(stage: ParseStage, detail: impl Into<String>) -> Error {
+        Error::Parse {
+            stage,
+            detail: detail.into(),
         }
     }
 }
@@ -451,6 +1739,17 @@ enum DownloadError {
     Overflow,
     Extension,
     Timeout,
+    Deadline,
+    Animated,
+    Svg,
+    #[cfg(feature = "image")]
+    Watermark,
+    #[cfg(feature = "image")]
+    Text,
+    Faces,
+    Format,
+    ContentType,
+    Size,
     Fs(std::io::Error),
     Network(surf::Error),
 }
@@ -461,6 +1760,17 @@ impl fmt::Display for DownloadError {
             Self::Overflow => write!(f, "Ran out of possible images"),
             Self::Extension => write!(f, "Unable to determine file extension"),
             Self::Timeout => write!(f, "GET request timed out"),
+            Self::Deadline => write!(f, "Download deadline reached"),
+            Self::Animated => write!(f, "Image is animated"),
+            Self::Svg => write!(f, "Image is an SVG"),
+            #[cfg(feature = "image")]
+            Self::Watermark => write!(f, "Image seems to be watermarked"),
+            #[cfg(feature = "image")]
+            Self::Text => write!(f, "Image is mostly text"),
+            Self::Faces => write!(f, "Image does not have the requested number of faces"),
+            Self::Format => write!(f, "Image is not in one of the requested formats"),
+            Self::ContentType => write!(f, "Content type is not an image"),
+            Self::Size => write!(f, "File size is outside of the limits"),
             Self::Fs(err) => write!(f, "Problem when creating or writing to file: {}", err),
             Self::Network(err) => write!(f, "Unable to fetch image: {}", err),
         }
@@ -473,12 +1783,33 @@ impl std::error::Error for DownloadError {
             Self::Overflow => "Ran out of possible images",
             Self::Extension => "File type not known or not an image",
             Self::Timeout => "GET request timed out",
+            Self::Deadline => "Download deadline reached",
+            Self::Animated => "Image is animated",
+            Self::Svg => "Image is an SVG",
+            #[cfg(feature = "image")]
+            Self::Watermark => "Image seems to be watermarked",
+            #[cfg(feature = "image")]
+            Self::Text => "Image is mostly text",
+            Self::Faces => "Image does not have the requested number of faces",
+            Self::Format => "Image is not in one of the requested formats",
+            Self::ContentType => "Content type is not an image",
+            Self::Size => "File size is outside of the limits",
             Self::Fs(_) => "Error occured creating or writing to file",
             Self::Network(_) => "Error when making GET request to fetch image",
         }
     }
 }
 
+impl From<DownloadError> for Error {
+    fn from(value: DownloadError) -> Self {
+        match value {
+            DownloadError::Fs(err) => Self::Dir(err),
+            DownloadError::Network(err) => Self::Network(err),
+            other => Self::Download(other.to_string()),
+        }
+    }
+}
+
 impl From<async_std::future::TimeoutError> for DownloadError {
     fn from(_: async_std::future::TimeoutError) -> Self {
         Self::Timeout
@@ -508,18 +1839,61 @@ macro_rules! debug_display {
         })*
     }
 }
-debug_display!(for Image, Arguments, Color, ColorType, License, ImageType, Time, Ratio, Format);
+/// Implements `FromStr` for enums by matching their variant names, ignoring case, spaces, dashes, and underscores.
+macro_rules! variant_from_str {
+    ($($t:ident { $($variant:ident),+ $(,)? })+) => {
+        $(impl std::str::FromStr for $t {
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let normalized: String = s
+                    .chars()
+                    .filter(|c| !matches!(c, ' ' | '-' | '_'))
+                    .collect();
+
+                $(if normalized.eq_ignore_ascii_case(stringify!($variant)) {
+                    return Ok(Self::$variant);
+                })+
+
+                Err(Error::InvalidArguments(format!("unknown {}: {}", stringify!($t), s)))
+            }
+        })+
+    }
+}
+#[cfg(feature = "image")]
+variant_from_str! {
+    Watermarks { Ignore, Flag, Reject }
+}
+
+variant_from_str! {
+    Engine { Google, Bing, DuckDuckGo, Yandex, Baidu, Qwant, Wikimedia }
+    Naming { Query, ContentHash }
+    DownloadOrder { AsReturned, LargestFirst, SmallestFirst, RoundRobin }
+    Color { None, Red, Orange, Yellow, Green, Teal, Blue, Purple, Pink, White, Gray, Black, Brown }
+    ColorType { None, Color, Grayscale, Transparent }
+    License { None, CreativeCommons, Other, Reuse, CommercialReuse, ReuseWithModification, CommercialReuseWithModification }
+    ImageType { None, Face, Photo, Clipart, Lineart, Animated }
+    Time { None, Hour, Day, Week, Month, Year }
+    Ratio { None, Tall, Square, Wide, Panoramic }
+    Size { None, Large, Medium, Icon }
+    MinSize { None, Qsvga, Vga, Svga, Xga, Mp2, Mp4, Mp6, Mp8, Mp10, Mp12, Mp15, Mp20, Mp40, Mp70 }
+    SafeSearch { Off, Moderate, Strict }
+    Format { None, Jpg, Gif, Png, Bmp, Svg, Webp, Ico, Raw }
+}
+
+debug_display!(for Engine, Image, SkipReason, ParseStage, DownloadedFile, Location, DownloadReport, UrlStatus, DownloadEvent, Arguments, Archive, Faces, Frames, Naming, DownloadOrder, Color, ColorType, License, ImageType, Time, Ratio, Size, MinSize, SafeSearch, Format);
 
 /// Search for images based on the provided arguments and return images up to the provided limit.
 ///
 /// # Errors
 /// This function will return an error if:
+/// * The query is empty, too long, or contains control characters
 /// * The GET request fails
 /// * The images are not able to be parsed
 ///
 /// # Examples
 ///
-/// ```
+/// ```no_run
 /// extern crate tokio;
 /// extern crate image_search;
 ///
@@ -532,8 +1906,9 @@ debug_display!(for Image, Arguments, Color, ColorType, License, ImageType, Time,
 ///
 ///     Ok(())
 /// }
-pub async fn search(args: Arguments) -> SearchResult<Vec<Image>> {
-    async_std::task::spawn(_search(args)).await
+/// ```
+pub async fn search(args: Arguments) -> SearchResult<ImageList> {
+    async_std::task::spawn(_search(args, Arc::new(Session::default()))).await
 }
 
 /// Search for images based on the provided arguments and return images up to the provided limit.
@@ -543,337 +1918,1990 @@ pub async fn search(args: Arguments) -> SearchResult<Vec<Image>> {
 ///
 /// # Errors
 /// This function will return an error if:
+/// * The query is empty, too long, or contains control characters
 /// * The GET request fails
 /// * The images are not able to be parsed
-async fn _search(args: Arguments) -> SearchResult<Vec<Image>> {
-    let url = build_url(&args);
-    let body = get(url).await?;
-
-    let imgs = match unpack(body) {
-        Some(i) => i,
-        None => return Err(Error::Parse),
-    };
-
-    if imgs.len() > args.limit && args.limit > 0 {
-        Ok(imgs[..args.limit].to_vec())
-    } else {
-        Ok(imgs)
-    }
+pub(crate) async fn _search(args: Arguments, session: Arc<Session>) -> SearchResult<ImageList> {
+    _search_detailed(args, session)
+        .await
+        .map(|detailed| detailed.images)
 }
 
-/// Search for images based on the provided arguments and return the urls of the images
+/// Search for images based on the provided arguments, returning the images along with statistics about how the results were parsed.
+/// Useful for finding out how many results are being lost because Google changed the structure of its results.
 ///
 /// # Errors
 /// This function will return an error if:
+/// * The query is empty, too long, or contains control characters
 /// * The GET request fails
 /// * The images are not able to be parsed
 ///
 /// # Examples
 ///
-/// ```
+/// ```no_run
 /// extern crate tokio;
 /// extern crate image_search;
 ///
-/// use image_search::{Arguments, urls};
+/// use image_search::{Arguments, search_detailed};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), image_search::Error> {
-///     let args = Arguments::new("example", 10);
-///     let images = image_search::urls(args).await?;
+///     let detailed = search_detailed(Arguments::new("example", 10)).await?;
+///     for (reason, count) in &detailed.stats.skipped {
+///         println!("{} results skipped: {}", count, reason);
+///     }
 ///
 ///     Ok(())
 /// }
-pub async fn urls(args: Arguments) -> SearchResult<Vec<String>> {
-    let thumbnails = (&args.thumbnails).to_owned();
-    let images = search(args).await?;
+/// ```
+pub async fn search_detailed(args: Arguments) -> SearchResult<DetailedSearch> {
+    async_std::task::spawn(_search_detailed(args, Arc::new(Session::default()))).await
+}
 
-    let mut all: Vec<String> = Vec::new();
-    for image in images.iter() {
-        if thumbnails {
-            all.push(image.thumbnail.to_owned());
-        } else {
-            all.push(image.url.to_owned());
-        }
-    }
+pub(crate) async fn _search_detailed(
+    args: Arguments,
+    session: Arc<Session>,
+) -> SearchResult<DetailedSearch> {
+    let pages = pages_for(&args);
+    let seed = args.seed;
+    let stats = Mutex::new(ParseStats::default());
+    let imgs = search_pages(args, pages, session, &stats).await?;
+
+    Ok(DetailedSearch {
+        images: ImageList::from(imgs).seed(seed),
+        stats: stats.into_inner().expect("Parse stats lock poisoned"),
+    })
+}
 
-    Ok(all)
+/// The images found by `search_detailed`, along with statistics about how the results were parsed.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DetailedSearch {
+    pub images: ImageList,
+    pub stats: ParseStats,
 }
 
-/// Search for images based on the provided `Arguments` and downloads them to the path specified in the `directory` field in `Arguments`, or the "images" folder if none is provided.
-///
-/// # Errors
-/// This function will return an error if:
-/// * The GET request fails
-/// * The images are not able to be parsed
-/// * The program is unable to create/read/write to files or directories
-///
-/// # Examples
-///
-/// ```
-/// extern crate tokio;
-/// extern crate image_search;
-///
-/// use image_search::{Arguments, download};
-/// use std::path::Path;
-///
-/// #[tokio::main]
-/// async fn main() -> Result<(), image_search::Error> {
-///     let args = Arguments::new("example", 10).directory(Path::new("downloads"));
-///     let paths = image_search::download(args).await?;
-///
-///     Ok(())
-/// }
-pub async fn download(args: Arguments) -> SearchResult<Vec<PathBuf>> {
-    async_std::task::spawn(_download(args)).await
+/// How many of the search results in Google's responses were parsed into images, and why the rest were skipped.
+/// Counts include every result on every page fetched, before duplicates are removed and the limit is applied.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ParseStats {
+    /// The number of results that were parsed into images.
+    pub parsed: usize,
+    /// The number of results that were skipped for each reason.
+    pub skipped: HashMap<SkipReason, usize>,
+    /// The number of responses that couldn't be parsed normally, and were scanned for image urls instead.
+    /// Skipped results can't be counted for these responses.
+    pub fallback_pages: usize,
 }
 
-/// Search for images based on the provided `Arguments` and downloads them to the path specified in the `directory` field in `Arguments`, or the "images" folder if none is provided.
-///
-/// Must be called with [async_std::task::spawn] or with a [Tokio 0.2.x runtime](https://crates.io/crates/tokio/0.2.25).
-/// This is because [http-client](https://crates.io/crates/http-client) uses Tokio 0.2 for the hyper client.
-///
-/// # Errors
-/// This function will return an error if:
-/// * The GET request fails
-/// * The images are not able to be parsed
-/// * The program is unable to create/read/write to files or directories
-async fn _download(args: Arguments) -> SearchResult<Vec<PathBuf>> {
-    let images = urls(Arguments {
-        query: args.query.clone(),
-        limit: 0,
-        directory: args.directory.clone(),
-        ..args
-    })
-    .await?;
+impl ParseStats {
+    /// The total number of results that were skipped.
+    pub fn total_skipped(&self) -> usize {
+        self.skipped.values().sum()
+    }
 
-    let dir = match args.directory {
-        Some(dir) => dir.to_owned(),
-        None => match env::current_dir() {
-            Ok(v) => v,
-            Err(e) => return Err(Error::Dir(e)),
+    fn merge(&mut self, other: ParseStats) {
+        self.parsed += other.parsed;
+        for (reason, count) in other.skipped {
+            *self.skipped.entry(reason).or_default() += count;
         }
-        .join("images"),
-    };
+        self.fallback_pages += other.fallback_pages;
+    }
+}
+
+/// The reason a search result was skipped while parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SkipReason {
+    /// The result has no image url.
+    MissingUrl,
+    /// The result has no width or height.
+    MissingDimensions,
+    /// The result has no thumbnail url.
+    MissingThumbnail,
+    /// The result has no source page url.
+    MissingSource,
+}
 
-    match std::fs::create_dir_all(&dir) {
-        Ok(_) => (),
-        Err(e) => return Err(Error::Dir(e)),
+/// Searches the given number of pages of results (or every page if it is 0), then orders and limits the images found.
+pub(crate) async fn search_pages(
+    args: Arguments,
+    pages: usize,
+    session: Arc<Session>,
+    stats: &Mutex<ParseStats>,
+) -> SearchResult<Vec<Image>> {
+    let imgs = if pages == 0 {
+        fetch_all(&args, &session, stats).await?
+    } else {
+        fetch_pages(&args, pages, &session, stats).await?
+    };
+    #[cfg(feature = "image")]
+    let imgs = match args.prefer_color {
+        Some(rgb) => color::rank_by_color(imgs, rgb, &args).await,
+        None => imgs,
     };
+    let mut imgs = post_process(&args, imgs);
 
-    let mut suffix = 0;
-    let mut paths: Vec<PathBuf> = Vec::new();
-    for _ in 0..args.limit {
-        let mut path = dir.join(args.query.to_owned() + &suffix.to_string());
+    if args.resolve_urls {
+        resolve_all(&mut imgs, &args, &session).await;
+    }
+
+    Ok(imgs)
+}
+
+/// The number of pages that need to be fetched from the provider to find the limit of images, or 0 if there is no limit and pages should be fetched until they run out.
+pub(crate) fn pages_for(args: &Arguments) -> usize {
+    if args.limit == 0 {
+        0
+    } else {
+        let Provider(provider) = &args.provider;
+        args.limit
+            .div_ceil(provider.page_size().max(1))
+            .clamp(1, MAX_PAGES)
+    }
+}
 
-        let mut matches = match glob::glob(&(path.display().to_string() + ".*")) {
-            Ok(paths) => paths.last().is_some(),
-            Err(_) => false,
+/// Fetches pages of results one at a time until a page has no new images or `MAX_PAGES` is reached, returning the images in page order without duplicates.
+/// Errors on any page after the first are treated as the end of the results, since Google has likely run out of images, unless the search engine returned an error.
+async fn fetch_all(
+    args: &Arguments,
+    session: &Session,
+    stats: &Mutex<ParseStats>,
+) -> SearchResult<Vec<Image>> {
+    let mut seen = HashSet::new();
+    let mut imgs = Vec::new();
+    for page in 0..MAX_PAGES {
+        let page_imgs = match fetch_page(args, page, session, stats).await {
+            Ok(page_imgs) => page_imgs,
+            Err(err) if page == 0 || matches!(err, Error::Provider(_)) => return Err(err),
+            Err(_) => break,
         };
 
-        while matches {
-            suffix += 1;
-            path = dir.join(args.query.to_owned() + &suffix.to_string());
-            matches = match glob::glob(&(path.display().to_string() + ".*")) {
-                Ok(paths) => paths.last().is_some(),
-                Err(_) => false,
-            };
+        let before = imgs.len();
+        imgs.extend(
+            page_imgs
+                .into_iter()
+                .filter(|image| seen.insert(image.url.clone())),
+        );
+        if imgs.len() == before {
+            break;
         }
-
-        paths.push(path);
-        suffix += 1;
     }
 
-    let with_extensions = download_n(images, paths, args.timeout).await;
-
-    Ok(with_extensions)
+    Ok(imgs)
 }
 
-/// Trys to download
-async fn download_n(
-    urls: Vec<String>,
-    paths: Vec<PathBuf>,
-    timeout: Option<Duration>,
-) -> Vec<PathBuf> {
-    let mut_urls = Arc::new(Mutex::new(urls));
+/// Fetches the first `pages` pages of results concurrently, returning the images in page order without duplicates.
+/// If images on blocked domains leave fewer than the limit, the pages after them are fetched one at a time until there are enough, like `Pages`.
+/// Errors on any page after the first are treated as the end of the results, since Google has likely run out of images, unless the search engine returned an error.
+pub(crate) async fn fetch_pages(
+    args: &Arguments,
+    pages: usize,
+    session: &Session,
+    stats: &Mutex<ParseStats>,
+) -> SearchResult<Vec<Image>> {
+    let results: Vec<SearchResult<Vec<Image>>> = stream::iter(0..pages)
+        .map(|page| fetch_page(args, page, session, stats))
+        .buffered(MAX_CONCURRENT_PAGES)
+        .collect()
+        .await;
+
+    let mut seen = HashSet::new();
+    let mut imgs = Vec::new();
+    for (page, result) in results.into_iter().enumerate() {
+        match result {
+            Ok(page_imgs) => imgs.extend(
+                page_imgs
+                    .into_iter()
+                    .filter(|image| seen.insert(image.url.clone())),
+            ),
+            Err(err) if page == 0 || matches!(err, Error::Provider(_)) => return Err(err),
+            Err(_) => return Ok(imgs),
+        }
+    }
 
-    let mut downloaders = Vec::new();
-    let client = surf::Client::new();
-    for path in paths {
-        downloaders.push(download_until(
-            mut_urls.clone(),
-            path,
-            client.clone(),
-            timeout,
-        ));
+    let unblocked = |imgs: &[Image]| imgs.iter().filter(|image| !args.is_blocked(image)).count();
+    for page in pages..MAX_PAGES {
+        if args.blocked_domains.is_empty() || args.limit == 0 || unblocked(&imgs) >= args.limit {
+            break;
+        }
+
+        let page_imgs = match fetch_page(args, page, session, stats).await {
+            Ok(page_imgs) => page_imgs,
+            Err(err) if matches!(err, Error::Provider(_)) => return Err(err),
+            Err(_) => break,
+        };
+        let before = imgs.len();
+        imgs.extend(
+            page_imgs
+                .into_iter()
+                .filter(|image| seen.insert(image.url.clone())),
+        );
+        if imgs.len() == before {
+            break;
+        }
     }
 
-    let with_extensions = future::join_all(downloaders)
-        .await
-        .into_iter()
-        .filter_map(|x| x.ok())
-        .collect();
+    Ok(imgs)
+}
 
-    with_extensions
+/// Fetches the given page of results from the provider set in `args`, adding how the results were parsed to `stats`.
+pub(crate) async fn fetch_page(
+    args: &Arguments,
+    page: usize,
+    session: &Session,
+    stats: &Mutex<ParseStats>,
+) -> SearchResult<Vec<Image>> {
+    args.validate()?;
+
+    let Provider(provider) = &args.provider;
+    provider
+        .fetch_page(PageRequest::new(args, page, session, stats))
+        .await
 }
 
-macro_rules! next_available {
-    ($urls:expr) => {{
-        let mut mut_urls = $urls.lock().expect("Other downloading thread panicked"); // SAFETY: no thread should panic while holding, since this is the only unwrap/expect
-        if mut_urls.is_empty() {
-            return Err(DownloadError::Overflow);
-        }
-        let url = mut_urls.remove(0);
-        std::mem::drop(mut_urls);
+/// The query parameters removed from resolved urls, since they're only used for tracking.
+const TRACKING_PARAMS: [&str; 8] = [
+    "fbclid", "gclid", "dclid", "msclkid", "igshid", "mc_cid", "mc_eid", "_ga",
+];
 
-        url
-    }};
-}
+/// The maximum number of redirects followed when resolving a url.
+pub(crate) const MAX_REDIRECTS: usize = 10;
 
-async fn download_until(
-    urls: Arc<Mutex<Vec<String>>>,
-    path: PathBuf,
-    client: surf::Client,
-    timeout: Option<Duration>,
-) -> Result<PathBuf, DownloadError> {
-    let mut url = next_available!(urls);
+/// The maximum number of times a rate limited request is retried.
+pub(crate) const MAX_RETRIES: usize = 3;
+
+/// How long to wait before retrying the request that received `res`, if it was rate limited.
+/// Returns `None` if the response isn't a `429 Too Many Requests`, has no valid `Retry-After` header, or asks for a wait longer than `max_wait`.
+pub(crate) fn retry_after(res: &surf::Response, max_wait: Option<Duration>) -> Option<Duration> {
+    if res.status() != surf::StatusCode::TooManyRequests {
+        return None;
+    }
 
-    let with_extension = loop {
-        let path = download_image(client.clone(), &path, url.to_owned(), timeout).await;
-        if path.is_ok() {
-            break path;
+    let value = res.header("Retry-After")?.last().as_str().trim();
+    // The header is either a number of seconds or an HTTP date
+    let wait = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+                .to_std()
+                .unwrap_or_default()
         }
-        url = next_available!(urls);
     };
 
-    with_extension
+    if wait <= max_wait? {
+        Some(wait)
+    } else {
+        None
+    }
 }
 
-async fn download_image(
-    client: surf::Client,
-    path: &PathBuf,
-    url: String,
+/// Resolves the urls of all of the images concurrently, setting their `resolved_url`.
+async fn resolve_all(imgs: &mut [Image], args: &Arguments, session: &Session) {
+    let resolved = future::join_all(
+        imgs.iter()
+            .map(|image| resolve_url(&session.client, &image.url, args.timeout)),
+    )
+    .await;
+
+    for (image, resolved) in imgs.iter_mut().zip(resolved) {
+        image.resolved_url = resolved.map(|url| strip_tracking(&url));
+    }
+}
+
+/// Follows the redirects from `url` using HEAD requests, returning the final url, or `None` if a request fails.
+async fn resolve_url(
+    client: &surf::Client,
+    url: &str,
     timeout: Option<Duration>,
-) -> Result<PathBuf, DownloadError> {
-    let buf = match timeout {
-        Some(duration) => {
-            async_std::future::timeout(duration, client.recv_bytes(surf::get(url))).await?
+) -> Option<String> {
+    let mut url = surf::Url::parse(url).ok()?;
+
+    for _ in 0..MAX_REDIRECTS {
+        let res = match timeout {
+            Some(duration) => async_std::future::timeout(duration, client.head(url.clone()))
+                .await
+                .ok()?,
+            None => client.head(url.clone()).await,
+        }
+        .ok()?;
+
+        if !res.status().is_redirection() {
+            return Some(url.to_string());
+        }
+
+        let location = res.header("Location")?.last().as_str();
+        url = url.join(location).ok()?;
+    }
+
+    Some(url.to_string())
+}
+
+/// Whether the host of `url` is one of `domains` or a subdomain of one, ignoring case.
+pub(crate) fn on_domains<S: AsRef<str>>(url: &str, domains: &[S]) -> bool {
+    let host = match surf::Url::parse(url) {
+        Ok(url) => match url.host_str() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return false,
+        },
+        Err(_) => return false,
+    };
+
+    domains.iter().any(|domain| {
+        let domain = domain.as_ref().to_ascii_lowercase();
+        host == domain || host.ends_with(&format!(".{}", domain))
+    })
+}
+
+/// Removes any tracking parameters (such as `utm_source` or `fbclid`) from the query of `url`.
+fn strip_tracking(url: &str) -> String {
+    let mut parsed = match surf::Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(_) => return url.to_owned(),
+    };
+
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_ref()))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    if pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+
+    parsed.to_string()
+}
+
+/// Applies the blocked domains and result ordering options in `args` to the parsed images, then truncates them to the limit.
+pub(crate) fn post_process(args: &Arguments, mut imgs: Vec<Image>) -> Vec<Image> {
+    imgs.retain(|image| !args.is_blocked(image));
+
+    if args.shuffle {
+        imgs.shuffle(&mut rng(args.seed));
+    }
+
+    if let Some(RankFn(score)) = &args.rank_by {
+        let mut scored: Vec<(f64, Image)> = imgs
+            .into_iter()
+            .map(|image| (score(&image), image))
+            .collect();
+        scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        imgs = scored.into_iter().map(|(_, image)| image).collect();
+    }
+
+    if imgs.len() > args.limit && args.limit > 0 {
+        imgs.truncate(args.limit);
+    }
+
+    imgs
+}
+
+/// Creates a random number generator from the given seed, or from entropy if there is none.
+pub(crate) fn rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// The maximum number of pages that will be fetched while paginating, so a search can't go on forever.
+const MAX_PAGES: usize = 10;
+
+/// The maximum number of pages that are fetched at the same time.
+const MAX_CONCURRENT_PAGES: usize = 4;
+
+/// Fetches pages of results one at a time, skipping any images that have already been returned.
+pub(crate) struct Pages {
+    args: Arguments,
+    session: Arc<Session>,
+    page: usize,
+    returned: usize,
+    seen: HashSet<String>,
+    done: bool,
+}
+
+impl Pages {
+    pub(crate) fn new(args: Arguments, session: Arc<Session>) -> Pages {
+        Pages {
+            args,
+            session,
+            page: 0,
+            returned: 0,
+            seen: HashSet::new(),
+            done: false,
+        }
+    }
+
+    /// Fetches the next page of new images, returning `None` once the limit has been reached or no new images are found.
+    pub(crate) async fn next_page(&mut self) -> Option<SearchResult<Vec<Image>>> {
+        if self.done {
+            return None;
+        }
+
+        // Pages where every new image is on a blocked domain are skipped, rather than ending the results
+        loop {
+            let args = self.args.clone();
+            let session = self.session.clone();
+            let page = self.page;
+            let imgs = match async_std::task::spawn(async move {
+                fetch_page(&args, page, &session, &Mutex::default()).await
+            })
+            .await
+            {
+                Ok(imgs) => imgs,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            };
+            self.page += 1;
+
+            let mut imgs: Vec<Image> = imgs
+                .into_iter()
+                .filter(|image| self.seen.insert(image.url.clone()))
+                .collect();
+            let found_new = !imgs.is_empty();
+            imgs.retain(|image| !self.args.is_blocked(image));
+
+            if self.args.limit > 0 {
+                imgs.truncate(self.args.limit - self.returned);
+            }
+            if self.args.resolve_urls {
+                resolve_all(&mut imgs, &self.args, &self.session).await;
+            }
+
+            self.returned += imgs.len();
+            if !found_new
+                || self.page >= MAX_PAGES
+                || (self.args.limit > 0 && self.returned >= self.args.limit)
+            {
+                self.done = true;
+            }
+
+            if !imgs.is_empty() {
+                return Some(Ok(imgs));
+            }
+            if self.done {
+                return None;
+            }
+        }
+    }
+}
+
+/// Search for images based on the provided arguments, returning a stream that lazily fetches further pages of results as they are needed.
+/// The stream ends once the limit has been reached (unless it is 0) or Google stops returning new images.
+///
+/// Unlike `search`, results are never shuffled or ranked, since they are returned as soon as each page is fetched.
+///
+/// # Errors
+/// The stream will return an error and then end if:
+/// * The query is empty, too long, or contains control characters
+/// * The GET request fails
+/// * The images are not able to be parsed
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate futures;
+/// extern crate tokio;
+/// extern crate image_search;
+///
+/// use futures::StreamExt;
+/// use image_search::{Arguments, search_stream};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), image_search::Error> {
+///     let args = Arguments::new("example", 250);
+///     let mut images = Box::pin(search_stream(args));
+///     while let Some(image) = images.next().await {
+///         println!("{}", image?.url);
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub fn search_stream(args: Arguments) -> impl Stream<Item = SearchResult<Image>> {
+    page_stream(Pages::new(args, Arc::new(Session::default())))
+}
+
+/// Flattens the pages of results into a stream of images.
+pub(crate) fn page_stream(pages: Pages) -> impl Stream<Item = SearchResult<Image>> {
+    stream::unfold(pages, |mut pages| async move {
+        let page = pages.next_page().await?;
+        Some((page, pages))
+    })
+    .flat_map(|page| match page {
+        Ok(imgs) => stream::iter(imgs.into_iter().map(Ok).collect::<Vec<_>>()),
+        Err(err) => stream::iter(vec![Err(err)]),
+    })
+}
+
+/// Search for images based on the provided arguments and return the urls of the images
+///
+/// # Errors
+/// This function will return an error if:
+/// * The query is empty, too long, or contains control characters
+/// * The GET request fails
+/// * The images are not able to be parsed
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate tokio;
+/// extern crate image_search;
+///
+/// use image_search::{Arguments, urls};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), image_search::Error> {
+///     let args = Arguments::new("example", 10);
+///     let images = image_search::urls(args).await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub async fn urls(args: Arguments) -> SearchResult<Vec<String>> {
+    let thumbnails = args.thumbnails;
+    let images = search(args).await?;
+
+    let mut all: Vec<String> = Vec::new();
+    for image in images.iter() {
+        if thumbnails {
+            all.push(image.thumbnail.to_owned());
+        } else {
+            all.push(image.url.to_owned());
         }
-        None => client.recv_bytes(surf::get(url)).await,
+    }
+
+    Ok(all)
+}
+
+/// The result of checking a url with `validate_urls`.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct UrlStatus {
+    pub url: String,
+    /// Whether the url responded with a successful status code.
+    pub reachable: bool,
+    /// The status code of the response, or `None` if the request failed or timed out.
+    pub status: Option<u16>,
+    /// The `Content-Type` header of the response, if there was one.
+    pub content_type: Option<String>,
+    /// The `Content-Length` header of the response, if there was one.
+    pub size: Option<u64>,
+}
+
+/// The maximum number of urls that are checked at the same time by `validate_urls`.
+const MAX_CONCURRENT_CHECKS: usize = 16;
+
+/// Checks whether each of the urls can still be reached, without downloading or saving anything.
+/// A HEAD request is made for each url, falling back to a GET request (whose body is never read) if the host doesn't handle HEAD requests.
+/// The results are in the same order as `urls`.
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate tokio;
+/// extern crate image_search;
+///
+/// use image_search::validate_urls;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let saved = vec!["https://www.example.com/image.jpg".to_owned()];
+///     let alive: Vec<String> = validate_urls(saved, Duration::from_secs(10))
+///         .await
+///         .into_iter()
+///         .filter(|status| status.reachable)
+///         .map(|status| status.url)
+///         .collect();
+/// }
+/// ```
+pub async fn validate_urls<D: Into<Option<Duration>>>(
+    urls: Vec<String>,
+    timeout: D,
+) -> Vec<UrlStatus> {
+    let timeout = timeout.into();
+    async_std::task::spawn(async move {
+        let client = surf::Client::new();
+        stream::iter(urls)
+            .map(|url| check_url(client.clone(), url, timeout))
+            .buffered(MAX_CONCURRENT_CHECKS)
+            .collect()
+            .await
+    })
+    .await
+}
+
+/// Groups the images into clusters of visually similar images by comparing their thumbnails, such as to separate the car from the animal when searching for "jaguar".
+/// Clusters are ordered from largest to smallest, and each has a representative image that can be shown for the whole group.
+/// Images whose thumbnails can't be fetched within `timeout` or decoded are each put in their own cluster at the end.
+/// Requires the `image` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate tokio;
+/// extern crate image_search;
+///
+/// use image_search::{Arguments, cluster, search};
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), image_search::Error> {
+///     let images = search(Arguments::new("jaguar", 50)).await?;
+///     for group in cluster(&images, Duration::from_secs(10)).await {
+///         println!("{} images like {}", group.images.len(), group.representative.url);
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "image")]
+pub async fn cluster<D: Into<Option<Duration>>>(images: &[Image], timeout: D) -> Vec<Cluster> {
+    let (images, timeout) = (images.to_vec(), timeout.into());
+    async_std::task::spawn(cluster::cluster(images, timeout)).await
+}
+
+/// Checks whether `url` can be reached for `validate_urls`.
+async fn check_url(client: surf::Client, url: String, timeout: Option<Duration>) -> UrlStatus {
+    let mut res = request_with_timeout(client.head(&url), timeout).await;
+    // Some hosts reject or mishandle HEAD requests, so those are tried again with a GET request
+    if !res.as_ref().is_some_and(|res| res.status().is_success()) {
+        if let Some(get) = request_with_timeout(client.get(&url), timeout).await {
+            res = Some(get);
+        }
+    }
+
+    match res {
+        Some(res) => UrlStatus {
+            reachable: res.status().is_success(),
+            status: Some(res.status().into()),
+            content_type: res
+                .header("Content-Type")
+                .map(|value| value.last().as_str().to_owned()),
+            size: res
+                .header("Content-Length")
+                .and_then(|value| value.last().as_str().parse().ok()),
+            url,
+        },
+        None => UrlStatus {
+            url,
+            reachable: false,
+            status: None,
+            content_type: None,
+            size: None,
+        },
+    }
+}
+
+/// Sends `req`, returning `None` if it fails or takes longer than `timeout`.
+async fn request_with_timeout(
+    req: surf::RequestBuilder,
+    timeout: Option<Duration>,
+) -> Option<surf::Response> {
+    match timeout {
+        Some(duration) => async_std::future::timeout(duration, req).await.ok()?.ok(),
+        None => req.await.ok(),
+    }
+}
+
+/// Search for images based on the provided `Arguments` and downloads them to the path specified in the `directory` field in `Arguments`, or the "images" folder if none is provided.
+///
+/// # Errors
+/// This function will return an error if:
+/// * The query is empty, too long, or contains control characters
+/// * The GET request fails
+/// * The images are not able to be parsed
+/// * The program is unable to create/read/write to files or directories
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate tokio;
+/// extern crate image_search;
+///
+/// use image_search::{Arguments, download};
+/// use std::path::Path;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), image_search::Error> {
+///     let args = Arguments::new("example", 10).directory(Path::new("downloads"));
+///     let paths = image_search::download(args).await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub async fn download(args: Arguments) -> SearchResult<Vec<PathBuf>> {
+    Ok(download_report(args).await?.paths())
+}
+
+/// Search for images based on the provided `Arguments` and downloads them the same way as the `download` function,
+/// but returns a `DownloadReport` containing more information about the downloaded files and the run as a whole.
+///
+/// # Errors
+/// This function will return an error if:
+/// * The query is empty, too long, or contains control characters
+/// * The GET request fails
+/// * The images are not able to be parsed
+/// * The program is unable to create/read/write to files or directories
+///
+/// # Examples
+///
+/// ```no_run
+/// extern crate tokio;
+/// extern crate image_search;
+///
+/// use image_search::{Arguments, download_report};
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), image_search::Error> {
+///     let args = Arguments::new("example", 10).deadline(Duration::from_secs(30));
+///     let report = download_report(args).await?;
+///     if report.deadline_reached {
+///         println!("Only downloaded {} images in time", report.files.len());
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub async fn download_report(args: Arguments) -> SearchResult<DownloadReport> {
+    async_std::task::spawn(_download(args, Arc::new(Session::default()))).await
+}
+
+/// Search for images based on the provided `Arguments` and downloads them to the path specified in the `directory` field in `Arguments`, or the "images" folder if none is provided.
+///
+/// Must be called with [async_std::task::spawn] or with a [Tokio 0.2.x runtime](https://crates.io/crates/tokio/0.2.25).
+/// This is because [http-client](https://crates.io/crates/http-client) uses Tokio 0.2 for the hyper client.
+///
+/// # Errors
+/// This function will return an error if:
+/// * The query is empty, too long, or contains control characters
+/// * The GET request fails
+/// * The images are not able to be parsed
+/// * The program is unable to create/read/write to files or directories
+pub(crate) async fn _download(
+    args: Arguments,
+    session: Arc<Session>,
+) -> SearchResult<DownloadReport> {
+    args.validate()?;
+    let started = Instant::now();
+
+    let stats = Mutex::default();
+    let found = search_pages(
+        Arguments {
+            limit: 0,
+            ..args.clone()
+        },
+        pages_for(&args),
+        session.clone(),
+        &stats,
+    );
+    let images = match args.deadline {
+        Some(deadline) => match async_std::future::timeout(deadline, found).await {
+            Ok(images) => images?,
+            Err(_) => {
+                return Ok(DownloadReport {
+                    files: Vec::new(),
+                    deadline_reached: true,
+                    format_rejected: 0,
+                })
+            }
+        },
+        None => found.await?,
+    };
+    args.emit(DownloadEvent::Found {
+        images: images.len(),
+    });
+
+    let dir = match &args.directory {
+        Some(dir) => dir.to_owned(),
+        None => match env::current_dir() {
+            Ok(v) => v,
+            Err(e) => return Err(Error::Dir(e)),
+        }
+        .join("images"),
+    };
+
+    let dir = if args.session_dirs {
+        dir.join(safe_file_name(&args.query))
+            .join(chrono::Utc::now().format("%Y-%m-%dT%H-%M-%SZ").to_string())
+    } else {
+        dir
+    };
+
+    if args.storage.is_none() {
+        match create_dir(&dir, &args) {
+            Ok(_) => (),
+            Err(e) => return Err(Error::Dir(e)),
+        };
+    }
+
+    // Windows and macOS compare names case-insensitively, so any existing file with the same name in a different case is a collision
+    let existing: HashSet<String> = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name();
+                // Queries can contain dots, so only the extension is removed from the name
+                Path::new(&name)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_lowercase())
+            })
+            .collect(),
+        Err(_) => HashSet::new(),
+    };
+    #[cfg(windows)]
+    let dir = long_path(dir);
+
+    let stem = safe_file_name(&args.query);
+    let mut suffix = 0;
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let count = if args.limit == 0 {
+        images.len()
+    } else {
+        args.limit
+    };
+    for _ in 0..count {
+        while existing.contains(&format!("{}{}", stem, suffix).to_lowercase()) {
+            suffix += 1;
+        }
+
+        paths.push(dir.join(format!("{}{}", stem, suffix)));
+        suffix += 1;
+    }
+
+    let remaining = args
+        .deadline
+        .map(|deadline| deadline.saturating_sub(started.elapsed()));
+    let images = order_downloads(images, &args);
+    let format_rejected = AtomicUsize::new(0);
+    let results = download_n(images, paths, &args, remaining, &session, &format_rejected).await;
+
+    let deadline_reached = results
+        .iter()
+        .any(|result| matches!(result, Err(DownloadError::Deadline)));
+    let files: Vec<DownloadedFile> = results.into_iter().filter_map(|x| x.ok()).collect();
+    args.emit(DownloadEvent::Finished {
+        downloaded: files.len(),
+    });
+
+    Ok(DownloadReport {
+        files,
+        deadline_reached,
+        format_rejected: format_rejected.into_inner(),
+    })
+}
+
+/// Orders the images to download according to `Arguments::download_order`.
+fn order_downloads(mut images: Vec<Image>, args: &Arguments) -> Vec<Image> {
+    match args.download_order {
+        DownloadOrder::AsReturned => images,
+        DownloadOrder::LargestFirst => {
+            images.sort_by_key(|image| std::cmp::Reverse(image.width * image.height));
+            images
+        }
+        DownloadOrder::SmallestFirst => {
+            images.sort_by_key(|image| image.width * image.height);
+            images
+        }
+        DownloadOrder::RoundRobin => {
+            // Hosts are taken in the order they first appear, and keep the order of their own images
+            let mut hosts: Vec<(String, VecDeque<Image>)> = Vec::new();
+            for image in images {
+                let host = url_host(&download_url(&image, args));
+
+                match hosts.iter_mut().find(|(h, _)| *h == host) {
+                    Some((_, queue)) => queue.push_back(image),
+                    None => hosts.push((host, VecDeque::from([image]))),
+                }
+            }
+
+            let mut ordered = Vec::new();
+            while !hosts.is_empty() {
+                for (_, queue) in hosts.iter_mut() {
+                    ordered.extend(queue.pop_front());
+                }
+                hosts.retain(|(_, queue)| !queue.is_empty());
+            }
+            ordered
+        }
+    }
+}
+
+/// The host of `url`, or an empty string if it doesn't have one.
+fn url_host(url: &str) -> String {
+    surf::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+/// Fetches the body of `url` into memory, decoding it directly if it is a base64 `data:` url.
+async fn fetch_bytes(url: String, timeout: Option<Duration>) -> SearchResult<Vec<u8>> {
+    if let Some(data) = url.strip_prefix("data:") {
+        let (_, encoded) = data
+            .split_once(";base64,")
+            .ok_or_else(|| Error::parse(ParseStage::DataUri, "not base64 encoded"))?;
+        return base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|err| Error::parse(ParseStage::DataUri, err.to_string()));
+    }
+
+    let request = async {
+        let mut res = surf::get(&url).await?;
+        if !res.status().is_success() {
+            return Err(surf::Error::from_str(
+                res.status(),
+                format!("{} returned {}", url, res.status()),
+            ));
+        }
+        res.body_bytes().await
+    };
+
+    let buf = match timeout {
+        Some(duration) => async_std::future::timeout(duration, request)
+            .await
+            .map_err(|_| {
+                surf::Error::from_str(surf::StatusCode::RequestTimeout, "Request timed out")
+            })?,
+        None => request.await,
     }?;
 
-    let first_128 = buf.iter().take(1024).map(|x| *x).collect::<Vec<u8>>();
+    Ok(buf)
+}
+
+/// Trys to download an image to each of the given paths concurrently, aborting any downloads still running once `remaining` has passed
+async fn download_n(
+    images: Vec<Image>,
+    paths: Vec<PathBuf>,
+    args: &Arguments,
+    remaining: Option<Duration>,
+    session: &Session,
+    format_rejected: &AtomicUsize,
+) -> Vec<Result<DownloadedFile, DownloadError>> {
+    let mut_images = Arc::new(Mutex::new(images));
+    let hosts = HostSlots::default();
+
+    let mut downloaders = Vec::new();
+    for path in paths {
+        let downloader = download_until(
+            mut_images.clone(),
+            &hosts,
+            path,
+            session.client.clone(),
+            args,
+            format_rejected,
+        );
+        downloaders.push(async move {
+            match remaining {
+                Some(remaining) => async_std::future::timeout(remaining, downloader)
+                    .await
+                    .unwrap_or(Err(DownloadError::Deadline)),
+                None => downloader.await,
+            }
+        });
+    }
+
+    future::join_all(downloaders).await
+}
+
+/// The number of downloads currently running against each host and when the last one started, used to enforce `Arguments::max_per_host` and `Arguments::host_delay`.
+struct HostSlots {
+    active: Mutex<HashMap<String, usize>>,
+    last_started: Mutex<HashMap<String, Instant>>,
+    /// Receives a message each time a slot is freed, waking one of the downloaders waiting for a host.
+    freed: (
+        async_std::channel::Sender<()>,
+        async_std::channel::Receiver<()>,
+    ),
+}
+
+impl Default for HostSlots {
+    fn default() -> Self {
+        HostSlots {
+            active: Mutex::new(HashMap::new()),
+            last_started: Mutex::new(HashMap::new()),
+            freed: async_std::channel::unbounded(),
+        }
+    }
+}
+
+impl HostSlots {
+    /// Wakes one of the downloaders waiting for a host.
+    fn notify(&self) {
+        let _ = self.freed.0.try_send(());
+    }
+}
+
+/// A download running against `host`, which frees its slot when dropped (including when the download is aborted).
+struct HostSlot<'a> {
+    slots: &'a HostSlots,
+    host: String,
+}
+
+impl Drop for HostSlot<'_> {
+    fn drop(&mut self) {
+        let mut active = self
+            .slots
+            .active
+            .lock()
+            .expect("Other downloading thread panicked");
+        if let Some(count) = active.get_mut(&self.host) {
+            *count -= 1;
+            if *count == 0 {
+                active.remove(&self.host);
+            }
+        }
+        drop(active);
+        self.slots.notify();
+    }
+}
+
+/// Takes the first remaining image whose host is below `Arguments::max_per_host` and outside of `Arguments::host_delay`, waiting for a host to free up if every remaining host is busy.
+/// Waiting downloaders sleep until a slot is freed or the earliest `host_delay` among the remaining hosts has passed.
+async fn next_available<'a>(
+    images: &Mutex<Vec<Image>>,
+    hosts: &'a HostSlots,
+    args: &Arguments,
+) -> Result<(Image, HostSlot<'a>), DownloadError> {
+    loop {
+        let wait = {
+            let mut mut_images = images.lock().expect("Other downloading thread panicked"); // SAFETY: no thread should panic while holding, since nothing that can panic is called while locked
+            if mut_images.is_empty() {
+                // Passes the wakeup on, so that every other waiting downloader finds out there's nothing left
+                hosts.notify();
+                return Err(DownloadError::Overflow);
+            }
+
+            let mut active = hosts
+                .active
+                .lock()
+                .expect("Other downloading thread panicked");
+            let mut last_started = hosts
+                .last_started
+                .lock()
+                .expect("Other downloading thread panicked");
+
+            // The time until each host is outside of its delay, or `None` if it's at `max_per_host` and has to wait for a slot instead
+            let wait_for = |image: &Image| -> Option<Duration> {
+                let host = url_host(&download_url(image, args));
+                if args
+                    .max_per_host
+                    .is_some_and(|max| active.get(&host).copied().unwrap_or(0) >= max.max(1))
+                {
+                    return None;
+                }
+                match (args.host_delay, last_started.get(&host)) {
+                    (Some(delay), Some(started)) => Some(delay.saturating_sub(started.elapsed())),
+                    _ => Some(Duration::ZERO),
+                }
+            };
+
+            let available = mut_images
+                .iter()
+                .position(|image| wait_for(image) == Some(Duration::ZERO));
+            if let Some(index) = available {
+                let image = mut_images.remove(index);
+                let host = url_host(&download_url(&image, args));
+                *active.entry(host.clone()).or_default() += 1;
+                if args.host_delay.is_some() {
+                    last_started.insert(host.clone(), Instant::now());
+                }
+                return Ok((image, HostSlot { slots: hosts, host }));
+            }
+
+            mut_images.iter().filter_map(wait_for).min()
+        };
+
+        match wait {
+            Some(wait) => {
+                let _ = async_std::future::timeout(wait, hosts.freed.1.recv()).await;
+            }
+            None => {
+                let _ = hosts.freed.1.recv().await;
+            }
+        }
+    }
+}
+
+/// Trys to download an image to a given path until one is successful or it runs out of possible images
+async fn download_until(
+    images: Arc<Mutex<Vec<Image>>>,
+    hosts: &HostSlots,
+    path: PathBuf,
+    client: surf::Client,
+    args: &Arguments,
+    format_rejected: &AtomicUsize,
+) -> Result<DownloadedFile, DownloadError> {
+    loop {
+        let (image, _slot) = next_available(&images, hosts, args).await?;
+        let url = download_url(&image, args);
+        match download_image(client.clone(), &path, image, args).await {
+            Ok(mut file) => {
+                // Files that came from an archive are already archived
+                if args.save_to_archive && file.archive.is_none() {
+                    file.archived = save_to_wayback(&client, &file.url, args).await;
+                }
+
+                args.emit(DownloadEvent::Downloaded(file.clone()));
+                return Ok(file);
+            }
+            Err(err) => {
+                if matches!(err, DownloadError::Format) {
+                    format_rejected.fetch_add(1, Ordering::Relaxed);
+                }
+                args.emit(DownloadEvent::Skipped {
+                    url,
+                    reason: err.to_string(),
+                })
+            }
+        }
+    }
+}
+
+/// The url that will be downloaded for `image`, depending on whether thumbnails are being downloaded.
+fn download_url(image: &Image, args: &Arguments) -> String {
+    if args.thumbnails {
+        image.thumbnail.clone()
+    } else {
+        image.url.clone()
+    }
+}
+
+/// Runs the checks of `download_image` that decode or scan the image in `buf` (for watermarks, text, and faces) on a blocking thread,
+/// so they don't hold up the other downloads. The image is decoded at most once for all of them.
+/// `buf` is handed back along with whether the image is watermarked and how many faces it has, if those were checked.
+// The urls are only used to check for watermarks
+#[cfg_attr(not(feature = "image"), allow(unused_variables))]
+async fn inspect(
+    buf: Vec<u8>,
+    url: &str,
+    source: &str,
+    args: &Arguments,
+) -> (
+    Vec<u8>,
+    Result<(Option<bool>, Option<usize>), DownloadError>,
+) {
+    #[cfg(feature = "image")]
+    let (watermarks, max_text_density) = (args.watermarks, args.max_text_density);
+    #[cfg(feature = "image")]
+    let decodes = !matches!(watermarks, Watermarks::Ignore) || max_text_density.is_some();
+    #[cfg(not(feature = "image"))]
+    let decodes = false;
+
+    let faces = args.faces.clone();
+    if !decodes && faces.is_none() {
+        return (buf, Ok((None, None)));
+    }
+
+    #[cfg(feature = "image")]
+    let (url, source) = (url.to_owned(), source.to_owned());
+    async_std::task::spawn_blocking(move || {
+        let checked = (|| {
+            #[cfg(feature = "image")]
+            let decoded = decodes
+                .then(|| image::load_from_memory(&buf).ok())
+                .flatten();
+
+            #[cfg(feature = "image")]
+            let watermarked = match watermarks {
+                Watermarks::Ignore => None,
+                check => {
+                    let watermarked = watermark::is_watermarked(decoded.as_ref(), &url, &source);
+                    if watermarked && matches!(check, Watermarks::Reject) {
+                        return Err(DownloadError::Watermark);
+                    }
+                    Some(watermarked)
+                }
+            };
+            #[cfg(not(feature = "image"))]
+            let watermarked = None;
+
+            #[cfg(feature = "image")]
+            if let (Some(max), Some(decoded)) = (max_text_density, &decoded) {
+                if text::text_density(decoded).is_some_and(|density| density > max) {
+                    return Err(DownloadError::Text);
+                }
+            }
+
+            let faces = match &faces {
+                Some((faces, FaceDetector(detector))) => match detector(&buf) {
+                    Some(count) if faces.allows(count) => Some(count),
+                    _ => return Err(DownloadError::Faces),
+                },
+                None => None,
+            };
+
+            Ok((watermarked, faces))
+        })();
+
+        (buf, checked)
+    })
+    .await
+}
+
+async fn download_image(
+    client: surf::Client,
+    path: &Path,
+    image: Image,
+    args: &Arguments,
+) -> Result<DownloadedFile, DownloadError> {
+    let url = download_url(&image, args);
+
+    if args.head_check {
+        head_check(&client, &url, args).await?;
+    }
+
+    let (url, buf, archive) = match get_bytes(&client, &url, args).await {
+        Err(err) if args.archive_fallback && is_dead(&err) => {
+            fetch_archived(&client, &url, args).await.ok_or(err)?
+        }
+        res => (url, res?, None),
+    };
+
+    check_size(buf.len() as u64, args)?;
+
+    let (buf, checked) = inspect(buf, &url, &image.source, args).await;
+    let (watermarked, faces) = checked?;
+
+    let (with_extension, location) = write_image(&buf, path, &url, &image, args)?;
+
+    let dimensions = imagesize::blob_size(&buf)
+        .ok()
+        .map(|size| (size.width, size.height));
+    // Google reports the dimensions of the full size image, so the thumbnail dimensions are expected to differ
+    let dimension_mismatch = match dimensions {
+        Some((width, height)) if !args.thumbnails || args.thumbnails_only => {
+            width as i64 != image.width || height as i64 != image.height
+        }
+        _ => false,
+    };
+
+    #[cfg(feature = "image")]
+    let (path, frames) = match location {
+        Location::Path(_) => save_frames(&buf, with_extension, args)?,
+        _ => (with_extension, Vec::new()),
+    };
+    #[cfg(not(feature = "image"))]
+    let (path, frames) = (with_extension, Vec::new());
+
+    Ok(DownloadedFile {
+        path,
+        location,
+        url,
+        image,
+        dimensions,
+        dimension_mismatch,
+        frames,
+        archive,
+        archived: false,
+        watermarked,
+        faces,
+    })
+}
+
+/// Asks the Wayback Machine to save a snapshot of `url`, returning whether the request was accepted.
+async fn save_to_wayback(client: &surf::Client, url: &str, args: &Arguments) -> bool {
+    let request = client.get(format!("https://web.archive.org/save/{}", url));
+    let res = match args.timeout {
+        Some(duration) => async_std::future::timeout(duration, request).await.ok(),
+        None => Some(request.await),
+    };
+
+    // The save API redirects to the new snapshot once it has been taken
+    res.is_some_and(|res| {
+        res.is_ok_and(|res| res.status().is_success() || res.status().is_redirection())
+    })
+}
+
+/// Whether `err` was caused by the url returning `404 Not Found` or `410 Gone`.
+fn is_dead(err: &DownloadError) -> bool {
+    matches!(
+        err,
+        DownloadError::Network(err)
+            if matches!(err.status(), surf::StatusCode::NotFound | surf::StatusCode::Gone)
+    )
+}
+
+/// Downloads an archived copy of `url` if there is one that is an image, returning the url it was downloaded from and the archive it came from.
+async fn fetch_archived(
+    client: &surf::Client,
+    url: &str,
+    args: &Arguments,
+) -> Option<(String, Vec<u8>, Option<Archive>)> {
+    let archive = Archive::Wayback;
+    let archived = archive.url(url);
+    let request = async {
+        let mut res = client
+            .get(&archived)
+            .middleware(surf::middleware::Redirect::default())
+            .await?;
+        if !res.status().is_success() {
+            return Err(surf::Error::from_str(res.status(), "No archived copy"));
+        }
+        res.body_bytes().await
+    };
+
+    let buf = match args.timeout {
+        Some(duration) => async_std::future::timeout(duration, request).await.ok(),
+        None => Some(request.await),
+    };
+
+    // The archive returns a page rather than an error for some missing files, so anything that isn't an image is skipped
+    match buf {
+        Some(Ok(buf)) if infer::is_image(&buf) => Some((archived, buf, Some(archive))),
+        _ => None,
+    }
+}
+
+/// Extracts the frames of the GIF at `path` if `Arguments::extract_frames` is set, returning the path of the downloaded file (which is the first frame if the GIF isn't kept) and the paths of the frames.
+#[cfg(feature = "image")]
+fn save_frames(
+    buf: &[u8],
+    path: PathBuf,
+    args: &Arguments,
+) -> Result<(PathBuf, Vec<PathBuf>), DownloadError> {
+    let frames = match args.extract_frames {
+        Some(selection) => frames::extract_frames(buf, &path, selection)?,
+        None => return Ok((path, Vec::new())),
+    };
+
+    if args.keep_gif || frames.is_empty() {
+        Ok((path, frames))
+    } else {
+        std::fs::remove_file(&path)?;
+        Ok((frames[0].clone(), frames))
+    }
+}
+
+/// Downloads the body of `url`, retrying if the host rate limits the request with a short enough `Retry-After`.
+/// The timeout applies to each attempt separately, so the time spent waiting to retry isn't counted against it.
+async fn get_bytes(
+    client: &surf::Client,
+    url: &str,
+    args: &Arguments,
+) -> Result<Vec<u8>, DownloadError> {
+    let mut retries = 0;
+    loop {
+        let max_wait = if retries < MAX_RETRIES {
+            args.max_retry_wait
+        } else {
+            None
+        };
+        let attempt = async {
+            let mut res = client.get(url).await?;
+            if matches!(
+                res.status(),
+                surf::StatusCode::NotFound | surf::StatusCode::Gone
+            ) {
+                return Err(surf::Error::from_str(
+                    res.status(),
+                    format!("{} returned {}", url, res.status()),
+                ));
+            }
+            match retry_after(&res, max_wait) {
+                Some(wait) => Ok(Err(wait)),
+                None => res.body_bytes().await.map(Ok),
+            }
+        };
+
+        let result = match args.timeout {
+            Some(duration) => async_std::future::timeout(duration, attempt).await?,
+            None => attempt.await,
+        }?;
+
+        match result {
+            Ok(buf) => return Ok(buf),
+            Err(wait) => {
+                retries += 1;
+                async_std::task::sleep(wait).await;
+            }
+        }
+    }
+}
+
+/// Makes a HEAD request to `url`, returning an error if the headers show that it isn't an image or is outside of the file size limits.
+/// Missing headers and failed HEAD requests are not treated as errors, since some hosts don't handle HEAD requests properly.
+async fn head_check(
+    client: &surf::Client,
+    url: &str,
+    args: &Arguments,
+) -> Result<(), DownloadError> {
+    let head = match args.timeout {
+        Some(duration) => match async_std::future::timeout(duration, client.head(url)).await {
+            Ok(res) => res,
+            Err(_) => return Ok(()),
+        },
+        None => client.head(url).await,
+    };
+
+    let res = match head {
+        Ok(res) if res.status().is_success() => res,
+        _ => return Ok(()),
+    };
+
+    if let Some(mime) = res.content_type() {
+        if mime.basetype() != "image" {
+            return Err(DownloadError::ContentType);
+        }
+    }
+
+    if let Some(length) = res.len() {
+        check_size(length as u64, args)?;
+    }
+
+    Ok(())
+}
+
+/// Checks that a file `size` bytes long is within the file size limits.
+fn check_size(size: u64, args: &Arguments) -> Result<(), DownloadError> {
+    let too_small = args.min_file_size.map(|min| size < min).unwrap_or(false);
+    let too_large = args.max_file_size.map(|max| size > max).unwrap_or(false);
+
+    if too_small || too_large {
+        Err(DownloadError::Size)
+    } else {
+        Ok(())
+    }
+}
+
+/// Adds `.extension` to the end of `path`, keeping any dots that are already in the file name.
+fn append_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+/// Determines the extension of the image in `buf` and writes it to `path` with that extension, returning the full path of the file.
+fn write_image(
+    buf: &[u8],
+    path: &Path,
+    url: &str,
+    image: &Image,
+    args: &Arguments,
+) -> Result<(PathBuf, Location), DownloadError> {
+    let first_128 = buf.iter().take(1024).copied().collect::<Vec<u8>>();
     let svg = match std::str::from_utf8(&first_128) {
         Ok(s) => s.contains("<svg"),
         Err(_) => false,
     };
 
     let extension = if svg {
+        if !args.allow_svg {
+            return Err(DownloadError::Svg);
+        }
         "svg".to_owned()
     } else {
-        let kind = match infer::get(&buf) {
+        let kind = match infer::get(buf) {
             Some(k) => k,
             None => return Err(DownloadError::Extension),
         };
 
-        if kind.matcher_type() != infer::MatcherType::Image {
+        // Videos are only kept when the url is for one, such as the MP4 versions of animations from `providers::Giphy` and `providers::Tenor`
+        let video = kind.mime_type() == "video/mp4"
+            && surf::Url::parse(url).is_ok_and(|url| url.path().ends_with(".mp4"));
+        if kind.matcher_type() != infer::MatcherType::Image && !video {
             return Err(DownloadError::Extension);
         }
 
         kind.extension().to_owned()
     };
 
-    let with_extension = path.clone().with_extension(extension);
+    if args.reject_animated && is_animated(buf) {
+        return Err(DownloadError::Animated);
+    }
+
+    if args.strict_format
+        && !args.formats.is_empty()
+        && !args
+            .formats
+            .iter()
+            .any(|format| format.matches_extension(&extension))
+    {
+        return Err(DownloadError::Format);
+    }
 
-    let mut f = match File::create(&with_extension) {
-        Ok(f) => f,
-        Err(e) => return Err(DownloadError::Fs(e)),
+    let with_extension = match args.naming {
+        Naming::Query => append_extension(path, &extension),
+        Naming::ContentHash => path.with_file_name(format!("{}.{}", content_hash(buf), extension)),
     };
 
-    match f.write_all(&buf) {
-        Ok(_) => (),
-        Err(e) => return Err(DownloadError::Fs(e)),
+    // The same content always hashes to the same name, so there's no need to write it again
+    if args.storage.is_none()
+        && matches!(args.naming, Naming::ContentHash)
+        && with_extension.exists()
+    {
+        return Ok((with_extension.clone(), Location::Path(with_extension)));
+    }
+
+    let metadata = Metadata {
+        url: url.to_owned(),
+        image: image.clone(),
+        extension,
+    };
+    let location = match &args.storage {
+        Some(Storage(sink)) => sink.store(&with_extension, buf, &metadata)?,
+        None => FileSink.store(&with_extension, buf, &metadata)?,
     };
 
-    Ok(with_extension)
+    #[cfg(unix)]
+    if let (Some(mode), Location::Path(path)) = (args.file_mode, &location) {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+
+    Ok((with_extension, location))
 }
 
-pub(crate) fn build_url(args: &Arguments) -> String {
-    let mut url = "https://www.google.com/search?udm=2&q=".to_string() + &args.query;
+/// The longest file name (before the number and extension are added) that is created from a query, leaving room for the directory within the 260 character path limit on Windows.
+const MAX_FILE_NAME: usize = 64;
+
+/// The names Windows reserves for devices, which can't be used as file names even with an extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Turns `query` into a name that can be used for files and directories on every platform.
+/// Characters that aren't allowed in file names are replaced with `_`, reserved device names are prefixed with `_`,
+/// and names that are too long are truncated and given a hash of the full query so that different long queries don't collide.
+fn safe_file_name(query: &str) -> String {
+    let mut name: String = query
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    // Windows strips trailing dots and spaces, which would make the name refer to a different file
+    name.truncate(name.trim_end_matches(['.', ' ']).len());
+    if name.is_empty() {
+        name.push('_');
+    }
 
-    let params = args.params();
-    if params.len() > 0 {
-        url += &"&tbs=ic:specific".to_string();
-        url += &params;
+    let base = name.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| base.eq_ignore_ascii_case(reserved))
+    {
+        name.insert(0, '_');
     }
 
-    url
+    if name.chars().count() > MAX_FILE_NAME {
+        let hash = content_hash(query.as_bytes());
+        let truncated: String = name.chars().take(MAX_FILE_NAME - 9).collect();
+        name = format!("{}-{}", truncated, &hash[..8]);
+    }
+
+    name
+}
+
+/// The length at which a path on Windows needs the `\\?\` prefix to go past the 260 character limit, leaving room for the file name.
+#[cfg(windows)]
+const LONG_PATH: usize = 180;
+
+/// Adds the `\\?\` prefix to `dir` if it is long enough that the files in it could go past the 260 character path limit on Windows.
+#[cfg(windows)]
+fn long_path(dir: PathBuf) -> PathBuf {
+    if dir.as_os_str().len() < LONG_PATH {
+        return dir;
+    }
+    // Canonicalizing returns the path with the prefix on Windows
+    dir.canonicalize().unwrap_or(dir)
 }
 
-async fn get(url: String) -> Result<String, surf::Error> {
-    Ok(surf::get(url)
-        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/88.0.4324.104 Safari/537.36")
-        .recv_string()
-        .await?)
+/// Creates `dir` and any missing parents, giving them the permissions set with `Arguments::dir_mode`.
+fn create_dir(dir: &Path, args: &Arguments) -> io::Result<()> {
+    #[cfg(unix)]
+    if let Some(mode) = args.dir_mode {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(mode)
+            .create(dir)?;
+        // The mode given when creating is restricted by the umask, so the download directory is set explicitly
+        return std::fs::set_permissions(dir, std::fs::Permissions::from_mode(mode));
+    }
+
+    #[cfg(not(unix))]
+    let _ = args;
+    std::fs::create_dir_all(dir)
 }
 
-/// shorthand for unwrap_or_continue
-macro_rules! uoc {
-    ($opt: expr) => {
-        match $opt {
-            Some(v) => v,
-            None => {
-                continue;
+/// Checks whether `buf` contains an animated GIF, WebP, or PNG (APNG).
+fn is_animated(buf: &[u8]) -> bool {
+    if buf.starts_with(b"GIF8") {
+        gif_frames(buf) > 1
+    } else if buf.len() > 20 && &buf[..4] == b"RIFF" && &buf[8..12] == b"WEBP" {
+        // The animation flag is stored in the extended format (VP8X) header
+        &buf[12..16] == b"VP8X" && buf[20] & 0x02 != 0
+    } else if buf.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png_has_animation_control(buf)
+    } else {
+        false
+    }
+}
+
+/// Counts the number of frames in a GIF by walking its blocks, stopping early if the data is malformed.
+fn gif_frames(buf: &[u8]) -> usize {
+    /// Skips a sequence of data sub-blocks starting at `pos`, returning the position after the terminator.
+    fn skip_sub_blocks(buf: &[u8], mut pos: usize) -> Option<usize> {
+        loop {
+            let size = *buf.get(pos)? as usize;
+            pos += 1;
+            if size == 0 {
+                return Some(pos);
             }
+            pos += size;
         }
+    }
+
+    let flags = match buf.get(10) {
+        Some(flags) => *flags,
+        None => return 0,
     };
+    let mut pos = 13;
+    if flags & 0x80 != 0 {
+        pos += 3 * (1 << ((flags & 0x07) + 1));
+    }
+
+    let mut frames = 0;
+    while let Some(block) = buf.get(pos) {
+        match block {
+            // Image descriptor
+            0x2C => {
+                frames += 1;
+                let flags = match buf.get(pos + 9) {
+                    Some(flags) => *flags,
+                    None => break,
+                };
+                pos += 10;
+                if flags & 0x80 != 0 {
+                    pos += 3 * (1 << ((flags & 0x07) + 1));
+                }
+                // Skip the LZW minimum code size before the image data
+                pos = match skip_sub_blocks(buf, pos + 1) {
+                    Some(pos) => pos,
+                    None => break,
+                };
+            }
+            // Extension
+            0x21 => {
+                pos = match skip_sub_blocks(buf, pos + 2) {
+                    Some(pos) => pos,
+                    None => break,
+                };
+            }
+            _ => break,
+        }
+
+        if frames > 1 {
+            break;
+        }
+    }
+
+    frames
 }
 
-pub(crate) fn unpack(recv: String) -> Option<Vec<Image>> {
-    let start = recv.find("var m={")? + "var m=".len();
-    let mut body = &recv[start..];
+/// Checks whether a PNG contains an animation control (`acTL`) chunk before its image data, marking it as an APNG.
+fn png_has_animation_control(buf: &[u8]) -> bool {
+    let mut pos = 8;
+    while pos + 8 <= buf.len() {
+        let length =
+            u32::from_be_bytes([buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3]]) as usize;
+        match &buf[pos + 4..pos + 8] {
+            b"acTL" => return true,
+            b"IDAT" | b"IEND" => return false,
+            _ => (),
+        }
+        // Chunk length, type, data, and CRC, which can overflow on 32-bit targets with a malformed length
+        pos = match pos.checked_add(12).and_then(|pos| pos.checked_add(length)) {
+            Some(pos) => pos,
+            None => return false,
+        };
+    }
+
+    false
+}
 
-    let script_end = body.find("var a=m")?;
-    body = &body[..script_end];
+/// Returns the first 16 hex digits of the SHA-256 hash of `buf`.
+fn content_hash(buf: &[u8]) -> String {
+    let hash = format!("{:x}", Sha256::digest(buf));
+    hash[..16].to_owned()
+}
 
-    let end = body.rfind(";")?;
-    body = &body[..end];
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A GIF header and logical screen descriptor without a global color table.
+    const GIF_HEADER: &[u8] = b"GIF89a\x01\x00\x01\x00\x00\x00\x00";
+    /// A graphic control extension, which comes before each frame of an animation.
+    const GIF_CONTROL: &[u8] = b"\x21\xF9\x04\x00\x0A\x00\x00\x00";
+    /// An image descriptor for a 1x1 frame, followed by its LZW code size and a single sub-block of data.
+    const GIF_FRAME: &[u8] = b"\x2C\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x01\x44\x00";
+
+    fn gif(frames: usize) -> Vec<u8> {
+        let mut buf = GIF_HEADER.to_vec();
+        for _ in 0..frames {
+            buf.extend_from_slice(GIF_CONTROL);
+            buf.extend_from_slice(GIF_FRAME);
+        }
+        buf.push(0x3B);
+        buf
+    }
 
-    let json: serde_json::Value = serde_json::from_str(&body).ok()?;
+    fn png(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut buf = b"\x89PNG\r\n\x1a\n".to_vec();
+        for (kind, data) in chunks {
+            buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            buf.extend_from_slice(*kind);
+            buf.extend_from_slice(data);
+            // The CRC isn't checked
+            buf.extend_from_slice(&[0; 4]);
+        }
+        buf
+    }
 
-    let image_objects = json
-        .as_object()?
-        .values()
-        .filter(|list| {
-            list.as_array()
-                .map(|list| {
-                    list.get(0).map(|value| value.is_u64()).unwrap_or(false)
-                        && list.get(1).map(|value| value.is_array()).unwrap_or(false)
-                })
-                .unwrap_or(false)
-        })
-        .map(|image| image.as_array().unwrap()[1].as_array().unwrap());
-
-    let mut images: Vec<Image> = Vec::new();
-    for obj in image_objects {
-        let (url, width, height) = match obj[3].as_array() {
-            Some(i) => (
-                uoc!(i[0].as_str()).to_string(),
-                uoc!(i[1].as_i64()),
-                uoc!(i[2].as_i64()),
-            ),
-            None => continue,
-        };
+    #[test]
+    fn gif_frames_are_counted() {
+        assert_eq!(gif_frames(&gif(0)), 0);
+        assert_eq!(gif_frames(&gif(1)), 1);
+        // Counting stops as soon as the GIF is known to be animated
+        assert_eq!(gif_frames(&gif(3)), 2);
+        // Truncated data stops the walk, counting the frame it stopped in
+        assert_eq!(gif_frames(&gif(2)[..GIF_HEADER.len() + 12]), 1);
+        assert_eq!(gif_frames(b"GIF89a"), 0);
+        assert!(!is_animated(&gif(1)));
+        assert!(is_animated(&gif(2)));
+    }
 
-        let image = Image {
-            url,
-            width,
-            height,
-            thumbnail: uoc!(uoc!(obj[2].as_array())[0].as_str()).to_string(),
-            source: uoc!(uoc!(uoc!(obj[9].as_object())["2003"].as_array())[2].as_str()).to_string(),
-        };
+    #[test]
+    fn apng_is_animated() {
+        let ihdr: (&[u8; 4], &[u8]) = (b"IHDR", &[0; 13]);
+        let actl: (&[u8; 4], &[u8]) = (b"acTL", &[0; 8]);
+        let idat: (&[u8; 4], &[u8]) = (b"IDAT", &[0; 4]);
+
+        assert!(png_has_animation_control(&png(&[ihdr, actl, idat])));
+        assert!(!png_has_animation_control(&png(&[ihdr, idat, actl])));
+        assert!(!png_has_animation_control(&png(&[ihdr])));
+        assert!(is_animated(&png(&[ihdr, actl, idat])));
+        assert!(!is_animated(&png(&[ihdr, idat])));
+    }
 
-        images.push(image);
+    #[test]
+    fn malformed_png_length_is_not_animated() {
+        let mut buf = png(&[(b"IHDR", &[0; 13])]);
+        buf.extend_from_slice(&u32::MAX.to_be_bytes());
+        buf.extend_from_slice(b"tEXt");
+        assert!(!png_has_animation_control(&buf));
     }
 
-    Some(images)
+    #[test]
+    fn animated_webp_flag() {
+        let mut webp = b"RIFF\x00\x00\x00\x00WEBPVP8X\x0A\x00\x00\x00".to_vec();
+        webp.extend_from_slice(&[0x02, 0, 0, 0]);
+        assert!(is_animated(&webp));
+        webp[20] = 0;
+        assert!(!is_animated(&webp));
+        assert!(!is_animated(b"not an image"));
+    }
+
+    #[test]
+    fn file_names_are_safe() {
+        assert_eq!(safe_file_name("red panda"), "red panda");
+        assert_eq!(safe_file_name("a/b\\c:d*e?"), "a_b_c_d_e_");
+        assert_eq!(safe_file_name("tab\there"), "tab_here");
+        assert_eq!(safe_file_name("trailing. "), "trailing");
+        assert_eq!(safe_file_name("..."), "_");
+        assert_eq!(safe_file_name("con"), "_con");
+        assert_eq!(safe_file_name("LPT1.txt"), "_LPT1.txt");
+        assert_eq!(safe_file_name("console"), "console");
+    }
+
+    #[test]
+    fn long_file_names_are_hashed() {
+        let long = "a".repeat(MAX_FILE_NAME * 2);
+        let name = safe_file_name(&long);
+        assert_eq!(name.chars().count(), MAX_FILE_NAME);
+        assert_ne!(name, safe_file_name(&"a".repeat(MAX_FILE_NAME * 3)));
+    }
+
+    #[test]
+    fn tracking_params_are_stripped() {
+        assert_eq!(
+            strip_tracking("https://example.com/image.jpg?utm_source=x&id=5&fbclid=abc"),
+            "https://example.com/image.jpg?id=5"
+        );
+        assert_eq!(
+            strip_tracking("https://example.com/image.jpg?utm_medium=a&gclid=b"),
+            "https://example.com/image.jpg"
+        );
+        assert_eq!(
+            strip_tracking("https://example.com/image.jpg?size=large"),
+            "https://example.com/image.jpg?size=large"
+        );
+        assert_eq!(strip_tracking("not a url"), "not a url");
+    }
+
+    #[test]
+    fn time_params() {
+        assert_eq!(Time::None.param(), "");
+        assert_eq!(Time::Hour.param(), "qdr:h");
+        assert_eq!(Time::Week.param(), "qdr:w");
+        assert_eq!(
+            Time::Within(Duration::from_secs(90 * 60)).param(),
+            "qdr:n90"
+        );
+        assert_eq!(
+            Time::Within(Duration::from_secs(3 * 60 * 60)).param(),
+            "qdr:h3"
+        );
+        assert_eq!(
+            Time::Within(Duration::from_secs(2 * 24 * 60 * 60)).param(),
+            "qdr:d2"
+        );
+        // Partial minutes are rounded up, and there is always at least one
+        assert_eq!(Time::Within(Duration::from_secs(61)).param(), "qdr:n2");
+        assert_eq!(Time::Within(Duration::ZERO).param(), "qdr:n1");
+    }
+
+    #[test]
+    fn time_rounds_up() {
+        assert!(matches!(Time::Hour.round_up(), Time::Day));
+        assert!(matches!(
+            Time::Within(Duration::from_secs(3 * 24 * 60 * 60)).round_up(),
+            Time::Week
+        ));
+        assert!(matches!(
+            Time::Within(Duration::from_secs(400 * 24 * 60 * 60)).round_up(),
+            Time::None
+        ));
+        assert!(matches!(Time::Month.round_up(), Time::Month));
+    }
+
+    /// A 1x1 transparent PNG.
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR\x00\x00\x00\x01\x00\x00\x00\x01\x08\x06\x00\x00\x00\x1f\x15\xc4\x89\x00\x00\x00\nIDAT\x78\x9c\x63\x00\x01\x00\x00\x05\x00\x01\x0d\x0a\x2d\xb4\x00\x00\x00\x00IEND\xaeB`\x82";
+
+    /// A provider that returns `count` copies of an image served on localhost.
+    #[derive(Debug)]
+    struct Local {
+        url: String,
+        count: usize,
+    }
+
+    impl SearchProvider for Local {
+        fn page_size(&self) -> usize {
+            10
+        }
+
+        fn fetch_page<'a>(
+            &'a self,
+            request: PageRequest<'a>,
+        ) -> futures::future::BoxFuture<'a, SearchResult<Vec<Image>>> {
+            Box::pin(async move {
+                if request.page() > 0 {
+                    return Ok(Vec::new());
+                }
+                Ok((0..self.count)
+                    .map(|i| {
+                        let url = format!("{}{}.png", self.url, i);
+                        Image::new(&url, 1, 1, &url, "http://localhost/")
+                    })
+                    .collect())
+            })
+        }
+    }
+
+    /// Serves `PNG` for every request on localhost, returning the url of the server.
+    fn serve_png() -> String {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buf = [0; 4096];
+                let _ = stream.read(&mut buf);
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    PNG.len()
+                );
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(PNG);
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn download_dotted_query_twice() {
+        let dir = env::temp_dir().join(format!("image_search_dotted_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let url = serve_png();
+
+        let mut names = Vec::new();
+        for _ in 0..2 {
+            let args = Arguments::new("mr. bean", 2)
+                .directory(&dir)
+                .provider(Local {
+                    url: url.clone(),
+                    count: 2,
+                });
+            for path in download(args).await.unwrap() {
+                names.push(path.file_name().unwrap().to_string_lossy().into_owned());
+            }
+        }
+        let mut files: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(
+            names,
+            [
+                "mr. bean0.png",
+                "mr. bean1.png",
+                "mr. bean2.png",
+                "mr. bean3.png"
+            ]
+        );
+        assert_eq!(files, names);
+    }
+
+    #[tokio::test]
+    async fn download_waits_for_host_slots() {
+        let dir = env::temp_dir().join(format!("image_search_hosts_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let delay = Duration::from_millis(50);
+        let args = Arguments::new("cat", 3)
+            .directory(&dir)
+            .max_per_host(1)
+            .host_delay(delay)
+            .provider(Local {
+                url: serve_png(),
+                count: 5,
+            });
+        let started = Instant::now();
+        let report = download_report(args).await.unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(report.files.len(), 3);
+        // Every download is from the same host, so each one after the first waits out the delay
+        assert!(started.elapsed() >= delay * 2);
+    }
 }
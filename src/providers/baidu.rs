@@ -0,0 +1,255 @@
+//! Baidu Images, which reads the results from the JSON API (`acjson`) that Baidu's own page loads them from.
+
+use super::{PageRequest, SearchProvider};
+use crate::{
+    Color, ColorType, Error, Image, ImageType, ParseStage, ParseStats, SearchResult, SkipReason,
+};
+use futures::future::BoxFuture;
+
+/// The number of results Baidu returns for each page.
+const PAGE_SIZE: usize = 30;
+
+/// Searches Baidu Images, which is usable from mainland China and gives much better results than Google for queries in Chinese.
+///
+/// Only `Arguments::color`, `ColorType::Grayscale`, and `ImageType::Face` are supported, and the other filters are ignored.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Baidu;
+
+impl SearchProvider for Baidu {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let body = request.get(&build_url(&request)).await?;
+            let (images, stats) = unpack(&body, request.thumbnails_only())?;
+            request.add_stats(stats);
+
+            Ok(images)
+        })
+    }
+}
+
+/// Builds the url of the page of results asked for by `request`.
+fn build_url(request: &PageRequest) -> String {
+    let query = request.query();
+    let mut url =
+        surf::Url::parse("https://image.baidu.com/search/acjson").expect("Baidu url is valid");
+    url.query_pairs_mut()
+        .append_pair("tn", "resultjson_com")
+        .append_pair("ipn", "rj")
+        .append_pair("word", &query)
+        .append_pair("queryWord", &query)
+        .append_pair("ie", "utf-8")
+        .append_pair("oe", "utf-8")
+        .append_pair("pn", &(request.page() * PAGE_SIZE).to_string())
+        .append_pair("rn", &PAGE_SIZE.to_string());
+
+    // Baidu's colors are bit flags
+    let color = match (request.color(), request.color_type()) {
+        (Color::Red, _) => "1",
+        (Color::Yellow, _) => "2",
+        (Color::Green, _) => "4",
+        (Color::Teal, _) => "8",
+        (Color::Blue, _) => "16",
+        (Color::Purple, _) => "32",
+        (Color::Pink, _) => "64",
+        (Color::Brown, _) => "128",
+        (Color::Orange, _) => "256",
+        (Color::Black, _) => "512",
+        (Color::White, _) => "1024",
+        (Color::Gray, _) | (Color::None, ColorType::Grayscale) => "2048",
+        (Color::None, _) => "",
+    };
+    if !color.is_empty() {
+        url.query_pairs_mut().append_pair("ic", color);
+    }
+    if let ImageType::Face = request.image_type() {
+        url.query_pairs_mut().append_pair("face", "1");
+    }
+
+    url.to_string()
+}
+
+/// Parses the images from a page of Baidu's results, along with how many results were skipped and why.
+fn unpack(body: &str, thumbnails_only: bool) -> SearchResult<(Vec<Image>, ParseStats)> {
+    // Baidu escapes single quotes, which isn't valid JSON
+    let body = body.replace("\\'", "'");
+    let json: serde_json::Value = serde_json::from_str(&body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+    let data = json
+        .get("data")
+        .and_then(|data| data.as_array())
+        .ok_or_else(|| Error::parse(ParseStage::ImageArray, "`data` is not an array"))?;
+
+    let mut images = Vec::new();
+    let mut stats = ParseStats::default();
+    // The list always ends with an empty object, which isn't a result
+    for result in data
+        .iter()
+        .filter(|result| result.as_object().is_some_and(|result| !result.is_empty()))
+    {
+        match unpack_result(result, thumbnails_only) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    Ok((images, stats))
+}
+
+/// Parses a single result, returning the reason it was skipped if it is missing any of the fields needed.
+fn unpack_result(result: &serde_json::Value, thumbnails_only: bool) -> Result<Image, SkipReason> {
+    let field = |name: &str| {
+        result
+            .get(name)
+            .and_then(|value| value.as_str())
+            .filter(|value| !value.is_empty())
+    };
+
+    let thumbnail = field("thumbURL")
+        .or_else(|| field("middleURL"))
+        .ok_or(SkipReason::MissingThumbnail)?
+        .to_owned();
+    let width = result.get("width").and_then(|width| width.as_i64());
+    let height = result.get("height").and_then(|height| height.as_i64());
+    let (width, height) = width.zip(height).ok_or(SkipReason::MissingDimensions)?;
+
+    if thumbnails_only {
+        return Ok(Image::new(&thumbnail, width, height, &thumbnail, ""));
+    }
+
+    // The original urls are given in plain text in `replaceUrl`, or obfuscated in `objURL` and `fromURL`
+    let replaced = result
+        .get("replaceUrl")
+        .and_then(|replaced| replaced.as_array())
+        .and_then(|replaced| replaced.first());
+    let replaced_field = |name: &str| {
+        replaced
+            .and_then(|replaced| replaced.get(name))
+            .and_then(|value| value.as_str())
+            .filter(|value| !value.is_empty())
+            .map(str::to_owned)
+    };
+
+    let url = replaced_field("ObjURL")
+        .or_else(|| field("objURL").map(decode_url))
+        .ok_or(SkipReason::MissingUrl)?;
+    let source = replaced_field("FromURL")
+        .or_else(|| field("fromURL").map(decode_url))
+        .ok_or(SkipReason::MissingSource)?;
+
+    Ok(Image::new(&url, width, height, &thumbnail, &source))
+}
+
+/// The sequences Baidu replaces the punctuation of obfuscated urls with.
+const URL_SEQUENCES: [(&str, &str); 3] = [("_z2C$q", ":"), ("_z&e3B", "."), ("AzdH3F", "/")];
+
+/// The substitution cipher Baidu obfuscates the letters and digits of urls with, as pairs of (obfuscated, original).
+/// Any other characters are left as they are.
+const URL_CHARACTERS: [(char, char); 33] = [
+    ('w', 'a'),
+    ('k', 'b'),
+    ('v', 'c'),
+    ('1', 'd'),
+    ('j', 'e'),
+    ('u', 'f'),
+    ('2', 'g'),
+    ('i', 'h'),
+    ('t', 'i'),
+    ('3', 'j'),
+    ('h', 'k'),
+    ('s', 'l'),
+    ('4', 'm'),
+    ('g', 'n'),
+    ('5', 'o'),
+    ('r', 'p'),
+    ('q', 'q'),
+    ('6', 'r'),
+    ('f', 's'),
+    ('p', 't'),
+    ('7', 'u'),
+    ('e', 'v'),
+    ('o', 'w'),
+    ('8', '1'),
+    ('d', '2'),
+    ('n', '3'),
+    ('9', '4'),
+    ('c', '5'),
+    ('m', '6'),
+    ('0', '7'),
+    ('b', '8'),
+    ('l', '9'),
+    ('a', '0'),
+];
+
+/// Decodes a url obfuscated by Baidu, such as `ippr_z2C$qAzdH3FAzdH3F...` (`http://...`).
+/// Urls that aren't obfuscated are returned as they are.
+fn decode_url(url: &str) -> String {
+    if url.starts_with("http") {
+        return url.to_owned();
+    }
+
+    let mut decoded = url.to_owned();
+    for (sequence, punctuation) in URL_SEQUENCES {
+        decoded = decoded.replace(sequence, punctuation);
+    }
+
+    decoded
+        .chars()
+        .map(|c| {
+            URL_CHARACTERS
+                .iter()
+                .find(|(obfuscated, _)| *obfuscated == c)
+                .map_or(c, |(_, original)| *original)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A result with plain text urls, one with obfuscated urls and an escaped quote, and the empty object that ends the list.
+    const RESULTS: &str = r#"{"data":[
+        {"thumbURL":"https://img0.baidu.com/it/u=1","width":800,"height":600,"replaceUrl":[{"ObjURL":"https://example.com/cat.jpg","FromURL":"https://example.com/cats"}]},
+        {"thumbURL":"","middleURL":"https://img1.baidu.com/it/u=2","width":640,"height":480,"objURL":"ippr_z2C$qAzdH3FAzdH3Fw_z&e3Bv54AzdH3F8_z&e3B3r2","fromURL":"https://example.com/dog\'s"},
+        {}
+    ]}"#;
+
+    #[test]
+    fn unpack_parses_results() {
+        let (images, stats) = unpack(RESULTS, false).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].url, "https://example.com/cat.jpg");
+        assert_eq!(images[0].source, "https://example.com/cats");
+        assert_eq!(images[0].thumbnail, "https://img0.baidu.com/it/u=1");
+        assert_eq!(images[1].url, "http://a.com/1.jpg");
+        assert_eq!(images[1].source, "https://example.com/dog's");
+        assert_eq!(images[1].thumbnail, "https://img1.baidu.com/it/u=2");
+        assert_eq!(stats.parsed, 2);
+        assert_eq!(stats.total_skipped(), 0);
+    }
+
+    #[test]
+    fn unpack_parses_thumbnails() {
+        let (images, _) = unpack(RESULTS, true).unwrap();
+        assert_eq!(images[0].url, "https://img0.baidu.com/it/u=1");
+        assert_eq!((images[1].width, images[1].height), (640, 480));
+    }
+
+    #[test]
+    fn decode_url_leaves_plain_urls() {
+        assert_eq!(
+            decode_url("https://example.com/a.jpg"),
+            "https://example.com/a.jpg"
+        );
+    }
+}
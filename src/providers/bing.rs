@@ -0,0 +1,233 @@
+//! Bing Images, which scrapes the results from the pages Bing loads as its results are scrolled through.
+
+use super::{unescape_html, PageRequest, SearchProvider};
+use crate::{
+    Color, ColorType, Image, ImageType, License, ParseStats, Ratio, SafeSearch, SearchResult, Size,
+    SkipReason,
+};
+use futures::future::BoxFuture;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// The number of results Bing returns for each page.
+const PAGE_SIZE: usize = 35;
+
+/// Searches Bing Images by scraping its results pages, as a fallback for when Google blocks or changes its results.
+///
+/// All of the filters on `Arguments` are supported except for `Arguments::format`, `Ratio::Panoramic`, and `License::Other`, which are ignored.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bing;
+
+impl SearchProvider for Bing {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let body = request.get(&build_url(&request)).await?;
+            let (images, stats) = unpack(&body, request.thumbnails_only());
+            request.add_stats(stats);
+
+            Ok(images)
+        })
+    }
+}
+
+/// Builds the url of the page of results asked for by `request`.
+fn build_url(request: &PageRequest) -> String {
+    let mut url = surf::Url::parse("https://www.bing.com/images/async").expect("Bing url is valid");
+    url.query_pairs_mut()
+        .append_pair("q", &request.query())
+        .append_pair("first", &(request.page() * PAGE_SIZE + 1).to_string())
+        .append_pair("count", &PAGE_SIZE.to_string())
+        .append_pair("mmasync", "1");
+
+    if let Some(region) = request.region() {
+        url.query_pairs_mut().append_pair("cc", region);
+    }
+    if let Some(language) = request.language() {
+        url.query_pairs_mut().append_pair("setlang", language);
+    }
+
+    let filters = filters(request);
+    if !filters.is_empty() {
+        url.query_pairs_mut().append_pair("qft", &filters);
+    }
+    if let Some(safe_search) = request.safe_search() {
+        let adult = match safe_search {
+            SafeSearch::Off => "off",
+            SafeSearch::Moderate => "moderate",
+            SafeSearch::Strict => "strict",
+        };
+        url.query_pairs_mut().append_pair("adlt", adult);
+    }
+
+    url.to_string()
+}
+
+/// The `qft` filters for the arguments of `request`, each preceded by a `+`.
+fn filters(request: &PageRequest) -> String {
+    let color = match request.color() {
+        Color::None => "",
+        Color::Red => "+filterui:color2-FGcls_RED",
+        Color::Orange => "+filterui:color2-FGcls_ORANGE",
+        Color::Yellow => "+filterui:color2-FGcls_YELLOW",
+        Color::Green => "+filterui:color2-FGcls_GREEN",
+        Color::Teal => "+filterui:color2-FGcls_TEAL",
+        Color::Blue => "+filterui:color2-FGcls_BLUE",
+        Color::Purple => "+filterui:color2-FGcls_PURPLE",
+        Color::Pink => "+filterui:color2-FGcls_PINK",
+        Color::White => "+filterui:color2-FGcls_WHITE",
+        Color::Gray => "+filterui:color2-FGcls_GRAY",
+        Color::Black => "+filterui:color2-FGcls_BLACK",
+        Color::Brown => "+filterui:color2-FGcls_BROWN",
+    };
+    let color_type = match request.color_type() {
+        ColorType::None => "",
+        ColorType::Color => "+filterui:color2-color",
+        ColorType::Grayscale => "+filterui:color2-bw",
+        ColorType::Transparent => "+filterui:photo-transparent",
+    };
+    let license = match request.license() {
+        License::CreativeCommons | License::Reuse => "+filterui:license-L2_L3_L4_L5_L6_L7",
+        License::CommercialReuse => "+filterui:license-L2_L3_L4",
+        License::ReuseWithModification => "+filterui:license-L2_L3_L5_L6",
+        License::CommercialReuseWithModification => "+filterui:license-L2_L3",
+        License::None | License::Other => "",
+    };
+    let image_type = match request.image_type() {
+        ImageType::None => "",
+        ImageType::Face => "+filterui:face-face",
+        ImageType::Photo => "+filterui:photo-photo",
+        ImageType::Clipart => "+filterui:photo-clipart",
+        ImageType::Lineart => "+filterui:photo-linedrawing",
+        ImageType::Animated => "+filterui:photo-animatedgif",
+    };
+    // Bing filters by age in minutes
+    let time = request
+        .time()
+        .minutes()
+        .map(|minutes| format!("+filterui:age-lt{}", minutes))
+        .unwrap_or_default();
+    let ratio = match request.ratio() {
+        Ratio::Tall => "+filterui:aspect-tall",
+        Ratio::Square => "+filterui:aspect-square",
+        Ratio::Wide => "+filterui:aspect-wide",
+        Ratio::None | Ratio::Panoramic => "",
+    };
+    // A minimum size is a custom size on Bing, which takes the place of the size buckets
+    let size = match (request.larger_than().dimensions(), request.size()) {
+        (Some((width, height)), _) => format!("+filterui:imagesize-custom_{}_{}", width, height),
+        (None, Size::None) => String::new(),
+        (None, Size::Large) => "+filterui:imagesize-large".to_owned(),
+        (None, Size::Medium) => "+filterui:imagesize-medium".to_owned(),
+        (None, Size::Icon) => "+filterui:imagesize-small".to_owned(),
+    };
+
+    [color, color_type, license, image_type, &time, ratio, &size].concat()
+}
+
+/// Matches the opening tag of each result, which stores its details as JSON in the `m` attribute.
+fn result_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r#"<a[^>]*class="iusc"[^>]*\sm="([^"]*)"[^>]*>"#).unwrap())
+}
+
+/// Matches the dimensions shown under each result, such as `1920 x 1080`.
+fn dimensions_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(\d+)\s*[x×]\s*(\d+)").unwrap())
+}
+
+/// Parses the images from a page of Bing's results, along with how many results were skipped and why.
+/// A page without any results is returned as empty, which ends the search.
+fn unpack(body: &str, thumbnails_only: bool) -> (Vec<Image>, ParseStats) {
+    let results: Vec<_> = result_regex().captures_iter(body).collect();
+
+    let mut images = Vec::new();
+    let mut stats = ParseStats::default();
+    for (i, captures) in results.iter().enumerate() {
+        let tag = captures.get(0).unwrap();
+        // The dimensions are shown after the link, before the next result
+        let rest = match results.get(i + 1) {
+            Some(next) => &body[tag.end()..next.get(0).unwrap().start()],
+            None => &body[tag.end()..],
+        };
+
+        match unpack_result(&unescape_html(&captures[1]), rest, thumbnails_only) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    (images, stats)
+}
+
+/// Parses a single result from the JSON in its `m` attribute and the markup that follows it.
+fn unpack_result(m: &str, rest: &str, thumbnails_only: bool) -> Result<Image, SkipReason> {
+    let json: serde_json::Value = serde_json::from_str(m).map_err(|_| SkipReason::MissingUrl)?;
+    let field = |name: &str| json.get(name).and_then(|value| value.as_str());
+
+    let thumbnail = field("turl")
+        .ok_or(SkipReason::MissingThumbnail)?
+        .to_owned();
+    let (width, height) = dimensions_regex()
+        .captures(rest)
+        .and_then(|captures| Some((captures[1].parse().ok()?, captures[2].parse().ok()?)))
+        .ok_or(SkipReason::MissingDimensions)?;
+
+    if thumbnails_only {
+        return Ok(Image::new(&thumbnail, width, height, &thumbnail, ""));
+    }
+
+    Ok(Image::new(
+        field("murl").ok_or(SkipReason::MissingUrl)?,
+        width,
+        height,
+        &thumbnail,
+        field("purl").ok_or(SkipReason::MissingSource)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two results, the second of which has no source page.
+    const RESULTS: &str = r#"<div><a class="iusc" m="{&quot;murl&quot;:&quot;https://example.com/cat.jpg&quot;,&quot;turl&quot;:&quot;https://tse1.mm.bing.net/th?id=1&quot;,&quot;purl&quot;:&quot;https://example.com/cats&quot;}" href="/images"></a><span>1920 x 1080 · jpeg</span></div>
+<div><a class="iusc" m="{&quot;murl&quot;:&quot;https://example.com/dog.jpg&quot;,&quot;turl&quot;:&quot;https://tse1.mm.bing.net/th?id=2&quot;}" href="/images"></a><span>640 × 480</span></div>"#;
+
+    #[test]
+    fn unpack_parses_results() {
+        let (images, stats) = unpack(RESULTS, false);
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://example.com/cat.jpg");
+        assert_eq!(images[0].thumbnail, "https://tse1.mm.bing.net/th?id=1");
+        assert_eq!(images[0].source, "https://example.com/cats");
+        assert_eq!((images[0].width, images[0].height), (1920, 1080));
+        assert_eq!(stats.skipped.get(&SkipReason::MissingSource), Some(&1));
+    }
+
+    #[test]
+    fn unpack_parses_thumbnails() {
+        let (images, stats) = unpack(RESULTS, true);
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[1].url, "https://tse1.mm.bing.net/th?id=2");
+        assert_eq!((images[1].width, images[1].height), (640, 480));
+        assert_eq!(stats.parsed, 2);
+    }
+
+    #[test]
+    fn unpack_empty_page() {
+        let (images, stats) = unpack("<html></html>", false);
+        assert!(images.is_empty());
+        assert_eq!(stats.parsed + stats.total_skipped(), 0);
+    }
+}
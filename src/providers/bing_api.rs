@@ -0,0 +1,317 @@
+//! The official Bing Image Search API on Azure, which searches with a subscription key. Enabled by the optional `bing-api` feature.
+
+use super::{parse_format, PageRequest, SearchProvider};
+use crate::{
+    Color, ColorType, Error, Image, ImageType, License, ParseStage, ParseStats, Ratio, SafeSearch,
+    SearchResult, Size, SkipReason, Time,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use futures::future::BoxFuture;
+use std::fmt;
+
+/// The number of results requested for each page, which is the most the API allows.
+const PAGE_SIZE: usize = 150;
+
+/// Searches Bing Images through the official Bing Image Search API, for services that can't rely on scraping.
+/// Requires a subscription key for a Bing Search resource, which can be created in the Azure portal.
+///
+/// Each `Image` includes its `file_size` and `format`, and its `published` date is when Bing first found it.
+/// Filtering by `Time::Year`, `License::Other`, and `Arguments::format` isn't supported.
+///
+/// ```
+/// use image_search::{providers::BingApi, Arguments};
+///
+/// let args = Arguments::new("example", 50).provider(BingApi::new("<subscription key>").market("en-GB"));
+/// ```
+#[derive(Clone)]
+pub struct BingApi {
+    subscription_key: String,
+    market: Option<String>,
+}
+
+impl fmt::Debug for BingApi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BingApi")
+            .field("market", &self.market)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BingApi {
+    /// Searches with the given subscription key.
+    pub fn new(subscription_key: &str) -> BingApi {
+        BingApi {
+            subscription_key: subscription_key.to_owned(),
+            market: None,
+        }
+    }
+
+    /// Sets the market results are ranked for, such as `en-GB` or `de-DE`, instead of letting Bing guess it from the location of the request.
+    pub fn market(mut self, market: &str) -> Self {
+        self.market = Some(market.to_owned());
+        self
+    }
+}
+
+impl SearchProvider for BingApi {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let url = build_url(self.market.as_deref(), &request);
+            let headers = [
+                ("Ocp-Apim-Subscription-Key", self.subscription_key.as_str()),
+                ("Accept", "application/json"),
+            ];
+            let body = request.get_with_headers(&url, &headers).await?;
+            let (images, stats) = unpack(&body, request.thumbnails_only())?;
+            request.add_stats(stats);
+
+            Ok(images)
+        })
+    }
+}
+
+/// Builds the url of the API call for the page of results asked for by `request`, ranked for `market`.
+fn build_url(market: Option<&str>, request: &PageRequest) -> String {
+    let mut url = surf::Url::parse("https://api.bing.microsoft.com/v7.0/images/search")
+        .expect("Bing Image Search url is valid");
+    url.query_pairs_mut()
+        .append_pair("q", &request.query())
+        .append_pair("count", &PAGE_SIZE.to_string())
+        .append_pair("offset", &(request.page() * PAGE_SIZE).to_string());
+
+    let color = match (request.color(), request.color_type()) {
+        (Color::Red, _) => "Red",
+        (Color::Orange, _) => "Orange",
+        (Color::Yellow, _) => "Yellow",
+        (Color::Green, _) => "Green",
+        (Color::Teal, _) => "Teal",
+        (Color::Blue, _) => "Blue",
+        (Color::Purple, _) => "Purple",
+        (Color::Pink, _) => "Pink",
+        (Color::White, _) => "White",
+        (Color::Gray, _) => "Gray",
+        (Color::Black, _) => "Black",
+        (Color::Brown, _) => "Brown",
+        (Color::None, ColorType::Color) => "ColorOnly",
+        (Color::None, ColorType::Grayscale) => "Monochrome",
+        (Color::None, ColorType::None | ColorType::Transparent) => "",
+    };
+    // Transparency is a type of image on Bing, so it is only used if no other type is set
+    let image_type = match (request.image_type(), request.color_type()) {
+        (ImageType::Photo, _) => "Photo",
+        (ImageType::Clipart, _) => "Clipart",
+        (ImageType::Lineart, _) => "Line",
+        (ImageType::Animated, _) => "AnimatedGif",
+        (ImageType::None | ImageType::Face, ColorType::Transparent) => "Transparent",
+        (ImageType::None | ImageType::Face, _) => "",
+    };
+    let image_content = match request.image_type() {
+        ImageType::Face => "Face",
+        _ => "",
+    };
+    let freshness = match request.time().round_up() {
+        Time::Day => "Day",
+        Time::Week => "Week",
+        Time::Month => "Month",
+        Time::None | Time::Hour | Time::Year | Time::Within(_) => "",
+    };
+    let license = match request.license() {
+        License::CreativeCommons => "Any",
+        License::Reuse => "Share",
+        License::CommercialReuse => "ShareCommercially",
+        License::ReuseWithModification => "Modify",
+        License::CommercialReuseWithModification => "ModifyCommercially",
+        License::None | License::Other => "",
+    };
+    let aspect = match request.ratio() {
+        Ratio::Tall => "Tall",
+        Ratio::Square => "Square",
+        Ratio::Wide | Ratio::Panoramic => "Wide",
+        Ratio::None => "",
+    };
+    let size = match request.size() {
+        Size::None => "",
+        Size::Large => "Large",
+        Size::Medium => "Medium",
+        Size::Icon => "Small",
+    };
+    let (min_width, min_height) = request
+        .larger_than()
+        .dimensions()
+        .map(|(width, height)| (width.to_string(), height.to_string()))
+        .unwrap_or_default();
+    let region = match market {
+        Some(_) => "",
+        None => request.region().unwrap_or_default(),
+    };
+    let safe_search = match request.safe_search() {
+        Some(SafeSearch::Off) => "Off",
+        Some(SafeSearch::Moderate) => "Moderate",
+        Some(SafeSearch::Strict) => "Strict",
+        None => "",
+    };
+
+    for (name, value) in [
+        ("mkt", market.unwrap_or_default()),
+        // The country can't be set along with the market
+        ("cc", region),
+        ("setLang", request.language().unwrap_or_default()),
+        ("color", color),
+        ("imageType", image_type),
+        ("imageContent", image_content),
+        ("freshness", freshness),
+        ("license", license),
+        ("aspect", aspect),
+        ("size", size),
+        ("minWidth", &min_width),
+        ("minHeight", &min_height),
+        ("safeSearch", safe_search),
+    ] {
+        if !value.is_empty() {
+            url.query_pairs_mut().append_pair(name, value);
+        }
+    }
+
+    url.to_string()
+}
+
+/// Parses the images from a page of the API's results, along with how many results were skipped and why.
+fn unpack(body: &str, thumbnails_only: bool) -> SearchResult<(Vec<Image>, ParseStats)> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+    // Errors are returned as `{"_type": "ErrorResponse", "errors": [...]}`, or as `{"error": {...}}` when the key is rejected
+    let error = json
+        .pointer("/errors/0/message")
+        .or_else(|| json.pointer("/error/message"));
+    if let Some(error) = error {
+        return Err(Error::Provider(format!(
+            "Bing Image Search API: {}",
+            error.as_str().unwrap_or("unknown error")
+        )));
+    }
+
+    let value = json
+        .get("value")
+        .and_then(|value| value.as_array())
+        .ok_or_else(|| Error::parse(ParseStage::ImageArray, "`value` is not an array"))?;
+
+    let mut images = Vec::new();
+    let mut stats = ParseStats::default();
+    for result in value {
+        match unpack_result(result, thumbnails_only) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    Ok((images, stats))
+}
+
+/// Parses a single result, returning the reason it was skipped if it is missing any of the fields needed.
+fn unpack_result(result: &serde_json::Value, thumbnails_only: bool) -> Result<Image, SkipReason> {
+    let field = |name: &str| result.get(name).and_then(|value| value.as_str());
+    let number = |name: &str| result.get(name).and_then(|value| value.as_i64());
+
+    let thumbnail = field("thumbnailUrl")
+        .ok_or(SkipReason::MissingThumbnail)?
+        .to_owned();
+    // Dates have seven digits of fractional seconds, and sometimes leave out the time zone
+    let published = field("datePublished").and_then(|date| {
+        DateTime::parse_from_rfc3339(date)
+            .map(|date| date.with_timezone(&Utc))
+            .or_else(|_| {
+                NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.f")
+                    .map(|date| date.and_utc())
+            })
+            .ok()
+    });
+
+    if thumbnails_only {
+        let width = result.pointer("/thumbnail/width").and_then(|w| w.as_i64());
+        let height = result.pointer("/thumbnail/height").and_then(|h| h.as_i64());
+        let (width, height) = width.zip(height).ok_or(SkipReason::MissingDimensions)?;
+        return Ok(Image {
+            published,
+            ..Image::new(&thumbnail, width, height, &thumbnail, "")
+        });
+    }
+
+    let (width, height) = number("width")
+        .zip(number("height"))
+        .ok_or(SkipReason::MissingDimensions)?;
+    // The size is given with its unit, such as `123456 B`
+    let file_size =
+        field("contentSize").and_then(|size| size.trim_end_matches('B').trim().parse().ok());
+
+    Ok(Image {
+        published,
+        file_size,
+        format: field("encodingFormat").and_then(parse_format),
+        ..Image::new(
+            field("contentUrl").ok_or(SkipReason::MissingUrl)?,
+            width,
+            height,
+            &thumbnail,
+            field("hostPageUrl").ok_or(SkipReason::MissingSource)?,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Format;
+
+    /// A result with every field and a date without a time zone, and one without a host page.
+    const RESULTS: &str = r#"{"_type":"Images","value":[
+        {"contentUrl":"https://example.com/cat.jpg","hostPageUrl":"https://example.com/cats","thumbnailUrl":"https://tse1.mm.bing.net/th?id=1","width":1920,"height":1080,"contentSize":"250000 B","encodingFormat":"jpeg","datePublished":"2020-09-13T12:26:40.0000000","thumbnail":{"width":474,"height":266}},
+        {"contentUrl":"https://example.com/dog.jpg","thumbnailUrl":"https://tse1.mm.bing.net/th?id=2","width":640,"height":480,"datePublished":"2020-09-13T12:26:40Z","thumbnail":{"width":300,"height":225}}
+    ]}"#;
+
+    #[test]
+    fn unpack_parses_results() {
+        let (images, stats) = unpack(RESULTS, false).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://example.com/cat.jpg");
+        assert_eq!(images[0].source, "https://example.com/cats");
+        assert_eq!((images[0].width, images[0].height), (1920, 1080));
+        assert_eq!(images[0].file_size, Some(250_000));
+        assert!(matches!(images[0].format, Some(Format::Jpg)));
+        assert_eq!(
+            images[0].published.map(|published| published.timestamp()),
+            Some(1_600_000_000)
+        );
+        assert_eq!(stats.skipped.get(&SkipReason::MissingSource), Some(&1));
+    }
+
+    #[test]
+    fn unpack_parses_thumbnails() {
+        let (images, _) = unpack(RESULTS, true).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!((images[1].width, images[1].height), (300, 225));
+        assert_eq!(
+            images[1].published.map(|published| published.timestamp()),
+            Some(1_600_000_000)
+        );
+    }
+
+    #[test]
+    fn unpack_reports_errors() {
+        for body in [
+            r#"{"_type":"ErrorResponse","errors":[{"code":"InvalidRequest","message":"Parameter has invalid value."}]}"#,
+            r#"{"error":{"code":"401","message":"Access denied due to invalid subscription key."}}"#,
+        ] {
+            assert!(matches!(unpack(body, false), Err(Error::Provider(_))));
+        }
+    }
+}
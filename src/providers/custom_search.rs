@@ -0,0 +1,312 @@
+//! Google's official Custom Search JSON API, which searches images with an API key and a Programmable Search Engine ID.
+
+use super::{parse_format, PageRequest, SearchProvider};
+use crate::{
+    Color, ColorType, Error, Format, Image, ImageType, License, ParseStage, ParseStats, SafeSearch,
+    SearchResult, Size, SkipReason, Time,
+};
+use futures::future::BoxFuture;
+use std::fmt;
+
+/// The number of results requested for each page, which is the most the API allows.
+/// The API only returns the first 100 results, which is also the most `MAX_PAGES` pages of this size can hold.
+const PAGE_SIZE: usize = 10;
+
+/// Searches Google Images through the official Custom Search JSON API, which doesn't break when Google changes its markup like scraping with `Google` does.
+/// Requires an API key and the ID (`cx`) of a Programmable Search Engine with image search enabled, both of which can be created at <https://programmablesearchengine.google.com/>.
+///
+/// The API returns at most 100 results for each search.
+/// Filtering by `Arguments::ratio` isn't supported, and only a single `Format` is.
+///
+/// ```
+/// use image_search::{providers::CustomSearch, Arguments};
+///
+/// let args = Arguments::new("example", 50).provider(CustomSearch::new("<api key>", "<cx>"));
+/// ```
+#[derive(Clone)]
+pub struct CustomSearch {
+    api_key: String,
+    cx: String,
+}
+
+impl fmt::Debug for CustomSearch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomSearch")
+            .field("cx", &self.cx)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CustomSearch {
+    /// Searches with the given API key and Programmable Search Engine ID (`cx`).
+    pub fn new(api_key: &str, cx: &str) -> CustomSearch {
+        CustomSearch {
+            api_key: api_key.to_owned(),
+            cx: cx.to_owned(),
+        }
+    }
+}
+
+impl SearchProvider for CustomSearch {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let body = request
+                .get(&build_url(&self.api_key, &self.cx, &request))
+                .await?;
+            let (images, stats) = unpack(&body, request.thumbnails_only())?;
+            request.add_stats(stats);
+
+            Ok(images)
+        })
+    }
+}
+
+/// Builds the url of the API call for the page of results asked for by `request`.
+fn build_url(api_key: &str, cx: &str, request: &PageRequest) -> String {
+    let mut url = surf::Url::parse("https://www.googleapis.com/customsearch/v1")
+        .expect("Custom Search url is valid");
+    url.query_pairs_mut()
+        .append_pair("key", api_key)
+        .append_pair("cx", cx)
+        .append_pair("q", &request.query())
+        .append_pair("searchType", "image")
+        .append_pair("num", &PAGE_SIZE.to_string())
+        // The index of the first result, starting from 1
+        .append_pair("start", &(request.page() * PAGE_SIZE + 1).to_string());
+
+    let color = match request.color() {
+        Color::None => "",
+        Color::Red => "red",
+        Color::Orange => "orange",
+        Color::Yellow => "yellow",
+        Color::Green => "green",
+        Color::Teal => "teal",
+        Color::Blue => "blue",
+        Color::Purple => "purple",
+        Color::Pink => "pink",
+        Color::White => "white",
+        Color::Gray => "gray",
+        Color::Black => "black",
+        Color::Brown => "brown",
+    };
+    let color_type = match request.color_type() {
+        ColorType::None => "",
+        ColorType::Color => "color",
+        ColorType::Grayscale => "gray",
+        ColorType::Transparent => "trans",
+    };
+    let image_type = match request.image_type() {
+        ImageType::None => "",
+        ImageType::Face => "face",
+        ImageType::Photo => "photo",
+        ImageType::Clipart => "clipart",
+        ImageType::Lineart => "lineart",
+        ImageType::Animated => "animated",
+    };
+    let rights = match request.license() {
+        License::CreativeCommons | License::Reuse => {
+            "cc_publicdomain|cc_attribute|cc_sharealike|cc_noncommercial|cc_nonderived"
+        }
+        License::CommercialReuse => "cc_publicdomain|cc_attribute|cc_sharealike|cc_nonderived",
+        License::ReuseWithModification => {
+            "cc_publicdomain|cc_attribute|cc_sharealike|cc_noncommercial"
+        }
+        License::CommercialReuseWithModification => "cc_publicdomain|cc_attribute|cc_sharealike",
+        License::None | License::Other => "",
+    };
+    // Custom Search restricts by a number of days at the finest
+    let date_restrict = match request.time() {
+        Time::None => String::new(),
+        Time::Week => "w1".to_owned(),
+        Time::Month => "m1".to_owned(),
+        Time::Year => "y1".to_owned(),
+        time => {
+            let days = time.minutes().unwrap_or(1).div_ceil(24 * 60);
+            format!("d{}", days)
+        }
+    };
+    let file_type = match request.formats() {
+        [Format::Jpg] => "jpg",
+        [Format::Gif] => "gif",
+        [Format::Png] => "png",
+        [Format::Bmp] => "bmp",
+        [Format::Svg] => "svg",
+        [Format::Webp] => "webp",
+        [Format::Ico] => "ico",
+        [Format::Raw] => "raw",
+        _ => "",
+    };
+
+    let size = match request.size() {
+        Size::None => "",
+        Size::Large => "large",
+        Size::Medium => "medium",
+        Size::Icon => "icon",
+    };
+    // Custom Search only has safe search on or off, which is off by default
+    let safe = match request.safe_search() {
+        Some(SafeSearch::Off) => "off",
+        Some(SafeSearch::Moderate | SafeSearch::Strict) => "active",
+        None => "",
+    };
+
+    let region = request.region().unwrap_or_default();
+    let country = match request.region() {
+        Some(region) if request.country_restrict() => {
+            format!("country{}", region.to_ascii_uppercase())
+        }
+        _ => String::new(),
+    };
+
+    for (name, value) in [
+        ("imgDominantColor", color),
+        ("imgColorType", color_type),
+        ("imgType", image_type),
+        ("rights", rights),
+        ("dateRestrict", &date_restrict),
+        ("fileType", file_type),
+        ("imgSize", size),
+        ("gl", region),
+        ("hl", request.language().unwrap_or_default()),
+        ("cr", &country),
+        ("safe", safe),
+    ] {
+        if !value.is_empty() {
+            url.query_pairs_mut().append_pair(name, value);
+        }
+    }
+
+    url.to_string()
+}
+
+/// Parses the images from a page of the API's results, along with how many results were skipped and why.
+fn unpack(body: &str, thumbnails_only: bool) -> SearchResult<(Vec<Image>, ParseStats)> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+    if let Some(error) = json.get("error") {
+        let message = error
+            .get("message")
+            .and_then(|message| message.as_str())
+            .unwrap_or("unknown error");
+        return Err(Error::Provider(format!("Custom Search API: {}", message)));
+    }
+
+    // There are no `items` at all when nothing was found
+    let items = match json.get("items") {
+        Some(items) => items
+            .as_array()
+            .ok_or_else(|| Error::parse(ParseStage::ImageArray, "`items` is not an array"))?,
+        None => return Ok((Vec::new(), ParseStats::default())),
+    };
+
+    let mut images = Vec::new();
+    let mut stats = ParseStats::default();
+    for item in items {
+        match unpack_item(item, thumbnails_only) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    Ok((images, stats))
+}
+
+/// Parses a single result, returning the reason it was skipped if it is missing any of the fields needed.
+fn unpack_item(item: &serde_json::Value, thumbnails_only: bool) -> Result<Image, SkipReason> {
+    let image = item.get("image");
+    let field = |name: &str| {
+        image
+            .and_then(|image| image.get(name))
+            .and_then(|value| value.as_str())
+    };
+    let number = |name: &str| {
+        image
+            .and_then(|image| image.get(name))
+            .and_then(|value| value.as_i64())
+    };
+
+    let thumbnail = field("thumbnailLink")
+        .ok_or(SkipReason::MissingThumbnail)?
+        .to_owned();
+
+    if thumbnails_only {
+        let (width, height) = number("thumbnailWidth")
+            .zip(number("thumbnailHeight"))
+            .ok_or(SkipReason::MissingDimensions)?;
+        return Ok(Image::new(&thumbnail, width, height, &thumbnail, ""));
+    }
+
+    let (width, height) = number("width")
+        .zip(number("height"))
+        .ok_or(SkipReason::MissingDimensions)?;
+
+    Ok(Image {
+        file_size: number("byteSize").and_then(|size| u64::try_from(size).ok()),
+        format: item
+            .get("mime")
+            .and_then(|mime| mime.as_str())
+            .and_then(parse_format),
+        ..Image::new(
+            item.get("link")
+                .and_then(|link| link.as_str())
+                .ok_or(SkipReason::MissingUrl)?,
+            width,
+            height,
+            &thumbnail,
+            field("contextLink").ok_or(SkipReason::MissingSource)?,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A result with every field, and one without a context link.
+    const RESULTS: &str = r#"{"items":[
+        {"link":"https://example.com/cat.png","mime":"image/png","image":{"contextLink":"https://example.com/cats","width":1920,"height":1080,"byteSize":250000,"thumbnailLink":"https://encrypted-tbn0.gstatic.com/images?q=tbn:1","thumbnailWidth":150,"thumbnailHeight":84}},
+        {"link":"https://example.com/dog.jpg","mime":"image/jpeg","image":{"width":640,"height":480,"thumbnailLink":"https://encrypted-tbn0.gstatic.com/images?q=tbn:2","thumbnailWidth":128,"thumbnailHeight":96}}
+    ]}"#;
+
+    #[test]
+    fn unpack_parses_items() {
+        let (images, stats) = unpack(RESULTS, false).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://example.com/cat.png");
+        assert_eq!(images[0].source, "https://example.com/cats");
+        assert_eq!((images[0].width, images[0].height), (1920, 1080));
+        assert_eq!(images[0].file_size, Some(250_000));
+        assert!(matches!(images[0].format, Some(Format::Png)));
+        assert_eq!(stats.skipped.get(&SkipReason::MissingSource), Some(&1));
+    }
+
+    #[test]
+    fn unpack_parses_thumbnails() {
+        let (images, _) = unpack(RESULTS, true).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!((images[1].width, images[1].height), (128, 96));
+    }
+
+    #[test]
+    fn unpack_without_results() {
+        let (images, _) = unpack(r#"{"kind":"customsearch#search"}"#, false).unwrap();
+        assert!(images.is_empty());
+        assert!(matches!(
+            unpack(
+                r#"{"error":{"code":403,"message":"Daily Limit Exceeded"}}"#,
+                false
+            ),
+            Err(Error::Provider(_))
+        ));
+    }
+}
@@ -0,0 +1,242 @@
+//! DuckDuckGo Images, which reads the results from the JSON endpoint (`i.js`) that DuckDuckGo's own page loads them from.
+
+use super::{PageRequest, SearchProvider};
+use crate::{
+    Color, ColorType, Error, Image, ImageType, License, ParseStage, ParseStats, Ratio, SafeSearch,
+    SearchResult, Size, SkipReason, Time,
+};
+use futures::future::BoxFuture;
+
+/// The number of results DuckDuckGo returns for each page.
+const PAGE_SIZE: usize = 100;
+
+/// Searches DuckDuckGo Images, whose results are plain JSON and don't need any consent cookies.
+///
+/// Each page takes two requests, since DuckDuckGo requires a token from its search page (`vqd`) before it returns any results.
+/// All of the filters on `Arguments` are supported except for `Arguments::format`, `ImageType::Face`, `Ratio::Panoramic`, and `License::Other`, which are ignored.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DuckDuckGo;
+
+impl SearchProvider for DuckDuckGo {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let vqd = token(&request).await?;
+            let body = request.get(&build_url(&request, &vqd)).await?;
+            let (images, stats) = unpack(&body, request.thumbnails_only())?;
+            request.add_stats(stats);
+
+            Ok(images)
+        })
+    }
+}
+
+/// Fetches the `vqd` token that DuckDuckGo requires for searching for the query of `request`.
+async fn token(request: &PageRequest<'_>) -> SearchResult<String> {
+    let mut url = surf::Url::parse("https://duckduckgo.com/").expect("DuckDuckGo url is valid");
+    url.query_pairs_mut()
+        .append_pair("q", &request.query())
+        .append_pair("iax", "images")
+        .append_pair("ia", "images");
+    let body = request.get(url.as_str()).await?;
+
+    // The token is either in a JavaScript string (`vqd="4-123"`) or a url parameter (`vqd=4-123&`)
+    let start = body
+        .find("vqd=")
+        .ok_or_else(|| Error::parse(ParseStage::Marker, "`vqd` token not found"))?
+        + "vqd=".len();
+    let token: String = body[start..]
+        .trim_start_matches(['"', '\''])
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect();
+
+    if token.is_empty() {
+        Err(Error::parse(ParseStage::Marker, "`vqd` token is empty"))
+    } else {
+        Ok(token)
+    }
+}
+
+/// Builds the url of the page of results asked for by `request`.
+fn build_url(request: &PageRequest, vqd: &str) -> String {
+    // Image results only have safe search on or off, which is on by default
+    let safe_search = match request.safe_search() {
+        Some(SafeSearch::Off) => "-1",
+        Some(SafeSearch::Moderate | SafeSearch::Strict) | None => "1",
+    };
+    let mut url = surf::Url::parse("https://duckduckgo.com/i.js").expect("DuckDuckGo url is valid");
+    url.query_pairs_mut()
+        .append_pair("l", "us-en")
+        .append_pair("o", "json")
+        .append_pair("q", &request.query())
+        .append_pair("vqd", vqd)
+        .append_pair("f", &filters(request))
+        .append_pair("p", safe_search);
+    if request.page() > 0 {
+        url.query_pairs_mut()
+            .append_pair("s", &(request.page() * PAGE_SIZE).to_string());
+    }
+
+    url.to_string()
+}
+
+/// The `f` parameter for the arguments of `request`, which lists every filter as `name:value` even if the value is empty.
+fn filters(request: &PageRequest) -> String {
+    let time = match request.time().round_up() {
+        Time::None | Time::Hour | Time::Within(_) => "",
+        Time::Day => "Day",
+        Time::Week => "Week",
+        Time::Month => "Month",
+        Time::Year => "Year",
+    };
+    let color = match (request.color(), request.color_type()) {
+        (Color::Red, _) => "Red",
+        (Color::Orange, _) => "Orange",
+        (Color::Yellow, _) => "Yellow",
+        (Color::Green, _) => "Green",
+        (Color::Teal, _) => "Teal",
+        (Color::Blue, _) => "Blue",
+        (Color::Purple, _) => "Purple",
+        (Color::Pink, _) => "Pink",
+        (Color::White, _) => "White",
+        (Color::Gray, _) => "Gray",
+        (Color::Black, _) => "Black",
+        (Color::Brown, _) => "Brown",
+        (Color::None, ColorType::Color) => "color",
+        (Color::None, ColorType::Grayscale) => "Monochrome",
+        (Color::None, ColorType::None | ColorType::Transparent) => "",
+    };
+    // Transparency is a type of image on DuckDuckGo, so it is only used if no other type is set
+    let image_type = match (request.image_type(), request.color_type()) {
+        (ImageType::Photo, _) => "photo",
+        (ImageType::Clipart, _) => "clipart",
+        (ImageType::Lineart, _) => "line",
+        (ImageType::Animated, _) => "gif",
+        (ImageType::None | ImageType::Face, ColorType::Transparent) => "transparent",
+        (ImageType::None | ImageType::Face, _) => "",
+    };
+    let layout = match request.ratio() {
+        Ratio::Tall => "Tall",
+        Ratio::Square => "Square",
+        Ratio::Wide => "Wide",
+        Ratio::None | Ratio::Panoramic => "",
+    };
+    let license = match request.license() {
+        License::CreativeCommons | License::Reuse => "Share",
+        License::CommercialReuse => "ShareCommercially",
+        License::ReuseWithModification => "Modify",
+        License::CommercialReuseWithModification => "ModifyCommercially",
+        License::None | License::Other => "",
+    };
+    let size = match request.size() {
+        Size::None => "",
+        Size::Large => "Large",
+        Size::Medium => "Medium",
+        Size::Icon => "Small",
+    };
+
+    format!(
+        "time:{},size:{},color:{},type:{},layout:{},license:{}",
+        time, size, color, image_type, layout, license
+    )
+}
+
+/// Parses the images from a page of DuckDuckGo's results, along with how many results were skipped and why.
+fn unpack(body: &str, thumbnails_only: bool) -> SearchResult<(Vec<Image>, ParseStats)> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+    let results = json
+        .get("results")
+        .and_then(|results| results.as_array())
+        .ok_or_else(|| Error::parse(ParseStage::ImageArray, "`results` is not an array"))?;
+
+    let mut images = Vec::new();
+    let mut stats = ParseStats::default();
+    for result in results {
+        match unpack_result(result, thumbnails_only) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    Ok((images, stats))
+}
+
+/// Parses a single result, returning the reason it was skipped if it is missing any of the fields needed.
+fn unpack_result(result: &serde_json::Value, thumbnails_only: bool) -> Result<Image, SkipReason> {
+    let field = |name: &str| result.get(name).and_then(|value| value.as_str());
+
+    let thumbnail = field("thumbnail")
+        .ok_or(SkipReason::MissingThumbnail)?
+        .to_owned();
+    let width = result.get("width").and_then(|width| width.as_i64());
+    let height = result.get("height").and_then(|height| height.as_i64());
+    let (width, height) = width.zip(height).ok_or(SkipReason::MissingDimensions)?;
+
+    if thumbnails_only {
+        return Ok(Image::new(&thumbnail, width, height, &thumbnail, ""));
+    }
+
+    Ok(Image::new(
+        field("image").ok_or(SkipReason::MissingUrl)?,
+        width,
+        height,
+        &thumbnail,
+        field("url").ok_or(SkipReason::MissingSource)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESULTS: &str = r#"{"results":[
+        {"image":"https://example.com/cat.jpg","thumbnail":"https://tse1.mm.bing.net/th?id=1","url":"https://example.com/cats","width":1920,"height":1080},
+        {"image":"https://example.com/dog.jpg","thumbnail":"https://tse1.mm.bing.net/th?id=2","url":"https://example.com/dogs"}
+    ]}"#;
+
+    #[test]
+    fn unpack_parses_results() {
+        let (images, stats) = unpack(RESULTS, false).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://example.com/cat.jpg");
+        assert_eq!(images[0].thumbnail, "https://tse1.mm.bing.net/th?id=1");
+        assert_eq!(images[0].source, "https://example.com/cats");
+        assert_eq!((images[0].width, images[0].height), (1920, 1080));
+        assert_eq!(stats.skipped.get(&SkipReason::MissingDimensions), Some(&1));
+    }
+
+    #[test]
+    fn unpack_parses_thumbnails() {
+        let (images, _) = unpack(RESULTS, true).unwrap();
+        assert_eq!(images[0].url, "https://tse1.mm.bing.net/th?id=1");
+    }
+
+    #[test]
+    fn unpack_rejects_other_responses() {
+        assert!(matches!(
+            unpack("<html>", false),
+            Err(Error::Parse {
+                stage: ParseStage::Json,
+                ..
+            })
+        ));
+        assert!(matches!(
+            unpack("{}", false),
+            Err(Error::Parse {
+                stage: ParseStage::ImageArray,
+                ..
+            })
+        ));
+    }
+}
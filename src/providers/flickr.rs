@@ -0,0 +1,271 @@
+//! Flickr, which is searched through its official REST API with an API key. Enabled by the optional `flickr` feature.
+
+use super::{PageRequest, SearchProvider};
+use crate::{
+    Error, Image, License, ParseStage, ParseStats, Ratio, SafeSearch, SearchResult, SkipReason,
+};
+use chrono::{DateTime, Duration, Utc};
+use futures::future::BoxFuture;
+use std::fmt;
+
+/// The number of results requested for each page. Flickr allows up to 500.
+const PAGE_SIZE: usize = 100;
+
+/// The sizes the full image is taken from, largest first: original, large, and medium.
+const SIZES: [&str; 3] = ["o", "l", "m"];
+
+/// The Flickr licenses that are Creative Commons or in the public domain: the six CC BY licenses, CC0, and the Public Domain Mark.
+const CREATIVE_COMMONS_LICENSES: &str = "1,2,3,4,5,6,9,10";
+
+/// The Flickr licenses that allow images to be used and shared: every license except all rights reserved.
+const REUSE_LICENSES: &str = "1,2,3,4,5,6,7,8,9,10";
+
+/// The Flickr licenses that allow images to be used and shared commercially, which leaves out the non-commercial (NC) CC licenses.
+const COMMERCIAL_REUSE_LICENSES: &str = "4,5,6,7,8,9,10";
+
+/// The Flickr licenses that allow images to be modified, which leaves out the no derivatives (ND) CC licenses.
+const MODIFICATION_LICENSES: &str = "1,2,4,5,7,8,9,10";
+
+/// The Flickr licenses that allow images to be modified and used commercially.
+const COMMERCIAL_MODIFICATION_LICENSES: &str = "4,5,7,8,9,10";
+
+/// The Flickr licenses that aren't Creative Commons: all rights reserved, no known copyright restrictions, and United States government works.
+const OTHER_LICENSES: &str = "0,7,8";
+
+/// Searches Flickr through its official API, which is a legally safer source of images than scraping, especially when filtering by `License::CreativeCommons`.
+/// Requires an API key, which can be created at <https://www.flickr.com/services/apps/create/>.
+///
+/// Only `Arguments::license`, `Arguments::time`, and `Arguments::ratio` are supported, and the other filters are ignored.
+/// The `published` date of each `Image` is when the photo was uploaded.
+///
+/// ```
+/// use image_search::{providers::Flickr, Arguments, License};
+///
+/// let args = Arguments::new("lighthouse", 50)
+///     .provider(Flickr::new("<api key>"))
+///     .license(License::CreativeCommons);
+/// ```
+#[derive(Clone)]
+pub struct Flickr {
+    api_key: String,
+}
+
+impl fmt::Debug for Flickr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Flickr").finish_non_exhaustive()
+    }
+}
+
+impl Flickr {
+    /// Searches Flickr with the given API key.
+    pub fn new(api_key: &str) -> Flickr {
+        Flickr {
+            api_key: api_key.to_owned(),
+        }
+    }
+}
+
+impl SearchProvider for Flickr {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let body = request.get(&build_url(&self.api_key, &request)).await?;
+            let (images, stats) = unpack(&body, request.thumbnails_only())?;
+            request.add_stats(stats);
+
+            Ok(images)
+        })
+    }
+}
+
+/// Builds the url of the API call for the page of results asked for by `request`.
+fn build_url(api_key: &str, request: &PageRequest) -> String {
+    let mut url =
+        surf::Url::parse("https://api.flickr.com/services/rest/").expect("Flickr url is valid");
+    url.query_pairs_mut()
+        .append_pair("method", "flickr.photos.search")
+        .append_pair("api_key", api_key)
+        .append_pair("text", &request.query())
+        .append_pair("sort", "relevance")
+        .append_pair("content_types", "0")
+        .append_pair("media", "photos")
+        .append_pair("extras", "url_o,url_l,url_m,url_q,date_upload")
+        .append_pair("per_page", &PAGE_SIZE.to_string())
+        // Flickr's pages start from 1
+        .append_pair("page", &(request.page() + 1).to_string())
+        .append_pair("format", "json")
+        .append_pair("nojsoncallback", "1");
+
+    let license = match request.license() {
+        License::CreativeCommons => CREATIVE_COMMONS_LICENSES,
+        License::Other => OTHER_LICENSES,
+        License::Reuse => REUSE_LICENSES,
+        License::CommercialReuse => COMMERCIAL_REUSE_LICENSES,
+        License::ReuseWithModification => MODIFICATION_LICENSES,
+        License::CommercialReuseWithModification => COMMERCIAL_MODIFICATION_LICENSES,
+        License::None => "",
+    };
+    let orientation = match request.ratio() {
+        Ratio::Tall => "portrait",
+        Ratio::Square => "square",
+        Ratio::Wide => "landscape",
+        Ratio::Panoramic => "panorama",
+        Ratio::None => "",
+    };
+    let age = request
+        .time()
+        .duration()
+        .and_then(|age| Duration::from_std(age).ok());
+    let safe_search = match request.safe_search() {
+        Some(SafeSearch::Off) => "3",
+        Some(SafeSearch::Moderate) => "2",
+        Some(SafeSearch::Strict) => "1",
+        None => "",
+    };
+    let min_upload_date = age
+        .map(|age| (Utc::now() - age).timestamp().to_string())
+        .unwrap_or_default();
+
+    for (name, value) in [
+        ("license", license),
+        ("orientation", orientation),
+        ("min_upload_date", &min_upload_date),
+        ("safe_search", safe_search),
+    ] {
+        if !value.is_empty() {
+            url.query_pairs_mut().append_pair(name, value);
+        }
+    }
+
+    url.to_string()
+}
+
+/// Parses the images from a page of Flickr's results, along with how many results were skipped and why.
+fn unpack(body: &str, thumbnails_only: bool) -> SearchResult<(Vec<Image>, ParseStats)> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+    if json.get("stat").and_then(|stat| stat.as_str()) != Some("ok") {
+        let message = json
+            .get("message")
+            .and_then(|message| message.as_str())
+            .unwrap_or("unknown error");
+        return Err(Error::Provider(format!("Flickr: {}", message)));
+    }
+
+    let photos = json
+        .pointer("/photos/photo")
+        .and_then(|photos| photos.as_array())
+        .ok_or_else(|| Error::parse(ParseStage::ImageArray, "`photo` is not an array"))?;
+
+    let mut images = Vec::new();
+    let mut stats = ParseStats::default();
+    for photo in photos {
+        match unpack_photo(photo, thumbnails_only) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    Ok((images, stats))
+}
+
+/// Parses a single photo, returning the reason it was skipped if it is missing any of the fields needed.
+fn unpack_photo(photo: &serde_json::Value, thumbnails_only: bool) -> Result<Image, SkipReason> {
+    let field = |name: &str| photo.get(name).and_then(|value| value.as_str());
+    // Numbers are sometimes given as strings
+    let number = |name: &str| {
+        photo.get(name).and_then(|value| {
+            value
+                .as_i64()
+                .or_else(|| value.as_str().and_then(|value| value.parse().ok()))
+        })
+    };
+    // The url and dimensions of the given size, if Flickr returned it
+    let size = |size: &str| {
+        let url = field(&format!("url_{}", size))?;
+        let width = number(&format!("width_{}", size))?;
+        let height = number(&format!("height_{}", size))?;
+        Some((url.to_owned(), width, height))
+    };
+
+    let thumbnail = size("q")
+        .or_else(|| size("m"))
+        .ok_or(SkipReason::MissingThumbnail)?;
+    let published =
+        number("dateupload").and_then(|timestamp| DateTime::from_timestamp(timestamp, 0));
+
+    if thumbnails_only {
+        let (url, width, height) = thumbnail;
+        return Ok(Image {
+            published,
+            ..Image::new(&url, width, height, &url, "")
+        });
+    }
+
+    let (url, width, height) = SIZES
+        .iter()
+        .find_map(|name| size(name))
+        .ok_or(SkipReason::MissingUrl)?;
+    let source = field("owner")
+        .zip(field("id"))
+        .map(|(owner, id)| format!("https://www.flickr.com/photos/{}/{}", owner, id))
+        .ok_or(SkipReason::MissingSource)?;
+
+    Ok(Image {
+        published,
+        ..Image::new(&url, width, height, &thumbnail.0, &source)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A photo with string dimensions and only a medium size, and one without any full size.
+    const RESULTS: &str = r#"{"stat":"ok","photos":{"photo":[
+        {"id":"123","owner":"45@N00","dateupload":"1600000000","url_q":"https://live.staticflickr.com/1_q.jpg","width_q":150,"height_q":150,"url_l":"https://live.staticflickr.com/1_b.jpg","width_l":"1024","height_l":"768","url_m":"https://live.staticflickr.com/1.jpg","width_m":500,"height_m":375},
+        {"id":"678","owner":"90@N00","url_q":"https://live.staticflickr.com/2_q.jpg","width_q":150,"height_q":150}
+    ]}}"#;
+
+    #[test]
+    fn unpack_parses_photos() {
+        let (images, stats) = unpack(RESULTS, false).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://live.staticflickr.com/1_b.jpg");
+        assert_eq!((images[0].width, images[0].height), (1024, 768));
+        assert_eq!(images[0].thumbnail, "https://live.staticflickr.com/1_q.jpg");
+        assert_eq!(images[0].source, "https://www.flickr.com/photos/45@N00/123");
+        assert_eq!(
+            images[0].published.map(|published| published.timestamp()),
+            Some(1_600_000_000)
+        );
+        assert_eq!(stats.skipped.get(&SkipReason::MissingUrl), Some(&1));
+    }
+
+    #[test]
+    fn unpack_parses_thumbnails() {
+        let (images, _) = unpack(RESULTS, true).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[1].url, "https://live.staticflickr.com/2_q.jpg");
+    }
+
+    #[test]
+    fn unpack_reports_errors() {
+        let error = unpack(
+            r#"{"stat":"fail","code":100,"message":"Invalid API Key"}"#,
+            false,
+        );
+        assert!(
+            matches!(error, Err(Error::Provider(message)) if message.contains("Invalid API Key"))
+        );
+    }
+}
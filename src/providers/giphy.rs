@@ -0,0 +1,241 @@
+//! Giphy, which is searched through its official API with an API key. Enabled by the optional `giphy` feature.
+
+use super::{PageRequest, SearchProvider};
+use crate::{
+    ColorType, Error, Image, ParseStage, ParseStats, SafeSearch, SearchResult, SkipReason,
+};
+use chrono::NaiveDateTime;
+use futures::future::BoxFuture;
+use std::fmt;
+
+/// The number of results requested for each page, which is the most Giphy allows.
+const PAGE_SIZE: usize = 50;
+
+/// Searches Giphy through its official API, which finds much better animations than `ImageType::Animated` does on Google.
+/// Requires an API key, which can be created at <https://developers.giphy.com/dashboard/>.
+///
+/// Each `Image` is the original GIF, or its MP4 version if `Giphy::mp4` is enabled, with an animated thumbnail 200 pixels wide.
+/// Only `ColorType::Transparent` is supported, which searches Giphy's stickers instead of its GIFs, and the other filters are ignored.
+///
+/// ```
+/// use image_search::{providers::Giphy, Arguments};
+///
+/// let args = Arguments::new("thumbs up", 10).provider(Giphy::new("<api key>").mp4(true));
+/// ```
+#[derive(Clone)]
+pub struct Giphy {
+    api_key: String,
+    mp4: bool,
+}
+
+impl fmt::Debug for Giphy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Giphy")
+            .field("mp4", &self.mp4)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Giphy {
+    /// Searches Giphy with the given API key.
+    pub fn new(api_key: &str) -> Giphy {
+        Giphy {
+            api_key: api_key.to_owned(),
+            mp4: false,
+        }
+    }
+
+    /// Returns the MP4 version of each animation instead of the GIF, which is usually a fraction of the size.
+    /// The MP4 files are kept by `download` even though they aren't images.
+    pub fn mp4(mut self, mp4: bool) -> Self {
+        self.mp4 = mp4;
+        self
+    }
+}
+
+impl SearchProvider for Giphy {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let body = request.get(&build_url(&self.api_key, &request)).await?;
+            let (images, stats) = unpack(&body, request.thumbnails_only(), self.mp4)?;
+            request.add_stats(stats);
+
+            Ok(images)
+        })
+    }
+}
+
+/// Builds the url of the API call for the page of results asked for by `request`.
+fn build_url(api_key: &str, request: &PageRequest) -> String {
+    // Stickers are animations with transparent backgrounds
+    let endpoint = match request.color_type() {
+        ColorType::Transparent => "stickers",
+        ColorType::None | ColorType::Color | ColorType::Grayscale => "gifs",
+    };
+    let mut url = surf::Url::parse(&format!("https://api.giphy.com/v1/{}/search", endpoint))
+        .expect("Giphy url is valid");
+    url.query_pairs_mut()
+        .append_pair("api_key", api_key)
+        .append_pair("q", &request.query())
+        .append_pair("limit", &PAGE_SIZE.to_string())
+        .append_pair("offset", &(request.page() * PAGE_SIZE).to_string());
+
+    if let Some(language) = request.language() {
+        url.query_pairs_mut().append_pair("lang", language);
+    }
+    if let Some(safe_search) = request.safe_search() {
+        let rating = match safe_search {
+            SafeSearch::Off => "r",
+            SafeSearch::Moderate => "pg-13",
+            SafeSearch::Strict => "g",
+        };
+        url.query_pairs_mut().append_pair("rating", rating);
+    }
+
+    url.to_string()
+}
+
+/// Parses the images from a page of Giphy's results, along with how many results were skipped and why.
+fn unpack(body: &str, thumbnails_only: bool, mp4: bool) -> SearchResult<(Vec<Image>, ParseStats)> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+    let status = json
+        .pointer("/meta/status")
+        .and_then(|status| status.as_i64());
+    if status != Some(200) {
+        let message = json
+            .pointer("/meta/msg")
+            .and_then(|message| message.as_str())
+            .unwrap_or("unknown error");
+        return Err(Error::Provider(format!("Giphy: {}", message)));
+    }
+
+    let data = json
+        .get("data")
+        .and_then(|data| data.as_array())
+        .ok_or_else(|| Error::parse(ParseStage::ImageArray, "`data` is not an array"))?;
+
+    let mut images = Vec::new();
+    let mut stats = ParseStats::default();
+    for gif in data {
+        match unpack_gif(gif, thumbnails_only, mp4) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    Ok((images, stats))
+}
+
+/// Parses a single animation, returning the reason it was skipped if it is missing any of the fields needed.
+fn unpack_gif(
+    gif: &serde_json::Value,
+    thumbnails_only: bool,
+    mp4: bool,
+) -> Result<Image, SkipReason> {
+    // The url and dimensions of the given rendition, with the url in `field`
+    let rendition = |name: &str, field: &str| {
+        let rendition = gif.get("images")?.get(name)?;
+        // Dimensions are given as strings
+        let number = |name: &str| {
+            rendition.get(name).and_then(|value| {
+                value
+                    .as_i64()
+                    .or_else(|| value.as_str().and_then(|value| value.parse().ok()))
+            })
+        };
+        let url = rendition
+            .get(field)?
+            .as_str()
+            .filter(|url| !url.is_empty())?;
+        Some((url.to_owned(), number("width")?, number("height")?))
+    };
+
+    let thumbnail = rendition("fixed_width", "url").ok_or(SkipReason::MissingThumbnail)?;
+    // Dates are in UTC, and are `0000-00-00 00:00:00` when unknown
+    let published = gif
+        .get("import_datetime")
+        .and_then(|date| date.as_str())
+        .and_then(|date| NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").ok())
+        .map(|date| date.and_utc());
+
+    if thumbnails_only {
+        let (url, width, height) = thumbnail;
+        return Ok(Image {
+            published,
+            ..Image::new(&url, width, height, &url, "")
+        });
+    }
+
+    let (url, width, height) =
+        rendition("original", if mp4 { "mp4" } else { "url" }).ok_or(SkipReason::MissingUrl)?;
+    let source = gif
+        .get("url")
+        .and_then(|url| url.as_str())
+        .ok_or(SkipReason::MissingSource)?
+        .to_owned();
+
+    Ok(Image {
+        published,
+        ..Image::new(&url, width, height, &thumbnail.0, &source)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An animation with an MP4 rendition, and one without an original.
+    const RESULTS: &str = r#"{"meta":{"status":200,"msg":"OK"},"data":[
+        {"url":"https://giphy.com/gifs/cat-1","import_datetime":"2020-09-13 12:26:40","images":{"original":{"url":"https://media.giphy.com/1/giphy.gif","mp4":"https://media.giphy.com/1/giphy.mp4","width":"480","height":"270"},"fixed_width":{"url":"https://media.giphy.com/1/200w.gif","width":"200","height":"113"}}},
+        {"url":"https://giphy.com/gifs/dog-2","import_datetime":"0000-00-00 00:00:00","images":{"fixed_width":{"url":"https://media.giphy.com/2/200w.gif","width":"200","height":"200"}}}
+    ]}"#;
+
+    #[test]
+    fn unpack_parses_animations() {
+        let (images, stats) = unpack(RESULTS, false, false).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://media.giphy.com/1/giphy.gif");
+        assert_eq!(images[0].thumbnail, "https://media.giphy.com/1/200w.gif");
+        assert_eq!(images[0].source, "https://giphy.com/gifs/cat-1");
+        assert_eq!((images[0].width, images[0].height), (480, 270));
+        assert_eq!(
+            images[0].published.map(|published| published.timestamp()),
+            Some(1_600_000_000)
+        );
+        assert_eq!(stats.skipped.get(&SkipReason::MissingUrl), Some(&1));
+
+        let (images, _) = unpack(RESULTS, false, true).unwrap();
+        assert_eq!(images[0].url, "https://media.giphy.com/1/giphy.mp4");
+    }
+
+    #[test]
+    fn unpack_parses_thumbnails() {
+        let (images, _) = unpack(RESULTS, true, false).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!((images[1].width, images[1].height), (200, 200));
+        assert!(images[1].published.is_none());
+    }
+
+    #[test]
+    fn unpack_reports_errors() {
+        assert!(matches!(
+            unpack(
+                r#"{"meta":{"status":401,"msg":"Unauthorized"},"data":[]}"#,
+                false,
+                false
+            ),
+            Err(Error::Provider(_))
+        ));
+    }
+}
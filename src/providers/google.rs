@@ -0,0 +1,485 @@
+//! Google Images, the default provider, which scrapes the results embedded in the `tbm=isch` results page.
+
+use super::{PageRequest, SearchProvider};
+#[cfg(feature = "browser")]
+use crate::browser;
+use crate::{
+    Arguments, Error, Format, Image, MinSize, ParseStage, ParseStats, SearchResult, SkipReason,
+};
+use futures::future::{self, BoxFuture};
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// The number of results Google returns for each page.
+const PAGE_SIZE: usize = 100;
+
+/// Searches Google Images by scraping the results page, which is the default provider.
+///
+/// All of the filters on `Arguments` are supported. If multiple formats are being filtered by, one request is made for each and the results of the requests that succeed are merged,
+/// and responses that can't be parsed are scanned for image urls instead (or loaded in a headless browser with the `browser` feature).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Google;
+
+impl SearchProvider for Google {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let args = request.args;
+            let request = &request;
+            let requests = build_urls(args, request.page())
+                .into_iter()
+                .map(|url| async move {
+                    let body = request.get(&url).await?;
+                    let err = match unpack(&body, args.thumbnails_only) {
+                        Ok((imgs, page_stats)) => {
+                            request.add_stats(page_stats);
+                            return Ok(imgs);
+                        }
+                        Err(err) => err,
+                    };
+
+                    request.add_stats(ParseStats {
+                        fallback_pages: 1,
+                        ..ParseStats::default()
+                    });
+                    if let Some(dir) = &args.parse_dump {
+                        dump_body(dir, &body);
+                    }
+                    if let Some(imgs) = unpack_fallback(&body, args.thumbnails_only) {
+                        return Ok(imgs);
+                    }
+
+                    #[cfg(feature = "browser")]
+                    if let Some(imgs) = browser::fetch(&url, args).await {
+                        return Ok(imgs);
+                    }
+
+                    Err(err)
+                });
+
+            // One format failing shouldn't lose the results of the others, so only fail if every request did
+            let mut results = Vec::new();
+            let mut error = None;
+            for result in future::join_all(requests).await {
+                match result {
+                    Ok(imgs) => results.push(imgs),
+                    Err(err) => error = error.or(Some(err)),
+                }
+            }
+            match error {
+                Some(err) if results.is_empty() => Err(err),
+                _ => Ok(merge(results)),
+            }
+        })
+    }
+}
+
+/// The `tbs` filters that are set for the arguments and the given format, such as `isc:red`.
+pub(crate) fn filters(args: &Arguments, format: Format) -> Vec<String> {
+    let color = args.color.param();
+    let color_type = args.color_type.param();
+    let license = args.license.param();
+    let image_type = args.image_type.param();
+    let time = args.time.param();
+    let ratio = args.ratio.param();
+    // Both sizes are set with `isz`, so only one can be used
+    let size = match args.larger_than {
+        MinSize::None => args.size.param(),
+        larger_than => larger_than.param(),
+    };
+    let format = format.param();
+    let raw = args.raw_tbs.clone().unwrap_or_default();
+    let params = [
+        color, color_type, license, image_type, time, ratio, size, format, raw,
+    ];
+
+    params.into_iter().filter(|param| param.len() > 1).collect()
+}
+
+/// Writes a response body that could not be parsed to a timestamped file in `dir`.
+/// Failing to write the file is ignored, since the parse error is what's reported.
+fn dump_body(dir: &Path, body: &str) {
+    let name = format!(
+        "image_search-{}.html",
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+    );
+
+    if std::fs::create_dir_all(dir).is_ok() {
+        let _ = std::fs::write(dir.join(name), body);
+    }
+}
+
+/// Interleaves the results of multiple searches by rank, skipping images that have already been found.
+fn merge(results: Vec<Vec<Image>>) -> Vec<Image> {
+    if results.len() == 1 {
+        return results.into_iter().next().unwrap_or_default();
+    }
+
+    let longest = results.iter().map(Vec::len).max().unwrap_or(0);
+    let mut seen = HashSet::new();
+    let mut merged = Vec::new();
+    for rank in 0..longest {
+        for imgs in results.iter() {
+            if let Some(image) = imgs.get(rank) {
+                if seen.insert(image.url.clone()) {
+                    merged.push(image.clone());
+                }
+            }
+        }
+    }
+
+    merged
+}
+
+/// Builds the search url of the given page for each format being filtered by, or a single url if there are none.
+fn build_urls(args: &Arguments, page: usize) -> Vec<String> {
+    if args.formats.is_empty() {
+        return vec![build_url(args, Format::None, page)];
+    }
+
+    args.formats
+        .iter()
+        .map(|format| build_url(args, *format, page))
+        .collect()
+}
+
+/// Builds the search url of the given page, filtered by `format`.
+/// Every parameter is percent-encoded, so queries can contain any text, including `&`, `#`, quotes, and non-Latin scripts.
+fn build_url(args: &Arguments, format: Format, page: usize) -> String {
+    let mut url = surf::Url::parse("https://www.google.com/search").expect("Google url is valid");
+    url.query_pairs_mut()
+        .append_pair("udm", "2")
+        .append_pair("q", &args.full_query());
+
+    let filters = filters(args, format);
+    if !filters.is_empty() {
+        url.query_pairs_mut()
+            .append_pair("tbs", &format!("ic:specific,{}", filters.join(",")));
+    }
+
+    let safe = args
+        .safe_search
+        .map(|safe| safe.param())
+        .unwrap_or_default();
+    if !safe.is_empty() {
+        url.query_pairs_mut().append_pair("safe", &safe);
+    }
+
+    if let Some(region) = &args.region {
+        url.query_pairs_mut().append_pair("gl", region);
+        if args.country_restrict {
+            url.query_pairs_mut()
+                .append_pair("cr", &format!("country{}", region.to_ascii_uppercase()));
+        }
+    }
+
+    if let Some(language) = &args.language {
+        url.query_pairs_mut().append_pair("hl", language);
+    }
+
+    if args.disable_filtering {
+        url.query_pairs_mut()
+            .append_pair("filter", "0")
+            .append_pair("nfpr", "1");
+    }
+
+    for (name, value) in args.extra_params.iter() {
+        url.query_pairs_mut().append_pair(name, value);
+    }
+
+    if page > 0 {
+        url.query_pairs_mut()
+            .append_pair("start", &(page * PAGE_SIZE).to_string());
+    }
+
+    url.to_string()
+}
+
+/// shorthand for unwrap_or_continue
+macro_rules! uoc {
+    ($opt: expr) => {
+        match $opt {
+            Some(v) => v,
+            None => {
+                continue;
+            }
+        }
+    };
+}
+
+/// Parses the images from the results embedded in a search response, along with how many results were skipped and why.
+/// Returns an `Error::Parse` with the stage that failed if the response doesn't have the structure that is expected.
+pub(crate) fn unpack(recv: &str, thumbnails_only: bool) -> SearchResult<(Vec<Image>, ParseStats)> {
+    let start = recv
+        .find("var m={")
+        .ok_or_else(|| Error::parse(ParseStage::Marker, "`var m={` not found"))?
+        + "var m=".len();
+    let mut body = &recv[start..];
+
+    let script_end = body
+        .find("var a=m")
+        .ok_or_else(|| Error::parse(ParseStage::ScriptBoundary, "`var a=m` not found"))?;
+    body = &body[..script_end];
+
+    let end = body
+        .rfind(";")
+        .ok_or_else(|| Error::parse(ParseStage::ScriptBoundary, "no `;` after the results"))?;
+    body = &body[..end];
+
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+
+    let image_objects = json
+        .as_object()
+        .ok_or_else(|| Error::parse(ParseStage::ImageArray, "the results are not an object"))?
+        .values()
+        .filter(|list| {
+            list.as_array()
+                .map(|list| {
+                    list.first().map(|value| value.is_u64()).unwrap_or(false)
+                        && list.get(1).map(|value| value.is_array()).unwrap_or(false)
+                })
+                .unwrap_or(false)
+        })
+        .map(|image| image.as_array().unwrap()[1].as_array().unwrap());
+
+    let mut images: Vec<Image> = Vec::new();
+    let mut stats = ParseStats::default();
+    for obj in image_objects {
+        match unpack_entry(obj, thumbnails_only) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    Ok((images, stats))
+}
+
+/// Parses a single search result, returning the reason it was skipped if it is missing any of the fields needed.
+fn unpack_entry(obj: &[serde_json::Value], thumbnails_only: bool) -> Result<Image, SkipReason> {
+    let thumbnail = obj.get(2).and_then(|t| t.as_array());
+
+    if thumbnails_only {
+        let t = thumbnail.ok_or(SkipReason::MissingThumbnail)?;
+        let thumbnail = t
+            .first()
+            .and_then(|t| t.as_str())
+            .ok_or(SkipReason::MissingThumbnail)?
+            .to_string();
+        let width = t.get(1).and_then(|w| w.as_i64());
+        let height = t.get(2).and_then(|h| h.as_i64());
+        let (width, height) = width.zip(height).ok_or(SkipReason::MissingDimensions)?;
+
+        return Ok(Image::new(&thumbnail, width, height, &thumbnail, ""));
+    }
+
+    let i = obj
+        .get(3)
+        .and_then(|i| i.as_array())
+        .ok_or(SkipReason::MissingUrl)?;
+    let url = i
+        .first()
+        .and_then(|url| url.as_str())
+        .ok_or(SkipReason::MissingUrl)?
+        .to_string();
+    let width = i.get(1).and_then(|w| w.as_i64());
+    let height = i.get(2).and_then(|h| h.as_i64());
+    let (width, height) = width.zip(height).ok_or(SkipReason::MissingDimensions)?;
+
+    let thumbnail = thumbnail
+        .and_then(|t| t.first())
+        .and_then(|t| t.as_str())
+        .ok_or(SkipReason::MissingThumbnail)?
+        .to_string();
+    let source = obj
+        .get(9)
+        .and_then(|meta| meta.as_object())
+        .and_then(|meta| meta.get("2003"))
+        .and_then(|source| source.as_array())
+        .and_then(|source| source.get(2))
+        .and_then(|source| source.as_str())
+        .ok_or(SkipReason::MissingSource)?
+        .to_string();
+
+    Ok(Image::new(&url, width, height, &thumbnail, &source))
+}
+
+/// Matches `["<url>",<number>,<number>]` triplets, which is how Google stores both the images and their thumbnails.
+fn triplet_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r#"\["(https?://(?:[^"\\]|\\.)+)",(\d+),(\d+)\]"#).unwrap())
+}
+
+/// Matches base64 encoded thumbnails embedded in the page.
+fn data_uri_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"data:image/[a-z+.-]+;base64,[A-Za-z0-9+/]+={0,2}").unwrap())
+}
+
+/// A last resort for when `unpack` can't find the structure it expects, which scans the whole body for image urls instead.
+/// The images it finds have no source, and only have a thumbnail when one directly precedes them.
+/// When only thumbnails are requested and none are found as urls, the base64 encoded thumbnails embedded in the page are returned instead.
+/// Returns `None` if no images are found at all.
+pub(crate) fn unpack_fallback(recv: &str, thumbnails_only: bool) -> Option<Vec<Image>> {
+    let mut images: Vec<Image> = Vec::new();
+    let mut thumbnail: Option<(String, i64, i64)> = None;
+
+    for captures in triplet_regex().captures_iter(recv) {
+        // The urls are inside of JavaScript strings, so escapes like `\u003d` need to be decoded
+        let url: String = uoc!(serde_json::from_str(&format!("\"{}\"", &captures[1])).ok());
+        let width = uoc!(captures[2].parse().ok());
+        let height = uoc!(captures[3].parse().ok());
+
+        if url.contains("gstatic.com/images?") {
+            if thumbnails_only {
+                images.push(Image::new(&url, width, height, &url, ""));
+            } else {
+                thumbnail = Some((url, width, height));
+            }
+        } else if !thumbnails_only {
+            images.push(Image::new(
+                &url,
+                width,
+                height,
+                &thumbnail.take().map(|(t, _, _)| t).unwrap_or_default(),
+                "",
+            ));
+        }
+    }
+
+    if thumbnails_only && images.is_empty() {
+        for data_uri in data_uri_regex().find_iter(recv) {
+            let data_uri = data_uri.as_str().to_owned();
+            images.push(Image::new(&data_uri, 0, 0, &data_uri, ""));
+        }
+    }
+
+    if images.is_empty() {
+        None
+    } else {
+        Some(images)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+
+    /// A thumbnail followed by its full size image, the way they appear in Google's scripts, with an escaped `=` in the thumbnail.
+    const TRIPLETS: &str = r#"foo(["https://encrypted-tbn0.gstatic.com/images?q\u003dtbn:abc",225,225],["https://example.com/cat.jpg",1920,1080]);"#;
+
+    #[test]
+    fn fallback_finds_images_and_thumbnails() {
+        let images = unpack_fallback(TRIPLETS, false).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://example.com/cat.jpg");
+        assert_eq!((images[0].width, images[0].height), (1920, 1080));
+        assert_eq!(
+            images[0].thumbnail,
+            "https://encrypted-tbn0.gstatic.com/images?q=tbn:abc"
+        );
+        assert!(images[0].source.is_empty());
+    }
+
+    #[test]
+    fn fallback_finds_only_thumbnails() {
+        let images = unpack_fallback(TRIPLETS, true).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(
+            images[0].url,
+            "https://encrypted-tbn0.gstatic.com/images?q=tbn:abc"
+        );
+        assert_eq!((images[0].width, images[0].height), (225, 225));
+    }
+
+    #[test]
+    fn fallback_uses_data_uris_for_thumbnails() {
+        let body = r#"<img src="data:image/jpeg;base64,/9j/4AAQSkZJRg==">"#;
+        let images = unpack_fallback(body, true).unwrap();
+        assert_eq!(images[0].url, "data:image/jpeg;base64,/9j/4AAQSkZJRg==");
+        assert!(unpack_fallback(body, false).is_none());
+    }
+
+    #[test]
+    fn fallback_finds_nothing() {
+        assert!(unpack_fallback("<html></html>", false).is_none());
+        assert!(unpack_fallback("<html></html>", true).is_none());
+    }
+
+    #[test]
+    fn build_url_encodes_the_query() {
+        let url = build_url(
+            &Arguments::new("cats & dogs #1 \"pets\" 猫", 10),
+            Format::None,
+            0,
+        );
+        assert_eq!(
+            url,
+            "https://www.google.com/search?udm=2&q=cats+%26+dogs+%231+%22pets%22+%E7%8C%AB"
+        );
+    }
+
+    #[test]
+    fn build_url_adds_filters_and_pages() {
+        let args = Arguments::new("cat", 10)
+            .color(Color::Red)
+            .extra_param("x", "a&b");
+        let url = build_url(&args, Format::Png, 2);
+        assert_eq!(
+            url,
+            "https://www.google.com/search?udm=2&q=cat&tbs=ic%3Aspecific%2Cisc%3Ared%2Cift%3Apng&x=a%26b&start=200"
+        );
+    }
+
+    /// A results script with one complete result and one without an image url.
+    const RESULTS: &str = r#"<script>var m={"a":[1,[0,"id",["https://encrypted-tbn0.gstatic.com/images?q=tbn:abc",100,80],["https://example.com/cat.jpg",800,600],0,0,0,0,0,{"2003":[null,"x","https://example.com/cats"],"2008":[1600000000]}]],"b":[1,[0,"id2",["https://encrypted-tbn0.gstatic.com/images?q=tbn:def",1,1]]],"c":"other"};var a=m;</script>"#;
+
+    #[test]
+    fn unpack_parses_results() {
+        let (images, stats) = unpack(RESULTS, false).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://example.com/cat.jpg");
+        assert_eq!((images[0].width, images[0].height), (800, 600));
+        assert_eq!(images[0].source, "https://example.com/cats");
+        // Google doesn't mark which of its numbers is a publish date, so none is guessed
+        assert!(images[0].published.is_none());
+        assert_eq!(stats.parsed, 1);
+        assert_eq!(stats.skipped.get(&SkipReason::MissingUrl), Some(&1));
+    }
+
+    #[test]
+    fn unpack_parses_thumbnails() {
+        let (images, stats) = unpack(RESULTS, true).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!(
+            images[0].url,
+            "https://encrypted-tbn0.gstatic.com/images?q=tbn:abc"
+        );
+        assert_eq!((images[0].width, images[0].height), (100, 80));
+        assert_eq!(stats.total_skipped(), 0);
+    }
+
+    #[test]
+    fn unpack_reports_the_stage_that_failed() {
+        let stage = |body: &str| match unpack(body, false) {
+            Err(Error::Parse { stage, .. }) => Some(stage),
+            _ => None,
+        };
+        assert_eq!(stage("<html></html>"), Some(ParseStage::Marker));
+        assert_eq!(stage("var m={};"), Some(ParseStage::ScriptBoundary));
+        assert_eq!(stage("var m={;var a=m"), Some(ParseStage::Json));
+        assert_eq!(stage("var m={};var a=m"), None);
+    }
+}
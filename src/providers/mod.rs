@@ -0,0 +1,395 @@
+//! The search engines that images can be found with, selected with `Arguments::provider`.
+//!
+//! Every provider returns the same `Image` struct, so `search`, `urls`, and `download` work the same way whichever engine is used.
+//! Paging through results, ordering, limiting, and downloading are all handled by the crate, so a provider only has to fetch and parse a single page of results.
+//! Other engines can be added by implementing `SearchProvider`.
+
+use crate::client::Session;
+use crate::{
+    Arguments, Color, ColorType, Format, Image, ImageType, License, MinSize, ParseStats, Ratio,
+    SafeSearch, SearchResult, Size, Time,
+};
+use futures::future::BoxFuture;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+mod baidu;
+mod bing;
+#[cfg(feature = "bing-api")]
+mod bing_api;
+mod custom_search;
+mod duckduckgo;
+#[cfg(feature = "flickr")]
+mod flickr;
+#[cfg(feature = "giphy")]
+mod giphy;
+pub(crate) mod google;
+mod pixabay;
+mod qwant;
+mod serpapi;
+mod tenor;
+mod wikimedia;
+mod yandex;
+
+pub use baidu::Baidu;
+pub use bing::Bing;
+#[cfg(feature = "bing-api")]
+pub use bing_api::BingApi;
+pub use custom_search::CustomSearch;
+pub use duckduckgo::DuckDuckGo;
+#[cfg(feature = "flickr")]
+pub use flickr::Flickr;
+#[cfg(feature = "giphy")]
+pub use giphy::Giphy;
+pub use google::Google;
+pub use pixabay::Pixabay;
+pub use qwant::Qwant;
+pub use serpapi::SerpApi;
+pub use tenor::Tenor;
+pub use wikimedia::Wikimedia;
+pub use yandex::Yandex;
+
+/// A search engine that can find images for the `search`, `urls`, and `download` functions.
+///
+/// Options that only apply to a single engine (such as an API key) belong on the type implementing this trait,
+/// while the filters on `Arguments` are applied by each provider as closely as the engine supports, and ignored otherwise.
+///
+/// # Example
+/// ```no_run
+/// extern crate tokio;
+/// extern crate image_search;
+///
+/// use futures::future::BoxFuture;
+/// use image_search::providers::{PageRequest, SearchProvider};
+/// use image_search::{Arguments, Image, SearchResult};
+///
+/// #[derive(Debug)]
+/// struct Example;
+///
+/// impl SearchProvider for Example {
+///     fn page_size(&self) -> usize {
+///         50
+///     }
+///
+///     fn fetch_page<'a>(&'a self, request: PageRequest<'a>) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+///         Box::pin(async move {
+///             let mut url = surf::Url::parse("https://example.com/images").unwrap();
+///             url.query_pairs_mut()
+///                 .append_pair("q", &request.query())
+///                 .append_pair("page", &request.page().to_string());
+///             let body = request.get(url.as_str()).await?;
+///             // Parse the images out of the body with `Image::new`...
+///             Ok(Vec::new())
+///         })
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), image_search::Error> {
+///     let images = image_search::search(Arguments::new("example", 10).provider(Example)).await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub trait SearchProvider: fmt::Debug + Send + Sync {
+    /// The number of results the engine returns on each page, used to work out how many pages are needed to reach the limit.
+    fn page_size(&self) -> usize;
+
+    /// Fetches and parses the page of results described by `request`.
+    /// Returning an empty list of images on any page after the first ends the search, as does returning an error.
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>>;
+}
+
+/// The built in search engines that don't need any configuration, for choosing an engine for each call or by name (such as from an `ArgumentsConfig`).
+/// Each variant searches with the provider of the same name.
+///
+/// ```
+/// use image_search::{providers::Engine, Arguments};
+///
+/// let engine: Engine = "duckduckgo".parse().unwrap();
+/// let args = Arguments::new("example", 10).provider(engine);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    Google,
+    Bing,
+    DuckDuckGo,
+    Yandex,
+    Baidu,
+    Qwant,
+    Wikimedia,
+}
+
+impl SearchProvider for Engine {
+    fn page_size(&self) -> usize {
+        match self {
+            Engine::Google => Google.page_size(),
+            Engine::Bing => Bing.page_size(),
+            Engine::DuckDuckGo => DuckDuckGo.page_size(),
+            Engine::Yandex => Yandex::new().page_size(),
+            Engine::Baidu => Baidu.page_size(),
+            Engine::Qwant => Qwant::new().page_size(),
+            Engine::Wikimedia => Wikimedia.page_size(),
+        }
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        match self {
+            Engine::Google => Google.fetch_page(request),
+            Engine::Bing => Bing.fetch_page(request),
+            Engine::DuckDuckGo => DuckDuckGo.fetch_page(request),
+            Engine::Yandex => Box::pin(async move { Yandex::new().fetch_page(request).await }),
+            Engine::Baidu => Baidu.fetch_page(request),
+            Engine::Qwant => Box::pin(async move { Qwant::new().fetch_page(request).await }),
+            Engine::Wikimedia => Wikimedia.fetch_page(request),
+        }
+    }
+}
+
+/// A `SearchProvider` of any type, so that providers of different types can be listed together, such as for `aggregate::search`.
+///
+/// ```
+/// use image_search::providers::{Bing, Google, Provider, Qwant};
+///
+/// let providers = [Provider::new(Google), Provider::new(Bing), Provider::new(Qwant::new())];
+/// ```
+#[derive(Clone)]
+pub struct Provider(pub(crate) Arc<dyn SearchProvider>);
+
+impl fmt::Debug for Provider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Provider {
+    /// Wraps `provider` so that it can be listed with providers of other types.
+    pub fn new<P: SearchProvider + 'static>(provider: P) -> Provider {
+        Provider(Arc::new(provider))
+    }
+}
+
+/// A single page of results to be fetched by a `SearchProvider`, along with the arguments of the search.
+/// Requests made through `PageRequest::get` share the rate limit, cookies, and recorded responses of the `Client` the search was made with.
+pub struct PageRequest<'a> {
+    pub(crate) args: &'a Arguments,
+    page: usize,
+    session: &'a Session,
+    stats: &'a Mutex<ParseStats>,
+}
+
+impl fmt::Debug for PageRequest<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PageRequest")
+            .field("args", self.args)
+            .field("page", &self.page)
+            .finish()
+    }
+}
+
+impl<'a> PageRequest<'a> {
+    pub(crate) fn new(
+        args: &'a Arguments,
+        page: usize,
+        session: &'a Session,
+        stats: &'a Mutex<ParseStats>,
+    ) -> PageRequest<'a> {
+        PageRequest {
+            args,
+            page,
+            session,
+            stats,
+        }
+    }
+
+    /// The query to search for, including any search operators such as `filetype:`.
+    pub fn query(&self) -> String {
+        self.args.full_query()
+    }
+
+    /// The page of results to fetch, starting from 0.
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    /// The maximum number of images being searched for, or 0 if there is no limit.
+    pub fn limit(&self) -> usize {
+        self.args.limit
+    }
+
+    /// The timeout set with `Arguments::timeout`.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.args.timeout
+    }
+
+    /// Whether only the thumbnails of the results are needed, set with `Arguments::thumbnails_only`.
+    pub fn thumbnails_only(&self) -> bool {
+        self.args.thumbnails_only
+    }
+
+    /// The color set with `Arguments::color`.
+    pub fn color(&self) -> Color {
+        self.args.color
+    }
+
+    /// The color type set with `Arguments::color_type`.
+    pub fn color_type(&self) -> ColorType {
+        self.args.color_type
+    }
+
+    /// The license set with `Arguments::license`.
+    pub fn license(&self) -> License {
+        self.args.license
+    }
+
+    /// The image type set with `Arguments::image_type`.
+    pub fn image_type(&self) -> ImageType {
+        self.args.image_type
+    }
+
+    /// The time set with `Arguments::time`.
+    pub fn time(&self) -> Time {
+        self.args.time
+    }
+
+    /// The aspect ratio set with `Arguments::ratio`.
+    pub fn ratio(&self) -> Ratio {
+        self.args.ratio
+    }
+
+    /// The size set with `Arguments::size`.
+    pub fn size(&self) -> Size {
+        self.args.size
+    }
+
+    /// The minimum size set with `Arguments::larger_than`.
+    pub fn larger_than(&self) -> MinSize {
+        self.args.larger_than
+    }
+
+    /// The country code set with `Arguments::region`, in lowercase.
+    pub fn region(&self) -> Option<&str> {
+        self.args.region.as_deref()
+    }
+
+    /// Whether only pages from the region should be searched, set with `Arguments::country_restrict`.
+    pub fn country_restrict(&self) -> bool {
+        self.args.country_restrict
+    }
+
+    /// The language code set with `Arguments::language`.
+    pub fn language(&self) -> Option<&str> {
+        self.args.language.as_deref()
+    }
+
+    /// The level of safe search set with `Arguments::safe_search`, or `None` if the provider's default should be used.
+    pub fn safe_search(&self) -> Option<SafeSearch> {
+        self.args.safe_search
+    }
+
+    /// The formats set with `Arguments::format` or `Arguments::formats`, which is empty if any format is allowed.
+    pub fn formats(&self) -> &[Format] {
+        &self.args.formats
+    }
+
+    /// Makes a GET request to `url` with the headers of a browser, returning the body of the response.
+    /// The request waits for the rate limit of the `Client`, retries if it is rate limited, and is recorded or replayed if the `Client` has a cassette.
+    /// `key` and `api_key` parameters are left out of the url that is recorded, so API keys passed in the url aren't saved to cassettes.
+    pub async fn get(&self, url: &str) -> SearchResult<String> {
+        self.get_with_headers(url, &[]).await
+    }
+
+    /// Makes a GET request to `url` like `PageRequest::get`, also sending the given headers, such as the API key of an engine that expects one in a header.
+    /// The headers aren't recorded to cassettes, so API keys aren't saved with the responses.
+    pub async fn get_with_headers(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> SearchResult<String> {
+        self.session.get(url.to_owned(), headers, self.args).await
+    }
+
+    /// Adds the statistics of how the results on this page were parsed to those returned by `search_detailed`.
+    pub fn add_stats(&self, stats: ParseStats) {
+        self.stats
+            .lock()
+            .expect("Parse stats lock poisoned")
+            .merge(stats)
+    }
+}
+
+/// Decodes the HTML entities that are used to escape attribute values, such as `&quot;` and `&amp;`.
+pub(crate) fn unescape_html(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "quot" => '"',
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "apos" => '\'',
+                entity => {
+                    let code = match entity
+                        .strip_prefix("#x")
+                        .or_else(|| entity.strip_prefix("#X"))
+                    {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => entity.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end))
+        });
+
+        match decoded {
+            Some((c, end)) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            // Not an entity, so the `&` is kept as it is
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+
+    unescaped
+}
+
+/// Adds `https:` to protocol relative urls (`//example.com/image.jpg`), which some engines use for their thumbnails.
+pub(crate) fn absolute_url(url: &str) -> String {
+    match url.strip_prefix("//") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_owned(),
+    }
+}
+
+/// Parses the format of an image from the name or MIME type an engine gives it, such as `jpeg`, `animatedgif`, or `image/svg+xml`.
+pub(crate) fn parse_format(name: &str) -> Option<Format> {
+    let name = name.to_ascii_lowercase();
+    match name.strip_prefix("image/").unwrap_or(&name) {
+        "jpeg" | "jpg" | "pjpeg" => Some(Format::Jpg),
+        "gif" | "animatedgif" => Some(Format::Gif),
+        "png" => Some(Format::Png),
+        "bmp" | "x-ms-bmp" => Some(Format::Bmp),
+        "svg" | "svg+xml" => Some(Format::Svg),
+        "webp" => Some(Format::Webp),
+        "ico" | "x-icon" | "vnd.microsoft.icon" => Some(Format::Ico),
+        _ => None,
+    }
+}
@@ -0,0 +1,257 @@
+//! Pixabay, which is searched through its official API with an API key.
+
+use super::{PageRequest, SearchProvider};
+use crate::{
+    Color, ColorType, Error, Image, ImageType, ParseStage, ParseStats, Ratio, SafeSearch,
+    SearchResult, SkipReason,
+};
+use futures::future::BoxFuture;
+use std::fmt;
+
+/// The number of results requested for each page. Pixabay allows up to 200.
+const PAGE_SIZE: usize = 100;
+
+/// The largest width or height of the images the API links to (`largeImageURL`), which are scaled down from the originals.
+const LARGE_SIZE: i64 = 1280;
+
+/// The license every image on Pixabay is published under.
+const LICENSE: &str = "Pixabay Content License";
+
+/// Searches Pixabay through its official API. Everything on Pixabay is free to use under the Pixabay Content License, without needing attribution.
+/// Requires an API key, which can be found at <https://pixabay.com/api/docs/> after signing up.
+///
+/// Only `Arguments::color`, `Arguments::color_type`, `ImageType::Photo`, `ImageType::Clipart` (illustrations), `ImageType::Lineart` (vector graphics), `Ratio::Tall`, and `Ratio::Wide` are supported, and the other filters are ignored.
+/// The images are Pixabay's large versions, which are at most 1280 pixels wide and tall, since the originals are only available to approved API keys.
+///
+/// ```
+/// use image_search::{providers::Pixabay, Arguments};
+///
+/// let args = Arguments::new("mountains", 50).provider(Pixabay::new("<api key>"));
+/// ```
+#[derive(Clone)]
+pub struct Pixabay {
+    api_key: String,
+}
+
+impl fmt::Debug for Pixabay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pixabay").finish_non_exhaustive()
+    }
+}
+
+impl Pixabay {
+    /// Searches Pixabay with the given API key.
+    pub fn new(api_key: &str) -> Pixabay {
+        Pixabay {
+            api_key: api_key.to_owned(),
+        }
+    }
+}
+
+impl SearchProvider for Pixabay {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let body = request.get(&build_url(&self.api_key, &request)).await?;
+            let (images, stats) = unpack(&body, request.thumbnails_only())?;
+            request.add_stats(stats);
+
+            Ok(images)
+        })
+    }
+}
+
+/// Builds the url of the API call for the page of results asked for by `request`.
+fn build_url(api_key: &str, request: &PageRequest) -> String {
+    let mut url = surf::Url::parse("https://pixabay.com/api/").expect("Pixabay url is valid");
+    url.query_pairs_mut()
+        .append_pair("key", api_key)
+        .append_pair("q", &request.query())
+        .append_pair("per_page", &PAGE_SIZE.to_string())
+        // Pixabay's pages start from 1
+        .append_pair("page", &(request.page() + 1).to_string());
+
+    let image_type = match request.image_type() {
+        ImageType::Photo => "photo",
+        ImageType::Clipart => "illustration",
+        ImageType::Lineart => "vector",
+        ImageType::None | ImageType::Face | ImageType::Animated => "",
+    };
+    let orientation = match request.ratio() {
+        Ratio::Tall => "vertical",
+        Ratio::Wide => "horizontal",
+        Ratio::None | Ratio::Square | Ratio::Panoramic => "",
+    };
+    // Pixabay allows any number of colors, so the color and color type are both used
+    let color = match request.color() {
+        Color::None => "",
+        Color::Red => "red",
+        Color::Orange => "orange",
+        Color::Yellow => "yellow",
+        Color::Green => "green",
+        Color::Teal => "turquoise",
+        Color::Blue => "blue",
+        Color::Purple => "lilac",
+        Color::Pink => "pink",
+        Color::White => "white",
+        Color::Gray => "gray",
+        Color::Black => "black",
+        Color::Brown => "brown",
+    };
+    let color_type = match request.color_type() {
+        ColorType::Grayscale => "grayscale",
+        ColorType::Transparent => "transparent",
+        ColorType::None | ColorType::Color => "",
+    };
+    let (min_width, min_height) = request
+        .larger_than()
+        .dimensions()
+        .map(|(width, height)| (width.to_string(), height.to_string()))
+        .unwrap_or_default();
+    // Pixabay's languages don't include a region, such as `zh` instead of `zh-CN`
+    let language = request
+        .language()
+        .and_then(|language| language.split('-').next())
+        .unwrap_or_default();
+    // Pixabay only has a filter for images suitable for all ages
+    let safe_search = match request.safe_search() {
+        Some(SafeSearch::Strict) => "true",
+        Some(SafeSearch::Off | SafeSearch::Moderate) | None => "",
+    };
+    let colors = [color, color_type]
+        .into_iter()
+        .filter(|color| !color.is_empty())
+        .collect::<Vec<&str>>()
+        .join(",");
+
+    for (name, value) in [
+        ("image_type", image_type),
+        ("orientation", orientation),
+        ("colors", &colors),
+        ("min_width", &min_width),
+        ("min_height", &min_height),
+        ("safesearch", safe_search),
+        ("lang", language),
+    ] {
+        if !value.is_empty() {
+            url.query_pairs_mut().append_pair(name, value);
+        }
+    }
+
+    url.to_string()
+}
+
+/// Parses the images from a page of Pixabay's results, along with how many results were skipped and why.
+fn unpack(body: &str, thumbnails_only: bool) -> SearchResult<(Vec<Image>, ParseStats)> {
+    // Errors are returned as plain text, such as `[ERROR 400] Invalid or missing API key`
+    if body.starts_with("[ERROR") {
+        return Err(Error::Provider(format!("Pixabay: {}", body.trim())));
+    }
+
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+    let hits = json
+        .get("hits")
+        .and_then(|hits| hits.as_array())
+        .ok_or_else(|| Error::parse(ParseStage::ImageArray, "`hits` is not an array"))?;
+
+    let mut images = Vec::new();
+    let mut stats = ParseStats::default();
+    for hit in hits {
+        match unpack_hit(hit, thumbnails_only) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    Ok((images, stats))
+}
+
+/// Parses a single result, returning the reason it was skipped if it is missing any of the fields needed.
+fn unpack_hit(hit: &serde_json::Value, thumbnails_only: bool) -> Result<Image, SkipReason> {
+    let field = |name: &str| hit.get(name).and_then(|value| value.as_str());
+    let number = |name: &str| hit.get(name).and_then(|value| value.as_i64());
+
+    let thumbnail = field("previewURL")
+        .ok_or(SkipReason::MissingThumbnail)?
+        .to_owned();
+
+    if thumbnails_only {
+        let (width, height) = number("previewWidth")
+            .zip(number("previewHeight"))
+            .ok_or(SkipReason::MissingDimensions)?;
+        return Ok(Image {
+            license: Some(LICENSE.to_owned()),
+            ..Image::new(&thumbnail, width, height, &thumbnail, "")
+        });
+    }
+
+    let (width, height) = number("imageWidth")
+        .zip(number("imageHeight"))
+        .ok_or(SkipReason::MissingDimensions)?;
+    // The dimensions are those of the original, so they're scaled down to match the large version
+    let longest = width.max(height);
+    let (width, height) = if longest > LARGE_SIZE {
+        (width * LARGE_SIZE / longest, height * LARGE_SIZE / longest)
+    } else {
+        (width, height)
+    };
+
+    Ok(Image {
+        license: Some(LICENSE.to_owned()),
+        ..Image::new(
+            field("largeImageURL").ok_or(SkipReason::MissingUrl)?,
+            width,
+            height,
+            &thumbnail,
+            field("pageURL").ok_or(SkipReason::MissingSource)?,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A large photo, and a small one without a page url.
+    const RESULTS: &str = r#"{"total":2,"totalHits":2,"hits":[
+        {"pageURL":"https://pixabay.com/photos/cat-1/","previewURL":"https://cdn.pixabay.com/1_150.jpg","previewWidth":150,"previewHeight":100,"largeImageURL":"https://pixabay.com/get/1_1280.jpg","imageWidth":6000,"imageHeight":4000},
+        {"previewURL":"https://cdn.pixabay.com/2_150.jpg","previewWidth":150,"previewHeight":150,"largeImageURL":"https://pixabay.com/get/2_1280.jpg","imageWidth":640,"imageHeight":640}
+    ]}"#;
+
+    #[test]
+    fn unpack_parses_hits() {
+        let (images, stats) = unpack(RESULTS, false).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://pixabay.com/get/1_1280.jpg");
+        assert_eq!(images[0].source, "https://pixabay.com/photos/cat-1/");
+        // Scaled down to the size of the large version
+        assert_eq!((images[0].width, images[0].height), (LARGE_SIZE, 853));
+        assert_eq!(images[0].license.as_deref(), Some(LICENSE));
+        assert_eq!(stats.skipped.get(&SkipReason::MissingSource), Some(&1));
+    }
+
+    #[test]
+    fn unpack_parses_thumbnails() {
+        let (images, _) = unpack(RESULTS, true).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!((images[0].width, images[0].height), (150, 100));
+    }
+
+    #[test]
+    fn unpack_reports_errors() {
+        assert!(matches!(
+            unpack("[ERROR 400] Invalid or missing API key", false),
+            Err(Error::Provider(_))
+        ));
+    }
+}
@@ -0,0 +1,240 @@
+//! Qwant, which returns its image results as JSON from its search API (`api/search/images`).
+
+use super::{absolute_url, PageRequest, SearchProvider};
+use crate::{
+    Color, ColorType, Error, Image, ImageType, License, ParseStage, ParseStats, SafeSearch,
+    SearchResult, Size, SkipReason, Time,
+};
+use futures::future::BoxFuture;
+
+/// The number of results Qwant returns for each page, which is the most it allows.
+const PAGE_SIZE: usize = 50;
+
+/// Searches Qwant, an EU hosted search engine that is useful when Google serves consent walls.
+///
+/// Filtering by `Arguments::format`, `Arguments::ratio`, `ImageType::Face`, `Time::Year`, and `License::Other` isn't supported.
+#[derive(Debug, Clone)]
+pub struct Qwant {
+    locale: String,
+}
+
+impl Default for Qwant {
+    fn default() -> Self {
+        Qwant::new()
+    }
+}
+
+impl Qwant {
+    /// Searches with the `en_US` locale.
+    pub fn new() -> Qwant {
+        Qwant {
+            locale: "en_US".to_owned(),
+        }
+    }
+
+    /// Sets the locale results are ranked for, such as `de_DE` or `fr_FR`.
+    pub fn locale(mut self, locale: &str) -> Self {
+        self.locale = locale.to_owned();
+        self
+    }
+}
+
+impl SearchProvider for Qwant {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let body = request.get(&build_url(&self.locale, &request)).await?;
+            let (images, stats) = unpack(&body, request.thumbnails_only())?;
+            request.add_stats(stats);
+
+            Ok(images)
+        })
+    }
+}
+
+/// Builds the url of the page of results asked for by `request`, ranked for `locale`.
+fn build_url(locale: &str, request: &PageRequest) -> String {
+    let mut url =
+        surf::Url::parse("https://api.qwant.com/v3/search/images").expect("Qwant url is valid");
+    url.query_pairs_mut()
+        .append_pair("q", &request.query())
+        .append_pair("t", "images")
+        .append_pair("locale", locale)
+        .append_pair("device", "desktop")
+        .append_pair("count", &PAGE_SIZE.to_string())
+        .append_pair("offset", &(request.page() * PAGE_SIZE).to_string());
+
+    let color = match (request.color(), request.color_type()) {
+        (Color::Red, _) => "red",
+        (Color::Orange, _) => "orange",
+        (Color::Yellow, _) => "yellow",
+        (Color::Green, _) => "green",
+        (Color::Teal, _) => "teal",
+        (Color::Blue, _) => "blue",
+        (Color::Purple, _) => "purple",
+        (Color::Pink, _) => "pink",
+        (Color::White, _) => "white",
+        (Color::Gray, _) => "gray",
+        (Color::Black, _) => "black",
+        (Color::Brown, _) => "brown",
+        (Color::None, ColorType::Color) => "color",
+        (Color::None, ColorType::Grayscale) => "monochrome",
+        (Color::None, ColorType::None | ColorType::Transparent) => "",
+    };
+    // Transparency is a type of image on Qwant, so it is only used if no other type is set
+    let image_type = match (request.image_type(), request.color_type()) {
+        (ImageType::Photo, _) => "photo",
+        (ImageType::Clipart, _) => "clipart",
+        (ImageType::Lineart, _) => "line",
+        (ImageType::Animated, _) => "animatedgif",
+        (ImageType::None | ImageType::Face, ColorType::Transparent) => "transparent",
+        (ImageType::None | ImageType::Face, _) => "",
+    };
+    let freshness = match request.time().round_up() {
+        Time::Day => "day",
+        Time::Week => "week",
+        Time::Month => "month",
+        Time::None | Time::Hour | Time::Year | Time::Within(_) => "",
+    };
+    let license = match request.license() {
+        License::CreativeCommons | License::Reuse => "share",
+        License::CommercialReuse => "sharecommercially",
+        License::ReuseWithModification => "modify",
+        License::CommercialReuseWithModification => "modifycommercially",
+        License::None | License::Other => "",
+    };
+    let size = match request.size() {
+        Size::None => "",
+        Size::Large => "large",
+        Size::Medium => "medium",
+        Size::Icon => "small",
+    };
+    let safe_search = match request.safe_search() {
+        Some(SafeSearch::Off) => "0",
+        Some(SafeSearch::Moderate) => "1",
+        Some(SafeSearch::Strict) => "2",
+        None => "",
+    };
+
+    for (name, value) in [
+        ("color", color),
+        ("imagetype", image_type),
+        ("freshness", freshness),
+        ("license", license),
+        ("size", size),
+        ("safesearch", safe_search),
+    ] {
+        if !value.is_empty() {
+            url.query_pairs_mut().append_pair(name, value);
+        }
+    }
+
+    url.to_string()
+}
+
+/// Parses the images from a page of Qwant's results, along with how many results were skipped and why.
+fn unpack(body: &str, thumbnails_only: bool) -> SearchResult<(Vec<Image>, ParseStats)> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+    if json.get("status").and_then(|status| status.as_str()) != Some("success") {
+        return Err(Error::Provider(format!("Qwant: {}", json["data"])));
+    }
+
+    let items = json
+        .pointer("/data/result/items")
+        .and_then(|items| items.as_array())
+        .ok_or_else(|| Error::parse(ParseStage::ImageArray, "`items` is not an array"))?;
+
+    let mut images = Vec::new();
+    let mut stats = ParseStats::default();
+    for item in items {
+        match unpack_item(item, thumbnails_only) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    Ok((images, stats))
+}
+
+/// Parses a single result, returning the reason it was skipped if it is missing any of the fields needed.
+fn unpack_item(item: &serde_json::Value, thumbnails_only: bool) -> Result<Image, SkipReason> {
+    let field = |name: &str| item.get(name).and_then(|value| value.as_str());
+    // Dimensions are sometimes given as strings
+    let number = |name: &str| {
+        item.get(name).and_then(|value| {
+            value
+                .as_i64()
+                .or_else(|| value.as_str().and_then(|value| value.parse().ok()))
+        })
+    };
+
+    // Thumbnail urls are protocol relative (`//s1.qwant.com/thumbr/...`)
+    let thumbnail = field("thumbnail")
+        .map(absolute_url)
+        .ok_or(SkipReason::MissingThumbnail)?;
+    let (width, height) = number("width")
+        .zip(number("height"))
+        .ok_or(SkipReason::MissingDimensions)?;
+
+    if thumbnails_only {
+        let (width, height) = number("thumb_width")
+            .zip(number("thumb_height"))
+            .unwrap_or((width, height));
+        return Ok(Image::new(&thumbnail, width, height, &thumbnail, ""));
+    }
+
+    Ok(Image::new(
+        field("media").ok_or(SkipReason::MissingUrl)?,
+        width,
+        height,
+        &thumbnail,
+        field("url").ok_or(SkipReason::MissingSource)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A result with numeric dimensions, and one with string dimensions but no source.
+    const RESULTS: &str = r#"{"status":"success","data":{"result":{"items":[
+        {"media":"https://example.com/cat.jpg","thumbnail":"//s1.qwant.com/thumbr/1","url":"https://example.com/cats","width":1920,"height":1080,"thumb_width":474,"thumb_height":266},
+        {"media":"https://example.com/dog.jpg","thumbnail":"//s1.qwant.com/thumbr/2","width":"640","height":"480"}
+    ]}}}"#;
+
+    #[test]
+    fn unpack_parses_results() {
+        let (images, stats) = unpack(RESULTS, false).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://example.com/cat.jpg");
+        assert_eq!(images[0].thumbnail, "https://s1.qwant.com/thumbr/1");
+        assert_eq!(images[0].source, "https://example.com/cats");
+        assert_eq!((images[0].width, images[0].height), (1920, 1080));
+        assert_eq!(stats.skipped.get(&SkipReason::MissingSource), Some(&1));
+    }
+
+    #[test]
+    fn unpack_parses_thumbnails() {
+        let (images, _) = unpack(RESULTS, true).unwrap();
+        assert_eq!((images[0].width, images[0].height), (474, 266));
+        assert_eq!((images[1].width, images[1].height), (640, 480));
+    }
+
+    #[test]
+    fn unpack_reports_errors() {
+        assert!(matches!(
+            unpack(r#"{"status":"error","data":{"error_code":24}}"#, false),
+            Err(Error::Provider(_))
+        ));
+    }
+}
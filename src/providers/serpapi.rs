@@ -0,0 +1,204 @@
+//! SerpApi, a paid proxy that searches Google Images (its `google_images` engine) and returns the results as JSON.
+
+use super::google::filters;
+use super::{PageRequest, SearchProvider};
+use crate::{Error, Format, Image, ParseStage, ParseStats, SearchResult, SkipReason};
+use futures::future::BoxFuture;
+use std::fmt;
+
+/// The number of results SerpApi returns for each page.
+const PAGE_SIZE: usize = 100;
+
+/// The error SerpApi returns when Google has no more results, which ends the search instead of failing it.
+const NO_RESULTS: &str = "hasn't returned any results";
+
+/// Searches Google Images through SerpApi, which solves Google's captchas and keeps its parsing up to date, for anyone already paying for it.
+/// Requires an API key, which can be found at <https://serpapi.com/manage-api-key>.
+///
+/// Supports the same filters as `Google`, except that only a single `Format` is.
+/// SerpApi doesn't include the dimensions of the thumbnails, so the dimensions of the full images are used with `Arguments::thumbnails_only`.
+///
+/// ```
+/// use image_search::{providers::SerpApi, Arguments};
+///
+/// let args = Arguments::new("example", 50).provider(SerpApi::new("<api key>"));
+/// ```
+#[derive(Clone)]
+pub struct SerpApi {
+    api_key: String,
+}
+
+impl fmt::Debug for SerpApi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SerpApi").finish_non_exhaustive()
+    }
+}
+
+impl SerpApi {
+    /// Searches with the given SerpApi key.
+    pub fn new(api_key: &str) -> SerpApi {
+        SerpApi {
+            api_key: api_key.to_owned(),
+        }
+    }
+}
+
+impl SearchProvider for SerpApi {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let body = request.get(&build_url(&self.api_key, &request)).await?;
+            let (images, stats) = unpack(&body, request.thumbnails_only())?;
+            request.add_stats(stats);
+
+            Ok(images)
+        })
+    }
+}
+
+/// Builds the url of the API call for the page of results asked for by `request`.
+fn build_url(api_key: &str, request: &PageRequest) -> String {
+    let mut url =
+        surf::Url::parse("https://serpapi.com/search.json").expect("SerpApi url is valid");
+    url.query_pairs_mut()
+        .append_pair("engine", "google_images")
+        .append_pair("api_key", api_key)
+        .append_pair("q", &request.query())
+        .append_pair("ijn", &request.page().to_string());
+
+    let safe = request
+        .safe_search()
+        .map(|safe| safe.param())
+        .unwrap_or_default();
+    if !safe.is_empty() {
+        url.query_pairs_mut().append_pair("safe", &safe);
+    }
+
+    if let Some(language) = request.language() {
+        url.query_pairs_mut().append_pair("hl", language);
+    }
+    if let Some(region) = request.region() {
+        url.query_pairs_mut().append_pair("gl", region);
+        if request.country_restrict() {
+            url.query_pairs_mut()
+                .append_pair("cr", &format!("country{}", region.to_ascii_uppercase()));
+        }
+    }
+
+    if request.args.disable_filtering {
+        url.query_pairs_mut()
+            .append_pair("filter", "0")
+            .append_pair("nfpr", "1");
+    }
+
+    // The filters are passed on to Google as they are
+    let format = match request.formats() {
+        [format] => *format,
+        _ => Format::None,
+    };
+    let filters = filters(request.args, format);
+    if !filters.is_empty() {
+        url.query_pairs_mut()
+            .append_pair("tbs", &format!("ic:specific,{}", filters.join(",")));
+    }
+
+    url.to_string()
+}
+
+/// Parses the images from a page of SerpApi's results, along with how many results were skipped and why.
+fn unpack(body: &str, thumbnails_only: bool) -> SearchResult<(Vec<Image>, ParseStats)> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+    if let Some(error) = json.get("error").and_then(|error| error.as_str()) {
+        if error.contains(NO_RESULTS) {
+            return Ok((Vec::new(), ParseStats::default()));
+        }
+        return Err(Error::Provider(format!("SerpApi: {}", error)));
+    }
+
+    let results = json
+        .get("images_results")
+        .and_then(|results| results.as_array())
+        .ok_or_else(|| Error::parse(ParseStage::ImageArray, "`images_results` is not an array"))?;
+
+    let mut images = Vec::new();
+    let mut stats = ParseStats::default();
+    for result in results {
+        match unpack_result(result, thumbnails_only) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    Ok((images, stats))
+}
+
+/// Parses a single result, returning the reason it was skipped if it is missing any of the fields needed.
+fn unpack_result(result: &serde_json::Value, thumbnails_only: bool) -> Result<Image, SkipReason> {
+    let field = |name: &str| result.get(name).and_then(|value| value.as_str());
+    let number = |name: &str| result.get(name).and_then(|value| value.as_i64());
+
+    let thumbnail = field("thumbnail")
+        .ok_or(SkipReason::MissingThumbnail)?
+        .to_owned();
+    let (width, height) = number("original_width")
+        .zip(number("original_height"))
+        .ok_or(SkipReason::MissingDimensions)?;
+
+    if thumbnails_only {
+        return Ok(Image::new(&thumbnail, width, height, &thumbnail, ""));
+    }
+
+    Ok(Image::new(
+        field("original").ok_or(SkipReason::MissingUrl)?,
+        width,
+        height,
+        &thumbnail,
+        field("link").ok_or(SkipReason::MissingSource)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A result with every field, and one without its original dimensions.
+    const RESULTS: &str = r#"{"search_metadata":{"status":"Success"},"images_results":[
+        {"position":1,"thumbnail":"https://serpapi.com/images/1.jpeg","original":"https://example.com/cat.jpg","original_width":1920,"original_height":1080,"link":"https://example.com/cats"},
+        {"position":2,"thumbnail":"https://serpapi.com/images/2.jpeg","original":"https://example.com/dog.jpg","link":"https://example.com/dogs"}
+    ]}"#;
+
+    #[test]
+    fn unpack_parses_results() {
+        let (images, stats) = unpack(RESULTS, false).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://example.com/cat.jpg");
+        assert_eq!(images[0].thumbnail, "https://serpapi.com/images/1.jpeg");
+        assert_eq!(images[0].source, "https://example.com/cats");
+        assert_eq!((images[0].width, images[0].height), (1920, 1080));
+        assert_eq!(stats.skipped.get(&SkipReason::MissingDimensions), Some(&1));
+
+        let (images, _) = unpack(RESULTS, true).unwrap();
+        assert_eq!(images[0].url, "https://serpapi.com/images/1.jpeg");
+    }
+
+    #[test]
+    fn unpack_without_results() {
+        let body = format!(r#"{{"error":"{}"}}"#, NO_RESULTS);
+        let (images, _) = unpack(&body, false).unwrap();
+        assert!(images.is_empty());
+        assert!(matches!(
+            unpack(r#"{"error":"Invalid API key."}"#, false),
+            Err(Error::Provider(_))
+        ));
+    }
+}
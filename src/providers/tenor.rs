@@ -0,0 +1,352 @@
+//! Tenor, which is searched through its official v2 API with an API key.
+
+use super::{PageRequest, SearchProvider};
+use crate::{
+    ColorType, Error, Image, ParseStage, ParseStats, SafeSearch, SearchResult, SkipReason,
+};
+use chrono::DateTime;
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// The number of results requested for each page, which is the most Tenor allows.
+const PAGE_SIZE: usize = 50;
+
+/// The most page positions that are remembered before they are all forgotten, so that a long running program doesn't keep every search's positions forever.
+const MAX_POSITIONS: usize = 256;
+
+/// Searches Tenor through its official v2 API, which is the GIF search used by most chat apps and bots.
+/// Requires an API key, which can be created in the Google Cloud console by enabling the Tenor API.
+///
+/// Each `Image` is the GIF, or its MP4 version if `Tenor::mp4` is enabled, with a small GIF as the thumbnail.
+/// Only `ColorType::Transparent` is supported, which searches Tenor's stickers instead of its GIFs, and the other filters are ignored.
+///
+/// Tenor can only find the position of a page from the page before it, so any pages that haven't been fetched yet are fetched first.
+/// The positions are remembered by the provider, so clones of the same `Tenor` share them,
+/// and pages are fetched one at a time so that each page's position is only looked up once, even when several pages are requested together.
+///
+/// ```
+/// use image_search::{providers::Tenor, Arguments};
+///
+/// let args = Arguments::new("thumbs up", 10).provider(Tenor::new("<api key>"));
+/// ```
+#[derive(Clone)]
+pub struct Tenor {
+    api_key: String,
+    client_key: Option<String>,
+    mp4: bool,
+    /// The `pos` of each page after the first, keyed by the url of the search without it and the page number.
+    positions: Arc<Mutex<HashMap<(String, usize), String>>>,
+    /// Held while a page is fetched, so that pages requested together don't each fetch the pages before them.
+    fetching: Arc<async_std::sync::Mutex<()>>,
+}
+
+impl fmt::Debug for Tenor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tenor")
+            .field("client_key", &self.client_key)
+            .field("mp4", &self.mp4)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Tenor {
+    /// Searches Tenor with the given API key.
+    pub fn new(api_key: &str) -> Tenor {
+        Tenor {
+            api_key: api_key.to_owned(),
+            client_key: None,
+            mp4: false,
+            positions: Arc::default(),
+            fetching: Arc::default(),
+        }
+    }
+
+    /// Sets the name of the app making the searches, which Tenor uses to tell apart integrations that share an API key.
+    pub fn client_key(mut self, client_key: &str) -> Self {
+        self.client_key = Some(client_key.to_owned());
+        self
+    }
+
+    /// Returns the MP4 version of each animation instead of the GIF, which is usually a fraction of the size.
+    /// The MP4 files are kept by `download` even though they aren't images.
+    pub fn mp4(mut self, mp4: bool) -> Self {
+        self.mp4 = mp4;
+        self
+    }
+
+    /// Finds the `pos` of `page`, fetching the pages before it that haven't been fetched yet.
+    async fn position(
+        &self,
+        url: &str,
+        page: usize,
+        request: &PageRequest<'_>,
+    ) -> SearchResult<Option<String>> {
+        let known = |page: usize| {
+            self.positions
+                .lock()
+                .expect("Tenor positions lock poisoned")
+                .get(&(url.to_owned(), page))
+                .cloned()
+        };
+
+        // The closest page whose position is known, with the first page always starting from the beginning
+        let (mut current, mut position) = (1..=page)
+            .rev()
+            .find_map(|page| known(page).map(|position| (page, Some(position))))
+            .unwrap_or((0, None));
+
+        while current < page {
+            let body = request
+                .get(&with_position(url, position.as_deref()))
+                .await?;
+            let next = match next_position(&body)? {
+                Some(next) => next,
+                // There are no more pages
+                None => return Ok(None),
+            };
+            current += 1;
+            self.remember(url, current, next.clone());
+            position = Some(next);
+        }
+
+        Ok(position)
+    }
+
+    /// Stores the `pos` of `page`, forgetting every stored position if there are too many.
+    fn remember(&self, url: &str, page: usize, position: String) {
+        let mut positions = self
+            .positions
+            .lock()
+            .expect("Tenor positions lock poisoned");
+        if positions.len() >= MAX_POSITIONS {
+            positions.clear();
+        }
+        positions.insert((url.to_owned(), page), position);
+    }
+}
+
+impl SearchProvider for Tenor {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let url = build_url(&self.api_key, self.client_key.as_deref(), &request);
+            let _fetching = self.fetching.lock().await;
+            let position = self.position(&url, request.page(), &request).await?;
+            // The results ran out before this page
+            if request.page() > 0 && position.is_none() {
+                return Ok(Vec::new());
+            }
+
+            let body = request
+                .get(&with_position(&url, position.as_deref()))
+                .await?;
+            if let Some(next) = next_position(&body)? {
+                self.remember(&url, request.page() + 1, next);
+            }
+            let (images, stats) = unpack(&body, request.thumbnails_only(), self.mp4)?;
+            request.add_stats(stats);
+
+            Ok(images)
+        })
+    }
+}
+
+/// Builds the url of the API call for the search asked for by `request`, without the position of the page.
+fn build_url(api_key: &str, client_key: Option<&str>, request: &PageRequest) -> String {
+    let mut url =
+        surf::Url::parse("https://tenor.googleapis.com/v2/search").expect("Tenor url is valid");
+    url.query_pairs_mut()
+        .append_pair("key", api_key)
+        .append_pair("q", &request.query())
+        .append_pair("limit", &PAGE_SIZE.to_string())
+        .append_pair(
+            "media_filter",
+            "gif,tinygif,mp4,gif_transparent,tinygif_transparent",
+        );
+
+    if let Some(client_key) = client_key {
+        url.query_pairs_mut().append_pair("client_key", client_key);
+    }
+    // Stickers are animations with transparent backgrounds
+    if let ColorType::Transparent = request.color_type() {
+        url.query_pairs_mut().append_pair("searchfilter", "sticker");
+    }
+    if let Some(safe_search) = request.safe_search() {
+        let content_filter = match safe_search {
+            SafeSearch::Off => "off",
+            SafeSearch::Moderate => "medium",
+            SafeSearch::Strict => "high",
+        };
+        url.query_pairs_mut()
+            .append_pair("contentfilter", content_filter);
+    }
+
+    url.to_string()
+}
+
+/// Adds the position of a page to the url of a search, or leaves it as is for the first page.
+fn with_position(url: &str, position: Option<&str>) -> String {
+    let mut url = surf::Url::parse(url).expect("Tenor url is valid");
+    if let Some(position) = position {
+        url.query_pairs_mut().append_pair("pos", position);
+    }
+
+    url.to_string()
+}
+
+/// Parses the position of the next page from a page of Tenor's results, which is empty when there are no more results.
+fn next_position(body: &str) -> SearchResult<Option<String>> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+
+    Ok(json
+        .get("next")
+        .and_then(|next| next.as_str())
+        .filter(|next| !next.is_empty() && *next != "0")
+        .map(str::to_owned))
+}
+
+/// Parses the images from a page of Tenor's results, along with how many results were skipped and why.
+fn unpack(body: &str, thumbnails_only: bool, mp4: bool) -> SearchResult<(Vec<Image>, ParseStats)> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+    if let Some(error) = json.get("error") {
+        let message = error
+            .get("message")
+            .and_then(|message| message.as_str())
+            .unwrap_or("unknown error");
+        return Err(Error::Provider(format!("Tenor: {}", message)));
+    }
+
+    let results = json
+        .get("results")
+        .and_then(|results| results.as_array())
+        .ok_or_else(|| Error::parse(ParseStage::ImageArray, "`results` is not an array"))?;
+
+    let mut images = Vec::new();
+    let mut stats = ParseStats::default();
+    for result in results {
+        match unpack_result(result, thumbnails_only, mp4) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    Ok((images, stats))
+}
+
+/// Parses a single animation, returning the reason it was skipped if it is missing any of the fields needed.
+fn unpack_result(
+    result: &serde_json::Value,
+    thumbnails_only: bool,
+    mp4: bool,
+) -> Result<Image, SkipReason> {
+    // The url and dimensions of the first of the given formats that Tenor returned
+    let format = |names: &[&str]| {
+        names.iter().find_map(|name| {
+            let format = result.get("media_formats")?.get(name)?;
+            let url = format.get("url")?.as_str().filter(|url| !url.is_empty())?;
+            let width = format.pointer("/dims/0")?.as_i64()?;
+            let height = format.pointer("/dims/1")?.as_i64()?;
+            Some((url.to_owned(), width, height))
+        })
+    };
+
+    // Stickers only have the transparent formats
+    let thumbnail =
+        format(&["tinygif", "tinygif_transparent"]).ok_or(SkipReason::MissingThumbnail)?;
+    let published = result
+        .get("created")
+        .and_then(|created| created.as_f64())
+        .and_then(|created| DateTime::from_timestamp(created as i64, 0));
+
+    if thumbnails_only {
+        let (url, width, height) = thumbnail;
+        return Ok(Image {
+            published,
+            ..Image::new(&url, width, height, &url, "")
+        });
+    }
+
+    let full: &[&str] = if mp4 {
+        &["mp4"]
+    } else {
+        &["gif", "gif_transparent"]
+    };
+    let (url, width, height) = format(full).ok_or(SkipReason::MissingUrl)?;
+    let source = result
+        .get("itemurl")
+        .and_then(|url| url.as_str())
+        .ok_or(SkipReason::MissingSource)?
+        .to_owned();
+
+    Ok(Image {
+        published,
+        ..Image::new(&url, width, height, &thumbnail.0, &source)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A GIF, and a sticker with only transparent formats.
+    const RESULTS: &str = r#"{"next":"CAgQ","results":[
+        {"itemurl":"https://tenor.com/view/cat-1","created":1600000000.123,"media_formats":{"gif":{"url":"https://media.tenor.com/1/cat.gif","dims":[498,280]},"mp4":{"url":"https://media.tenor.com/1/cat.mp4","dims":[640,360]},"tinygif":{"url":"https://media.tenor.com/1/tiny.gif","dims":[220,124]}}},
+        {"itemurl":"https://tenor.com/view/dog-2","media_formats":{"gif_transparent":{"url":"https://media.tenor.com/2/dog.gif","dims":[300,300]},"tinygif_transparent":{"url":"https://media.tenor.com/2/tiny.gif","dims":[100,100]}}}
+    ]}"#;
+
+    #[test]
+    fn unpack_parses_animations_and_stickers() {
+        let (images, stats) = unpack(RESULTS, false, false).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].url, "https://media.tenor.com/1/cat.gif");
+        assert_eq!(images[0].thumbnail, "https://media.tenor.com/1/tiny.gif");
+        assert_eq!(images[0].source, "https://tenor.com/view/cat-1");
+        assert_eq!((images[0].width, images[0].height), (498, 280));
+        assert_eq!(
+            images[0].published.map(|published| published.timestamp()),
+            Some(1_600_000_000)
+        );
+        assert_eq!(images[1].url, "https://media.tenor.com/2/dog.gif");
+        assert_eq!(stats.parsed, 2);
+    }
+
+    #[test]
+    fn unpack_parses_mp4s() {
+        let (images, stats) = unpack(RESULTS, false, true).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!((images[0].width, images[0].height), (640, 360));
+        assert_eq!(stats.skipped.get(&SkipReason::MissingUrl), Some(&1));
+    }
+
+    #[test]
+    fn next_positions() {
+        assert_eq!(next_position(RESULTS).unwrap().as_deref(), Some("CAgQ"));
+        assert_eq!(next_position(r#"{"next":"0"}"#).unwrap(), None);
+        assert_eq!(next_position(r#"{"next":""}"#).unwrap(), None);
+    }
+
+    #[test]
+    fn unpack_reports_errors() {
+        assert!(matches!(
+            unpack(
+                r#"{"error":{"code":400,"message":"API key not valid"}}"#,
+                false,
+                false
+            ),
+            Err(Error::Provider(_))
+        ));
+    }
+}
@@ -0,0 +1,219 @@
+//! Wikimedia Commons, which is searched through the MediaWiki API (`api.php`) instead of being scraped.
+
+use super::{unescape_html, PageRequest, SearchProvider};
+use crate::{Error, Format, Image, ImageType, ParseStage, ParseStats, SearchResult, SkipReason};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+
+/// The number of results requested for each page, which is the most the API returns with file metadata.
+const PAGE_SIZE: usize = 50;
+
+/// The width of the thumbnails the API is asked to generate.
+const THUMBNAIL_WIDTH: usize = 300;
+
+/// Searches Wikimedia Commons, where every file is public domain or freely licensed, through the official MediaWiki API.
+/// The `license` of each `Image` is the short name of the file's license, such as `CC BY-SA 4.0` or `Public domain`,
+/// and its `published` date is when the current version of the file was uploaded.
+///
+/// Only `ImageType::Photo`, `ImageType::Clipart`, `ImageType::Lineart` (both of which find drawings such as SVGs), and a single `Format` are supported, and the other filters are ignored.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Wikimedia;
+
+impl SearchProvider for Wikimedia {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let body = request.get(&build_url(&request)).await?;
+            let (images, stats) = unpack(&body, request.thumbnails_only())?;
+            request.add_stats(stats);
+
+            Ok(images)
+        })
+    }
+}
+
+/// Builds the url of the API call for the page of results asked for by `request`.
+fn build_url(request: &PageRequest) -> String {
+    // Filters are search keywords on Commons, and only files (not audio, video, or documents) are searched for by default
+    let file_type = match request.image_type() {
+        ImageType::Photo => "filetype:bitmap",
+        ImageType::Clipart | ImageType::Lineart => "filetype:drawing",
+        ImageType::None | ImageType::Face | ImageType::Animated => "filetype:bitmap|drawing",
+    };
+    let mime = match request.formats() {
+        [Format::Jpg] => " filemime:image/jpeg",
+        [Format::Png] => " filemime:image/png",
+        [Format::Gif] => " filemime:image/gif",
+        [Format::Svg] => " filemime:image/svg+xml",
+        [Format::Webp] => " filemime:image/webp",
+        [Format::Bmp] => " filemime:image/bmp",
+        _ => "",
+    };
+    let search = format!("{} {}{}", request.query(), file_type, mime);
+
+    let mut url = surf::Url::parse("https://commons.wikimedia.org/w/api.php")
+        .expect("Wikimedia Commons url is valid");
+    url.query_pairs_mut()
+        .append_pair("action", "query")
+        .append_pair("format", "json")
+        .append_pair("formatversion", "2")
+        .append_pair("generator", "search")
+        .append_pair("gsrsearch", &search)
+        // The File: namespace
+        .append_pair("gsrnamespace", "6")
+        .append_pair("gsrlimit", &PAGE_SIZE.to_string())
+        .append_pair("gsroffset", &(request.page() * PAGE_SIZE).to_string())
+        .append_pair("prop", "imageinfo")
+        .append_pair("iiprop", "url|size|timestamp|extmetadata")
+        .append_pair("iiextmetadatafilter", "LicenseShortName")
+        .append_pair("iiurlwidth", &THUMBNAIL_WIDTH.to_string());
+
+    url.to_string()
+}
+
+/// Parses the images from a page of the API's results, along with how many results were skipped and why.
+fn unpack(body: &str, thumbnails_only: bool) -> SearchResult<(Vec<Image>, ParseStats)> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+    if let Some(error) = json.get("error") {
+        let info = error
+            .get("info")
+            .and_then(|info| info.as_str())
+            .unwrap_or("unknown error");
+        return Err(Error::Provider(format!("Wikimedia Commons: {}", info)));
+    }
+    // There is no `query` at all when nothing was found
+    let query = match json.get("query") {
+        Some(query) => query,
+        None => return Ok((Vec::new(), ParseStats::default())),
+    };
+
+    let mut pages: Vec<&serde_json::Value> = query
+        .get("pages")
+        .and_then(|pages| pages.as_array())
+        .ok_or_else(|| Error::parse(ParseStage::ImageArray, "`pages` is not an array"))?
+        .iter()
+        .collect();
+    // Pages are ordered by id, with their rank in the search results given by `index`
+    pages.sort_by_key(|page| page.get("index").and_then(|index| index.as_i64()));
+
+    let mut images = Vec::new();
+    let mut stats = ParseStats::default();
+    for page in pages {
+        match unpack_page(page, thumbnails_only) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    Ok((images, stats))
+}
+
+/// Parses a single file, returning the reason it was skipped if it is missing any of the fields needed.
+fn unpack_page(page: &serde_json::Value, thumbnails_only: bool) -> Result<Image, SkipReason> {
+    let info = page
+        .get("imageinfo")
+        .and_then(|info| info.as_array())
+        .and_then(|info| info.first())
+        .ok_or(SkipReason::MissingUrl)?;
+    let field = |name: &str| info.get(name).and_then(|value| value.as_str());
+    let number = |name: &str| info.get(name).and_then(|value| value.as_i64());
+
+    let thumbnail = field("thumburl")
+        .ok_or(SkipReason::MissingThumbnail)?
+        .to_owned();
+    let published = field("timestamp")
+        .and_then(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok())
+        .map(|timestamp| timestamp.with_timezone(&Utc));
+    // The license can contain HTML entities, such as `CC BY-SA 4.0 &amp; GFDL`
+    let license = info
+        .pointer("/extmetadata/LicenseShortName/value")
+        .and_then(|license| license.as_str())
+        .map(unescape_html);
+
+    if thumbnails_only {
+        let (width, height) = number("thumbwidth")
+            .zip(number("thumbheight"))
+            .ok_or(SkipReason::MissingDimensions)?;
+        return Ok(Image {
+            published,
+            license,
+            ..Image::new(&thumbnail, width, height, &thumbnail, "")
+        });
+    }
+
+    let (width, height) = number("width")
+        .zip(number("height"))
+        .ok_or(SkipReason::MissingDimensions)?;
+
+    Ok(Image {
+        published,
+        license,
+        ..Image::new(
+            field("url").ok_or(SkipReason::MissingUrl)?,
+            width,
+            height,
+            &thumbnail,
+            field("descriptionurl").ok_or(SkipReason::MissingSource)?,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two files out of order, the second of which is missing its dimensions.
+    const RESULTS: &str = r#"{"batchcomplete":true,"query":{"pages":[
+        {"pageid":2,"title":"File:Dog.jpg","index":2,"imageinfo":[{"url":"https://upload.wikimedia.org/dog.jpg","descriptionurl":"https://commons.wikimedia.org/wiki/File:Dog.jpg","thumburl":"https://upload.wikimedia.org/thumb/dog.jpg","thumbwidth":320,"thumbheight":240}]},
+        {"pageid":1,"title":"File:Cat.jpg","index":1,"imageinfo":[{"url":"https://upload.wikimedia.org/cat.jpg","descriptionurl":"https://commons.wikimedia.org/wiki/File:Cat.jpg","thumburl":"https://upload.wikimedia.org/thumb/cat.jpg","thumbwidth":320,"thumbheight":213,"width":3000,"height":2000,"timestamp":"2020-09-13T12:26:40Z","extmetadata":{"LicenseShortName":{"value":"CC BY-SA 4.0 &amp; GFDL"}}}]}
+    ]}}"#;
+
+    #[test]
+    fn unpack_parses_files_in_order() {
+        let (images, stats) = unpack(RESULTS, false).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://upload.wikimedia.org/cat.jpg");
+        assert_eq!(
+            images[0].source,
+            "https://commons.wikimedia.org/wiki/File:Cat.jpg"
+        );
+        assert_eq!((images[0].width, images[0].height), (3000, 2000));
+        assert_eq!(images[0].license.as_deref(), Some("CC BY-SA 4.0 & GFDL"));
+        assert_eq!(
+            images[0].published.map(|published| published.timestamp()),
+            Some(1_600_000_000)
+        );
+        assert_eq!(stats.skipped.get(&SkipReason::MissingDimensions), Some(&1));
+    }
+
+    #[test]
+    fn unpack_parses_thumbnails() {
+        let (images, _) = unpack(RESULTS, true).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].url, "https://upload.wikimedia.org/thumb/cat.jpg");
+        assert_eq!((images[1].width, images[1].height), (320, 240));
+    }
+
+    #[test]
+    fn unpack_without_results() {
+        let (images, _) = unpack(r#"{"batchcomplete":true}"#, false).unwrap();
+        assert!(images.is_empty());
+        assert!(matches!(
+            unpack(
+                r#"{"error":{"code":"badvalue","info":"Unrecognized value"}}"#,
+                false
+            ),
+            Err(Error::Provider(_))
+        ));
+    }
+}
@@ -0,0 +1,292 @@
+//! Yandex Images, which reads the results from the JSON that Yandex loads further pages of results with.
+
+use super::{absolute_url, unescape_html, PageRequest, SearchProvider};
+use crate::{
+    Color, ColorType, Error, Format, Image, ImageType, ParseStage, ParseStats, Ratio, SafeSearch,
+    SearchResult, Size, SkipReason, Time,
+};
+use futures::future::BoxFuture;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// The number of results Yandex returns for each page.
+const PAGE_SIZE: usize = 30;
+
+/// The block of results asked for in each request, which makes Yandex return the results as HTML inside of JSON.
+const RESULTS_BLOCK: &str =
+    r#"{"blocks":[{"block":"serp-list_infinite_yes","params":{},"version":2}]}"#;
+
+/// Searches Yandex Images, which often gives better results than Google for queries in Cyrillic, and is available where Google is blocked.
+///
+/// Filtering by `Arguments::license`, `ImageType::Animated` (use `Format::Gif` instead), `Color::Pink`, `Color::Brown`, `ColorType::Transparent`, and `Ratio::Panoramic` isn't supported.
+/// Yandex only filters by a single format, and can only limit results to the last week, so other times are ignored.
+#[derive(Debug, Clone)]
+pub struct Yandex {
+    domain: String,
+}
+
+impl Default for Yandex {
+    fn default() -> Self {
+        Yandex::new()
+    }
+}
+
+impl Yandex {
+    /// Searches `yandex.com`.
+    pub fn new() -> Yandex {
+        Yandex {
+            domain: "yandex.com".to_owned(),
+        }
+    }
+
+    /// Sets the Yandex domain that searches are made on, such as `yandex.ru` or `yandex.com.tr`, which changes the region results are ranked for.
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = domain.to_owned();
+        self
+    }
+}
+
+impl SearchProvider for Yandex {
+    fn page_size(&self) -> usize {
+        PAGE_SIZE
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        request: PageRequest<'a>,
+    ) -> BoxFuture<'a, SearchResult<Vec<Image>>> {
+        Box::pin(async move {
+            let url = build_url(&self.domain, &request)?;
+            let body = request.get(&url).await?;
+            let (images, stats) = unpack(&body, request.thumbnails_only())?;
+            request.add_stats(stats);
+
+            Ok(images)
+        })
+    }
+}
+
+/// Builds the url of the page of results asked for by `request` on `domain`.
+fn build_url(domain: &str, request: &PageRequest) -> SearchResult<String> {
+    let mut url = surf::Url::parse(&format!("https://{}/images/search", domain))
+        .map_err(|_| Error::InvalidArguments(format!("{} is not a valid Yandex domain", domain)))?;
+    url.query_pairs_mut()
+        .append_pair("format", "json")
+        .append_pair("request", RESULTS_BLOCK)
+        .append_pair("text", &request.query())
+        .append_pair("p", &request.page().to_string());
+
+    let color = match (request.color(), request.color_type()) {
+        (Color::Red, _) => "red",
+        (Color::Orange, _) => "orange",
+        (Color::Yellow, _) => "yellow",
+        (Color::Green, _) => "green",
+        (Color::Teal, _) => "cyan",
+        (Color::Blue, _) => "blue",
+        (Color::Purple, _) => "violet",
+        (Color::White, _) => "white",
+        (Color::Black, _) => "black",
+        // Yandex's gray is a grayscale filter
+        (Color::Gray, _) | (Color::None, ColorType::Grayscale) => "gray",
+        (Color::None, ColorType::Color) => "color",
+        (Color::Pink | Color::Brown, _)
+        | (Color::None, ColorType::None | ColorType::Transparent) => "",
+    };
+    let image_type = match request.image_type() {
+        ImageType::Face => "face",
+        ImageType::Photo => "photo",
+        ImageType::Clipart => "clipart",
+        ImageType::Lineart => "lineart",
+        ImageType::None | ImageType::Animated => "",
+    };
+    let orientation = match request.ratio() {
+        Ratio::Tall => "vertical",
+        Ratio::Square => "square",
+        Ratio::Wide => "horizontal",
+        Ratio::None | Ratio::Panoramic => "",
+    };
+    let recent = match request.time() {
+        Time::Week => "7D",
+        _ => "",
+    };
+    let size = match request.size() {
+        Size::None => "",
+        Size::Large => "large",
+        Size::Medium => "medium",
+        Size::Icon => "small",
+    };
+    // Yandex only has a family filter, and moderate filtering is its default
+    let family = match request.safe_search() {
+        Some(SafeSearch::Strict) => "yes",
+        Some(SafeSearch::Off | SafeSearch::Moderate) | None => "",
+    };
+    let format = match request.formats() {
+        [Format::Jpg] => "jpg",
+        [Format::Png] => "png",
+        [Format::Gif] => "gifan",
+        _ => "",
+    };
+
+    for (name, value) in [
+        ("icolor", color),
+        ("type", image_type),
+        ("iorient", orientation),
+        ("recent", recent),
+        ("itype", format),
+        ("isize", size),
+        ("family", family),
+    ] {
+        if !value.is_empty() {
+            url.query_pairs_mut().append_pair(name, value);
+        }
+    }
+
+    Ok(url.to_string())
+}
+
+/// Matches the `data-bem` attributes that Yandex stores the details of each result in, which are either in double or single quotes.
+fn bem_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r#"data-bem=(?:"([^"]*)"|'([^']*)')"#).unwrap())
+}
+
+/// Parses the images from a page of Yandex's results, along with how many results were skipped and why.
+fn unpack(body: &str, thumbnails_only: bool) -> SearchResult<(Vec<Image>, ParseStats)> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| Error::parse(ParseStage::Json, err.to_string()))?;
+    if json.get("type").and_then(|kind| kind.as_str()) == Some("captcha") {
+        return Err(Error::parse(
+            ParseStage::Marker,
+            "Yandex asked for a captcha",
+        ));
+    }
+
+    let html: String = json
+        .get("blocks")
+        .and_then(|blocks| blocks.as_array())
+        .ok_or_else(|| Error::parse(ParseStage::ImageArray, "`blocks` is not an array"))?
+        .iter()
+        .filter_map(|block| block.get("html").and_then(|html| html.as_str()))
+        .collect();
+
+    let mut images = Vec::new();
+    let mut stats = ParseStats::default();
+    for captures in bem_regex().captures_iter(&html) {
+        let attribute = unescape_html(
+            captures
+                .get(1)
+                .or_else(|| captures.get(2))
+                .unwrap()
+                .as_str(),
+        );
+        let bem: serde_json::Value = match serde_json::from_str(&attribute) {
+            Ok(bem) => bem,
+            Err(_) => continue,
+        };
+        // Other elements have `data-bem` attributes too, which aren't results
+        let item = match bem.get("serp-item") {
+            Some(item) => item,
+            None => continue,
+        };
+
+        match unpack_item(item, thumbnails_only) {
+            Ok(image) => {
+                stats.parsed += 1;
+                images.push(image);
+            }
+            Err(reason) => *stats.skipped.entry(reason).or_default() += 1,
+        }
+    }
+
+    Ok((images, stats))
+}
+
+/// Parses a single result, returning the reason it was skipped if it is missing any of the fields needed.
+fn unpack_item(item: &serde_json::Value, thumbnails_only: bool) -> Result<Image, SkipReason> {
+    let preview = item
+        .get("preview")
+        .and_then(|preview| preview.as_array())
+        .and_then(|preview| preview.first());
+
+    // Thumbnail urls are protocol relative (`//im0-tub-com.yandex.net/...`)
+    let thumbnail = item
+        .pointer("/thumb/url")
+        .and_then(|url| url.as_str())
+        .map(absolute_url)
+        .ok_or(SkipReason::MissingThumbnail)?;
+
+    if thumbnails_only {
+        let width = item.pointer("/thumb/size/width").and_then(|w| w.as_i64());
+        let height = item.pointer("/thumb/size/height").and_then(|h| h.as_i64());
+        let (width, height) = width.zip(height).ok_or(SkipReason::MissingDimensions)?;
+
+        return Ok(Image::new(&thumbnail, width, height, &thumbnail, ""));
+    }
+
+    let url = item
+        .get("img_href")
+        .or_else(|| preview.and_then(|preview| preview.get("url")))
+        .and_then(|url| url.as_str())
+        .ok_or(SkipReason::MissingUrl)?
+        .to_owned();
+    let width = preview
+        .and_then(|preview| preview.get("w"))
+        .and_then(|w| w.as_i64());
+    let height = preview
+        .and_then(|preview| preview.get("h"))
+        .and_then(|h| h.as_i64());
+    let (width, height) = width.zip(height).ok_or(SkipReason::MissingDimensions)?;
+    let source = item
+        .pointer("/snippet/url")
+        .and_then(|url| url.as_str())
+        .ok_or(SkipReason::MissingSource)?
+        .to_owned();
+
+    Ok(Image::new(&url, width, height, &thumbnail, &source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A response with a result, a result without a source, and an element that isn't a result.
+    fn results() -> String {
+        let item = r#"{"serp-item":{"img_href":"https://example.com/cat.jpg","preview":[{"url":"https://example.com/cat.jpg","w":800,"h":600}],"thumb":{"url":"//im0-tub-com.yandex.net/i?id=1","size":{"width":320,"height":240}},"snippet":{"url":"https://example.com/cats"}}}"#;
+        let unsourced = r#"{&quot;serp-item&quot;:{&quot;img_href&quot;:&quot;https://example.com/dog.jpg&quot;,&quot;preview&quot;:[{&quot;w&quot;:1,&quot;h&quot;:1}],&quot;thumb&quot;:{&quot;url&quot;:&quot;//im0-tub-com.yandex.net/i?id=2&quot;}}}"#;
+        let html = format!(
+            r#"<div data-bem='{}'></div><div data-bem="{}"></div><div data-bem='{{"pager":{{}}}}'></div>"#,
+            item, unsourced
+        );
+        serde_json::json!({ "blocks": [{ "html": html }] }).to_string()
+    }
+
+    #[test]
+    fn unpack_parses_results() {
+        let (images, stats) = unpack(&results(), false).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url, "https://example.com/cat.jpg");
+        assert_eq!(images[0].thumbnail, "https://im0-tub-com.yandex.net/i?id=1");
+        assert_eq!(images[0].source, "https://example.com/cats");
+        assert_eq!((images[0].width, images[0].height), (800, 600));
+        assert_eq!(stats.skipped.get(&SkipReason::MissingSource), Some(&1));
+    }
+
+    #[test]
+    fn unpack_parses_thumbnails() {
+        let (images, stats) = unpack(&results(), true).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!((images[0].width, images[0].height), (320, 240));
+        assert_eq!(stats.skipped.get(&SkipReason::MissingDimensions), Some(&1));
+    }
+
+    #[test]
+    fn unpack_detects_captchas() {
+        assert!(matches!(
+            unpack(r#"{"type":"captcha"}"#, false),
+            Err(Error::Parse {
+                stage: ParseStage::Marker,
+                ..
+            })
+        ));
+    }
+}
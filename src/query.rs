@@ -0,0 +1,208 @@
+//! Building queries out of terms, exact phrases, and alternatives, instead of assembling the search syntax by hand.
+
+use std::fmt;
+
+/// A search query made of terms, exact phrases, and `OR` alternatives, which renders to the syntax search engines understand.
+/// Queries are combined from left to right, so `a.or(b).and(c)` finds `(a OR b) c`, and a query with alternatives is grouped in parentheses when it is combined with `and`.
+/// Quotes are stripped from terms and phrases, so they can't break out of a phrase.
+///
+/// ```
+/// use image_search::{Arguments, Query};
+///
+/// // "red panda" OR (firefox animal)
+/// let query = Query::phrase("red panda").or("firefox animal");
+/// let args = Arguments::from_query(&query, 10);
+///
+/// // panda ("red panda" OR giant)
+/// let query = Query::terms("panda").and(Query::phrase("red panda").or("giant"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    alternatives: Vec<Vec<Part>>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Terms(String),
+    Phrase(String),
+    Group(Query),
+}
+
+impl Query {
+    /// A query that finds results containing all of the space separated terms.
+    pub fn terms(terms: &str) -> Query {
+        Query::single(Part::Terms(clean(terms)))
+    }
+
+    /// A query that finds results containing the exact phrase.
+    pub fn phrase(phrase: &str) -> Query {
+        Query::single(Part::Phrase(clean(phrase)))
+    }
+
+    /// Finds results that match both this query and `other`.
+    pub fn and<Q: Into<Query>>(self, other: Q) -> Query {
+        let other = other.into();
+        let mut query = match self.alternatives.len() {
+            0 => return other,
+            1 => self,
+            _ => Query::single(Part::Group(self)),
+        };
+
+        match other.alternatives.len() {
+            0 => {}
+            1 => query.alternatives[0].extend(other.alternatives.into_iter().flatten()),
+            _ => query.alternatives[0].push(Part::Group(other)),
+        }
+        query
+    }
+
+    /// Finds results that match either this query or `other`.
+    pub fn or<Q: Into<Query>>(mut self, other: Q) -> Query {
+        self.alternatives.extend(other.into().alternatives);
+        self
+    }
+
+    /// Whether the query has no terms or phrases.
+    pub fn is_empty(&self) -> bool {
+        self.alternatives.is_empty()
+    }
+
+    fn single(part: Part) -> Query {
+        let empty = match &part {
+            Part::Terms(text) | Part::Phrase(text) => text.is_empty(),
+            Part::Group(query) => query.is_empty(),
+        };
+
+        if empty {
+            return Query::default();
+        }
+        Query {
+            alternatives: vec![vec![part]],
+        }
+    }
+}
+
+impl From<&str> for Query {
+    fn from(terms: &str) -> Query {
+        Query::terms(terms)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Search engines bind `OR` tighter than the spaces between terms, so alternatives with several parts are grouped
+        let grouped = self.alternatives.len() > 1;
+        for (i, parts) in self.alternatives.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" OR ")?;
+            }
+
+            let group = grouped
+                && (parts.len() > 1
+                    || matches!(parts[..], [Part::Terms(ref terms)] if terms.contains(' ')));
+            if group {
+                f.write_str("(")?;
+            }
+            for (j, part) in parts.iter().enumerate() {
+                if j > 0 {
+                    f.write_str(" ")?;
+                }
+                match part {
+                    Part::Terms(terms) => f.write_str(terms)?,
+                    Part::Phrase(phrase) => write!(f, "\"{}\"", phrase)?,
+                    Part::Group(query) => match query.alternatives.len() {
+                        1 => write!(f, "{}", query)?,
+                        _ => write!(f, "({})", query)?,
+                    },
+                }
+            }
+            if group {
+                f.write_str(")")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Removes quotes and control characters, and collapses whitespace.
+fn clean(text: &str) -> String {
+    text.split(|c: char| c.is_whitespace() || c.is_control() || c == '"')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terms_and_phrases() {
+        assert_eq!(Query::terms("red  panda").to_string(), "red panda");
+        assert_eq!(Query::phrase("red panda").to_string(), "\"red panda\"");
+        assert_eq!(
+            Query::terms("panda")
+                .and(Query::phrase("bamboo forest"))
+                .to_string(),
+            "panda \"bamboo forest\""
+        );
+    }
+
+    #[test]
+    fn alternatives() {
+        assert_eq!(
+            Query::phrase("red panda").or("firefox animal").to_string(),
+            "\"red panda\" OR (firefox animal)"
+        );
+        assert_eq!(Query::terms("cat").or("dog").to_string(), "cat OR dog");
+    }
+
+    #[test]
+    fn nesting() {
+        assert_eq!(
+            Query::terms("panda")
+                .and(Query::phrase("red panda").or("giant"))
+                .to_string(),
+            "panda (\"red panda\" OR giant)"
+        );
+        assert_eq!(Query::terms("a").or("b").and("c").to_string(), "(a OR b) c");
+        assert_eq!(
+            Query::terms("a")
+                .or("b")
+                .and(Query::terms("c").or("d"))
+                .to_string(),
+            "(a OR b) (c OR d)"
+        );
+        assert_eq!(
+            Query::terms("a")
+                .and("b")
+                .or(Query::terms("c").and("d"))
+                .to_string(),
+            "(a b) OR (c d)"
+        );
+    }
+
+    #[test]
+    fn empty_parts() {
+        assert!(Query::terms("  ").is_empty());
+        assert!(Query::phrase("\"\"").is_empty());
+        assert_eq!(Query::terms("").and("cat").to_string(), "cat");
+        assert_eq!(Query::terms("cat").and("").to_string(), "cat");
+        assert_eq!(Query::terms("cat").or("").to_string(), "cat");
+        assert_eq!(Query::default().or("cat").to_string(), "cat");
+    }
+
+    #[test]
+    fn embedded_quotes() {
+        assert_eq!(
+            Query::phrase("say \"cheese\" now").to_string(),
+            "\"say cheese now\""
+        );
+        assert_eq!(Query::terms("\"quoted\"").to_string(), "quoted");
+        assert_eq!(
+            Query::phrase("line\nbreak\ttab").to_string(),
+            "\"line break tab\""
+        );
+    }
+}
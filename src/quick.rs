@@ -0,0 +1,34 @@
+//! One-line helpers for the simplest searches, which use the default arguments instead of taking an [`Arguments`].
+//!
+//! ```no_run
+//! extern crate tokio;
+//! extern crate image_search;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), image_search::Error> {
+//!     let urls = image_search::quick::urls("cats", 10).await?;
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{Arguments, ImageList, SearchResult};
+use std::path::PathBuf;
+
+/// Search for `query` and return images up to `limit`.
+/// See [`search`](crate::search) for more information.
+pub async fn search(query: &str, limit: usize) -> SearchResult<ImageList> {
+    crate::search(Arguments::new(query, limit)).await
+}
+
+/// Search for `query` and return the urls of the images, up to `limit`.
+/// See [`urls`](crate::urls) for more information.
+pub async fn urls(query: &str, limit: usize) -> SearchResult<Vec<String>> {
+    crate::urls(Arguments::new(query, limit)).await
+}
+
+/// Search for `query` and download up to `limit` images to the "images" folder.
+/// See [`download`](crate::download) for more information.
+pub async fn download(query: &str, limit: usize) -> SearchResult<Vec<PathBuf>> {
+    crate::download(Arguments::new(query, limit)).await
+}
@@ -0,0 +1,83 @@
+//! Heuristic scoring of results by the reputation of the site they were found on, for `Image::score`.
+
+use crate::Image;
+
+/// The score given to images from sites that aren't in any of the lists.
+const NEUTRAL: f64 = 0.5;
+
+/// Domains of reference sites, archives, and institutions, whose images are usually accurate and well described.
+const TRUSTED: &[(&str, f64)] = &[
+    ("wikipedia.org", 1.0),
+    ("wikimedia.org", 1.0),
+    ("si.edu", 0.95),
+    ("metmuseum.org", 0.95),
+    ("loc.gov", 0.95),
+    ("nasa.gov", 0.95),
+    ("britishmuseum.org", 0.95),
+    ("rijksmuseum.nl", 0.95),
+    ("europeana.eu", 0.9),
+    ("archive.org", 0.9),
+    ("nationalgeographic.com", 0.85),
+    ("flickr.com", 0.75),
+];
+
+/// Domains of stock photo sites, whose previews are almost always watermarked.
+pub(crate) const STOCK_SITES: &[&str] = &[
+    "shutterstock.com",
+    "istockphoto.com",
+    "gettyimages.com",
+    "alamy.com",
+    "dreamstime.com",
+    "123rf.com",
+    "depositphotos.com",
+];
+
+/// The score given to images from stock photo sites.
+const STOCK: f64 = 0.15;
+
+/// Domains of content farms and aggregators, whose images are often watermarked, reposted, or unrelated to the query.
+const UNTRUSTED: &[(&str, f64)] = &[
+    ("vecteezy.com", 0.2),
+    ("freepik.com", 0.2),
+    ("pinterest.com", 0.25),
+    ("pinimg.com", 0.25),
+    ("aliexpress.com", 0.25),
+    ("etsy.com", 0.3),
+    ("amazon.com", 0.3),
+];
+
+/// Top level domains used by institutions, which are trusted when the domain itself isn't listed.
+const INSTITUTIONAL_TLDS: &[(&str, f64)] = &[("edu", 0.8), ("gov", 0.8), ("museum", 0.8)];
+
+/// Scores `image` from 0 to 1 by the reputation of the page it was found on, falling back to the host of the image itself.
+pub(crate) fn score(image: &Image) -> f64 {
+    let host = match host(&image.source).or_else(|| host(&image.url)) {
+        Some(host) => host,
+        None => return NEUTRAL,
+    };
+
+    if STOCK_SITES.iter().any(|domain| on_domain(&host, domain)) {
+        return STOCK;
+    }
+
+    TRUSTED
+        .iter()
+        .chain(UNTRUSTED)
+        .find(|(domain, _)| on_domain(&host, domain))
+        .or_else(|| {
+            let tld = host.rsplit('.').next()?;
+            INSTITUTIONAL_TLDS.iter().find(|(domain, _)| *domain == tld)
+        })
+        .map_or(NEUTRAL, |(_, score)| *score)
+}
+
+/// The lowercase host of `url`, if it has one.
+pub(crate) fn host(url: &str) -> Option<String> {
+    let url = surf::Url::parse(url).ok()?;
+    url.host_str().map(str::to_ascii_lowercase)
+}
+
+/// Whether `host` is `domain` or one of its subdomains.
+pub(crate) fn on_domain(host: &str, domain: &str) -> bool {
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
@@ -0,0 +1,125 @@
+//! Pluggable destinations for the files created by the `download` function, set with `Arguments::storage`.
+
+use crate::Image;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// A destination for downloaded files, allowing them to be stored somewhere other than the local filesystem (such as cloud storage or a database).
+/// Files are written to the filesystem with [`FileSink`] unless another sink is set with `Arguments::storage`.
+///
+/// # Example
+/// ```no_run
+/// extern crate tokio;
+/// extern crate image_search;
+///
+/// use image_search::{Arguments, MemorySink, download_report};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), image_search::Error> {
+///     let sink = MemorySink::new();
+///     let report = download_report(Arguments::new("example", 10).storage(sink.clone())).await?;
+///
+///     for file in report.files {
+///         let bytes = sink.get(&file.path);
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub trait StorageSink: Send + Sync {
+    /// Stores the contents of a downloaded file, returning where it was stored.
+    /// `name` is the path the file would be written to on the filesystem, including its extension.
+    /// Sinks that don't write to the filesystem can use it as a key, or take just its file name.
+    fn store(&self, name: &Path, bytes: &[u8], metadata: &Metadata) -> io::Result<Location>;
+}
+
+/// Information about a downloaded file, passed to `StorageSink::store` along with its contents.
+#[derive(Debug, Clone)]
+pub struct Metadata {
+    /// The url the file was downloaded from.
+    pub url: String,
+    /// The search result the file was downloaded for.
+    pub image: Image,
+    /// The extension of the file, determined from its contents (e.g. `"jpg"`).
+    pub extension: String,
+}
+
+/// Where a `StorageSink` stored a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    /// The file was written to this path on the filesystem.
+    Path(PathBuf),
+    /// The file was stored in memory under this name, such as by [`MemorySink`].
+    Memory(PathBuf),
+    /// The file was stored somewhere else, identified by this url or key.
+    Remote(String),
+}
+
+/// Writes files to the filesystem at the path they are given. This is what the `download` function uses by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileSink;
+
+impl StorageSink for FileSink {
+    fn store(&self, name: &Path, bytes: &[u8], metadata: &Metadata) -> io::Result<Location> {
+        if let Some(parent) = name.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // The file is written under a temporary name and then renamed, so a partially written file is never left at the final path
+        let partial = name.with_extension(format!("{}.part", metadata.extension));
+        let written = File::create(&partial)
+            .and_then(|mut f| f.write_all(bytes))
+            .and_then(|_| std::fs::rename(&partial, name));
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&partial);
+            return Err(e);
+        }
+
+        Ok(Location::Path(name.to_owned()))
+    }
+}
+
+/// Keeps files in memory instead of writing them anywhere, keyed by the path they would have been written to.
+/// Clones share the same files, so a clone can be passed to `Arguments::storage` and the original used to read the files afterwards.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    files: Arc<Mutex<HashMap<PathBuf, Vec<u8>>>>,
+}
+
+impl MemorySink {
+    pub fn new() -> MemorySink {
+        MemorySink::default()
+    }
+
+    /// The contents of the file stored under `name`, if there is one.
+    pub fn get(&self, name: &Path) -> Option<Vec<u8>> {
+        self.lock().get(name).cloned()
+    }
+
+    /// The names of all of the stored files.
+    pub fn names(&self) -> Vec<PathBuf> {
+        self.lock().keys().cloned().collect()
+    }
+
+    /// Removes all of the stored files and returns them.
+    pub fn take(&self) -> HashMap<PathBuf, Vec<u8>> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Vec<u8>>> {
+        // A panic while holding the lock can't leave the map in an invalid state, so the poison is ignored
+        self.files
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl StorageSink for MemorySink {
+    fn store(&self, name: &Path, bytes: &[u8], _metadata: &Metadata) -> io::Result<Location> {
+        self.lock().insert(name.to_owned(), bytes.to_vec());
+        Ok(Location::Memory(name.to_owned()))
+    }
+}
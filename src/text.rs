@@ -0,0 +1,66 @@
+//! Heuristic detection of images that are mostly text (memes, screenshots, and slides), for `Arguments::max_text_density`.
+
+extern crate image;
+
+/// The width and height the image is shrunk to fit before it is checked.
+const SAMPLE_SIZE: u32 = 512;
+
+/// The width and height of the blocks the image is split into.
+const BLOCK_SIZE: u32 = 8;
+
+/// The smallest difference in brightness between the darkest and lightest pixels of a block for it to contain text.
+const MIN_CONTRAST: u8 = 96;
+
+/// The smallest fraction of a block's pixels that have to be close to its darkest or lightest pixel for it to contain text.
+/// Text is drawn in flat colors on a flat background, while photos are mostly made up of gradients.
+const MIN_TWO_TONE: f64 = 0.8;
+
+/// The fraction of the decoded `image`, from 0 to 1, that seems to be covered in text, or `None` if it's too small to check.
+/// The image is split into small blocks, and blocks with sharp, two-tone contrast (like the strokes of letters on a background) are counted as text.
+pub(crate) fn text_density(image: &image::DynamicImage) -> Option<f64> {
+    let gray = image.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE).to_luma8();
+    let (columns, rows) = (gray.width() / BLOCK_SIZE, gray.height() / BLOCK_SIZE);
+    if columns == 0 || rows == 0 {
+        return None;
+    }
+
+    let mut text_blocks = 0;
+    for row in 0..rows {
+        for column in 0..columns {
+            let pixels: Vec<u8> = (0..BLOCK_SIZE * BLOCK_SIZE)
+                .map(|i| {
+                    let x = column * BLOCK_SIZE + i % BLOCK_SIZE;
+                    let y = row * BLOCK_SIZE + i / BLOCK_SIZE;
+                    gray.get_pixel(x, y).0[0]
+                })
+                .collect();
+            if is_text_block(&pixels) {
+                text_blocks += 1;
+            }
+        }
+    }
+
+    Some(text_blocks as f64 / (columns * rows) as f64)
+}
+
+/// Whether the brightnesses of a block's pixels look like text: high contrast, with nearly every pixel close to either the darkest or lightest.
+fn is_text_block(pixels: &[u8]) -> bool {
+    let (darkest, lightest) = pixels
+        .iter()
+        .fold((u8::MAX, u8::MIN), |(min, max), &pixel| {
+            (min.min(pixel), max.max(pixel))
+        });
+    let contrast = lightest - darkest;
+    if contrast < MIN_CONTRAST {
+        return false;
+    }
+
+    // Within a fifth of the contrast of either extreme
+    let margin = contrast / 5;
+    let two_tone = pixels
+        .iter()
+        .filter(|&&pixel| pixel <= darkest + margin || pixel >= lightest - margin)
+        .count();
+
+    two_tone as f64 / pixels.len() as f64 >= MIN_TWO_TONE
+}
@@ -0,0 +1,78 @@
+//! Heuristic detection of stock photo watermarks in downloaded images, for `Arguments::watermarks`.
+
+extern crate image;
+
+use crate::reputation::{host, on_domain, STOCK_SITES};
+
+/// The width and height the image is shrunk to fit before looking for edges.
+const SAMPLE_SIZE: u32 = 256;
+
+/// The smallest difference in brightness between neighbouring pixels that counts as an edge.
+const EDGE_THRESHOLD: i32 = 48;
+
+/// The smallest fraction of a region's pixels that have to be edges for it to be considered text or a logo.
+const MIN_REGION_DENSITY: f64 = 0.12;
+
+/// How many times denser the edges in a region have to be than in the image as a whole for it to stand out as a watermark.
+const MIN_DENSITY_RATIO: f64 = 2.0;
+
+/// Guesses whether the decoded `image`, downloaded from `url`, carries a stock photo watermark.
+/// Images from stock photo sites are always assumed to be watermarked. Otherwise, the corners, center, and bottom edge are checked for
+/// dense clusters of edges that stand out from the rest of the image, which is where the text and logos of watermarks are usually placed.
+pub(crate) fn is_watermarked(image: Option<&image::DynamicImage>, url: &str, source: &str) -> bool {
+    let from_stock_site = [url, source].iter().any(|url| {
+        host(url).is_some_and(|host| STOCK_SITES.iter().any(|domain| on_domain(&host, domain)))
+    });
+    if from_stock_site {
+        return true;
+    }
+
+    let gray = match image {
+        Some(image) => image.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE).to_luma8(),
+        None => return false,
+    };
+    let (width, height) = gray.dimensions();
+    if width < 8 || height < 8 {
+        return false;
+    }
+
+    let is_edge = |x: u32, y: u32| {
+        let pixel = gray.get_pixel(x, y).0[0] as i32;
+        let right = gray.get_pixel(x + 1, y).0[0] as i32;
+        let below = gray.get_pixel(x, y + 1).0[0] as i32;
+        (pixel - right).abs() + (pixel - below).abs() >= EDGE_THRESHOLD
+    };
+    // The fraction of pixels in the region from (x, y) to (x + w, y + h) that are edges, stopping one pixel short of the right and bottom of the image
+    let density = |x: u32, y: u32, w: u32, h: u32| {
+        let (x_end, y_end) = ((x + w).min(width - 1), (y + h).min(height - 1));
+        let total = ((x_end - x) * (y_end - y)) as f64;
+        let edges = (y..y_end)
+            .flat_map(|py| (x..x_end).map(move |px| (px, py)))
+            .filter(|&(px, py)| is_edge(px, py))
+            .count() as f64;
+        if total == 0.0 {
+            0.0
+        } else {
+            edges / total
+        }
+    };
+
+    let overall = density(0, 0, width, height);
+    let (quarter_w, quarter_h) = (width / 4, height / 4);
+    let regions = [
+        // Corners
+        (0, 0, quarter_w, quarter_h),
+        (width - quarter_w, 0, quarter_w, quarter_h),
+        (0, height - quarter_h, quarter_w, quarter_h),
+        (width - quarter_w, height - quarter_h, quarter_w, quarter_h),
+        // A band across the middle
+        (quarter_w, height * 3 / 8, width / 2, height / 4),
+        // A strip along the bottom, where credit lines are placed
+        (0, height - height / 8, width, height / 8),
+    ];
+
+    regions.iter().any(|&(x, y, w, h)| {
+        let region = density(x, y, w, h);
+        region >= MIN_REGION_DENSITY && region >= overall * MIN_DENSITY_RATIO
+    })
+}